@@ -14,6 +14,7 @@ use pyo3::{
     exceptions::{PyTypeError, PyValueError},
     prelude::*,
 };
+use std::collections::HashMap;
 use std::path::Path;
 
 use qoqo::convert_into_circuit;
@@ -78,6 +79,8 @@ impl QasmBackendWrapper {
 
     /// Translates a Circuit to a QASM file.
     ///
+    /// The QASM output is streamed to the file without building the full string in memory.
+    ///
     /// Args:
     ///     circuit: The Circuit that is translated
     ///     folder_name: The name of the folder that is prepended to all filenames.
@@ -107,6 +110,93 @@ impl QasmBackendWrapper {
             .map_err(|x| PyValueError::new_err(format!("Error during QASM translation: {x:?}")))
     }
 
+    /// Returns the number of `gate` definitions the QASM output of a Circuit contains.
+    ///
+    /// Args:
+    ///     circuit: The Circuit that is translated
+    ///
+    /// Returns:
+    ///     int: The number of distinct gate definitions in the QASM output
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not in QASM backend
+    #[pyo3(text_signature = "($self, circuit)")]
+    pub fn definition_count(&self, circuit: &Bound<PyAny>) -> PyResult<usize> {
+        let circuit = convert_into_circuit(circuit).map_err(|x| {
+            PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+        })?;
+        Backend::definition_count(&self.internal, &circuit)
+            .map_err(|x| PyValueError::new_err(format!("Error during QASM translation: {x:?}")))
+    }
+
+    /// Returns the QASM translation of every operation of a Circuit.
+    ///
+    /// Operations that are dropped from the output have an empty translation.
+    ///
+    /// Args:
+    ///     circuit: The Circuit that is translated
+    ///
+    /// Returns:
+    ///     List[Tuple[int, str, str]]: The index, hqslang name and QASM translation of each operation
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not in QASM backend
+    #[pyo3(text_signature = "($self, circuit)")]
+    pub fn operation_qasm_map(
+        &self,
+        circuit: &Bound<PyAny>,
+    ) -> PyResult<Vec<(usize, String, String)>> {
+        let circuit = convert_into_circuit(circuit).map_err(|x| {
+            PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+        })?;
+        Backend::operation_qasm_map(&self.internal, &circuit)
+            .map_err(|x| PyValueError::new_err(format!("Error during QASM translation: {x:?}")))
+    }
+
+    /// Returns how often each QASM gate is used in the translation of a Circuit.
+    ///
+    /// Args:
+    ///     circuit: The Circuit that is translated
+    ///
+    /// Returns:
+    ///     Dict[str, int]: The number of occurrences of each QASM gate name
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not in QASM backend
+    #[pyo3(text_signature = "($self, circuit)")]
+    pub fn gate_statistics(&self, circuit: &Bound<PyAny>) -> PyResult<HashMap<String, usize>> {
+        let circuit = convert_into_circuit(circuit).map_err(|x| {
+            PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+        })?;
+        Backend::gate_statistics(&self.internal, &circuit)
+            .map_err(|x| PyValueError::new_err(format!("Error during QASM translation: {x:?}")))
+    }
+
+    /// Returns a copy of the backend that uses a different QASM version.
+    ///
+    /// The qubit register name and all other settings are kept.
+    ///
+    /// Args:
+    ///     qasm_version (str): The QASM version of the returned backend.
+    ///
+    /// Returns:
+    ///     QasmBackend: The backend with the new QASM version.
+    ///
+    /// Raises:
+    ///     ValueError: The QASM version is not supported
+    #[pyo3(text_signature = "($self, qasm_version)")]
+    pub fn clone_with_version(&self, qasm_version: &str) -> PyResult<Self> {
+        Ok(Self {
+            internal: self
+                .internal
+                .clone_with_version_str(qasm_version)
+                .map_err(|x| PyValueError::new_err(format!("{x}")))?,
+        })
+    }
+
     /// Translates a QASM File to a Circuit.
     ///
     /// Args:
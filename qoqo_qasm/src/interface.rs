@@ -16,7 +16,7 @@ use pyo3::exceptions::PyValueError;
 use pyo3::{exceptions::PyTypeError, prelude::*};
 use qoqo::convert_into_circuit;
 use qoqo::operations::convert_pyany_to_operation;
-use roqoqo_qasm::{call_circuit, call_operation, gate_definition, QasmVersion};
+use roqoqo_qasm::{call_circuit, call_operation, gate_definition, operation_to_qasm, QasmVersion};
 
 /// Translate the qoqo circuit into QASM ouput
 ///
@@ -84,12 +84,20 @@ pub fn qasm_call_operation(
 ///
 /// Args:
 ///     operation: The qoqo Operation to be defined
+///     qasm_version (str): The QASM version of the definition
 ///
 /// Returns:
 ///     str: The gate QASM gate definition.
 ///
 /// Raises:
+///     TypeError: Operation conversion error
 ///     ValueError: Operation-specific error or Operation not in QASM backend
+///
+/// Example:
+///     >>> from qoqo import operations as ops
+///     >>> from qoqo_qasm import qasm_gate_definition
+///     >>> qasm_gate_definition(ops.PauliX(0), "2.0")
+///     'gate x a { u3(pi,0,pi) a; }'
 #[pyfunction]
 pub fn qasm_gate_definition(operation: &Bound<PyAny>, qasm_version: &str) -> PyResult<String> {
     let operation = convert_pyany_to_operation(operation).map_err(|x| {
@@ -100,3 +108,37 @@ pub fn qasm_gate_definition(operation: &Bound<PyAny>, qasm_version: &str) -> PyR
     gate_definition(&operation, qasm_version)
         .map_err(|x| PyValueError::new_err(format!("Error during QASM gate definition: {x:?}")))
 }
+
+/// Translate a qoqo operation to its QASM gate definition and its QASM call
+///
+/// Args:
+///     operation: The qoqo operation that is translated
+///     qubit_register_name (str): The name of the quantum register
+///     qasm_version (str): The QASM version of the translation
+///
+/// Returns:
+///     Tuple[Optional[str], str]: The gate definition, None when no definition is needed, and the translated operation
+///
+/// Raises:
+///     TypeError: Operation conversion error
+///     ValueError: Operation not in QASM backend
+///
+/// Example:
+///     >>> from qoqo import operations as ops
+///     >>> from qoqo_qasm import qasm_operation_full
+///     >>> qasm_operation_full(ops.PauliX(0), "q", "2.0")
+///     ('gate x a { u3(pi,0,pi) a; }', 'x q[0];')
+#[pyfunction]
+pub fn qasm_operation_full(
+    operation: &Bound<PyAny>,
+    qubit_register_name: &str,
+    qasm_version: &str,
+) -> PyResult<(Option<String>, String)> {
+    let operation = convert_pyany_to_operation(operation).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Operation: {x:?}"))
+    })?;
+    let qasm_version =
+        QasmVersion::from_str(qasm_version).map_err(|x| PyValueError::new_err(format!("{x}")))?;
+    operation_to_qasm(&operation, qubit_register_name, qasm_version)
+        .map_err(|x| PyValueError::new_err(format!("Error during QASM translation: {x:?}")))
+}
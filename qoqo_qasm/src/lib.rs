@@ -35,11 +35,17 @@ pub use interface::*;
 mod parser;
 pub use parser::*;
 
+/// QASM interface for qoqo.
+///
+/// Translates qoqo operations and circuits to QASM operations via the interface,
+/// and creates a QASM file with the QasmBackend.
 #[pymodule]
-fn qoqo_qasm(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
+pub fn qoqo_qasm(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
     module.add_class::<QasmBackendWrapper>()?;
     module.add_function(wrap_pyfunction!(qasm_call_circuit, module)?)?;
     module.add_function(wrap_pyfunction!(qasm_call_operation, module)?)?;
+    module.add_function(wrap_pyfunction!(qasm_gate_definition, module)?)?;
+    module.add_function(wrap_pyfunction!(qasm_operation_full, module)?)?;
     module.add_function(wrap_pyfunction!(qasm_file_to_circuit, module)?)?;
     module.add_function(wrap_pyfunction!(qasm_str_to_circuit, module)?)?;
     Ok(())
@@ -18,6 +18,7 @@ use pyo3::prelude::*;
 use qoqo::QoqoBackendError;
 use roqoqo::RoqoqoBackendError;
 
+use std::collections::HashMap;
 use std::env::temp_dir;
 use std::fs;
 use std::path::Path;
@@ -125,31 +126,162 @@ fn test_circuit_to_qasm_file(qasm_version: &str, qubits: &str, bits: &str) {
     })
 }
 
+/// Test circuit_to_qasm_file on a large Circuit, which is streamed to the file
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_circuit_to_qasm_file_large(qasm_version: &str) {
+    let number_gates = 10_000;
+    let filename = format!("fnametest_large_{}", qasm_version.replace('.', "_"));
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, false);
+    for i in 0..number_gates {
+        circuit += CNOT::new(i % 2, (i + 1) % 2);
+    }
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 20, None);
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let backendpy = new_qasmbackend(py, None, Some(qasm_version.to_string()));
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        backendpy
+            .call_method1(
+                "circuit_to_qasm_file",
+                (
+                    circuitpy.clone(),
+                    temp_dir().to_str().unwrap(),
+                    filename.clone(),
+                    true,
+                ),
+            )
+            .unwrap();
+
+        let read_in_path = temp_dir().join(Path::new(&format!("{filename}.qasm")));
+        let extracted = fs::read_to_string(&read_in_path).unwrap();
+        fs::remove_file(&read_in_path).unwrap();
+
+        let expected: String = backendpy
+            .call_method1("circuit_to_qasm_str", (circuitpy,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(extracted, expected);
+        assert_eq!(
+            extracted.lines().filter(|l| l.starts_with("cx q")).count(),
+            number_gates
+        );
+    })
+}
+
+/// Test definition_count on a Circuit with several distinct gates
+#[test]
+fn test_definition_count() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += PauliX::new(1);
+    circuit += SGate::new(0);
+    circuit += PauliX::new(0);
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let backendpy = new_qasmbackend(py, None, Some("3.0".to_string()));
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let count: usize = backendpy
+            .call_method1("definition_count", (circuitpy,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(count, 10);
+    })
+}
+
+/// Test gate_statistics counts the QASM gates of a Circuit
+#[test]
+fn test_gate_statistics() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += Hadamard::new(1);
+    circuit += CNOT::new(1, 0);
+    circuit += Hadamard::new(0);
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let backendpy = new_qasmbackend(py, None, None);
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let statistics: HashMap<String, usize> = backendpy
+            .call_method1("gate_statistics", (circuitpy,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(statistics.len(), 2);
+        assert_eq!(statistics["h"], 3);
+        assert_eq!(statistics["cx"], 2);
+    })
+}
+
+/// Test operation_qasm_map returns one tuple per operation
+#[test]
+fn test_operation_qasm_map() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += PragmaSetNumberOfMeasurements::new(10, "ro".to_string());
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let backendpy = new_qasmbackend(py, None, None);
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let map: Vec<(usize, String, String)> = backendpy
+            .call_method1("operation_qasm_map", (circuitpy,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(
+            map,
+            vec![
+                (0, "Hadamard".to_string(), "h q[0];".to_string()),
+                (
+                    1,
+                    "PragmaSetNumberOfMeasurements".to_string(),
+                    "".to_string()
+                ),
+            ]
+        );
+    })
+}
+
+/// Test clone_with_version keeps the register name and only changes the version
+#[test]
+fn test_clone_with_version() {
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let backendpy = new_qasmbackend(py, Some("qr".to_string()), Some("2.0".to_string()));
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let cloned = backendpy
+            .call_method1("clone_with_version", ("3.0Braket",))
+            .unwrap();
+        let qasm: String = cloned
+            .call_method1("circuit_to_qasm_str", (circuitpy,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(qasm, "OPENQASM 3.0;\n\n\nqubit[1] qr;\n\nx qr[0];\n");
+
+        let error = backendpy.call_method1("clone_with_version", ("4.0",));
+        assert!(error.is_err());
+    })
+}
+
 /// Test circuit_to_qasm_str and circuit_to_qasm_file errors
-#[test_case(Operation::from(Bogoliubov::new(
-    0,
-    1,
-    CalculatorFloat::from(0.2),
-    CalculatorFloat::from(0.3)
-)), "2.0"; "bog, 2.0")]
-#[test_case(Operation::from(Bogoliubov::new(
-    0,
-    1,
-    CalculatorFloat::from(0.2),
-    CalculatorFloat::from(0.3)
-)), "3.0"; "bog, 3.0")]
-#[test_case(Operation::from(ComplexPMInteraction::new(
-    0,
-    1,
-    CalculatorFloat::from(0.3),
-    CalculatorFloat::from(0.2)
-)), "2.0"; "complexpm, 2.0")]
-#[test_case(Operation::from(ComplexPMInteraction::new(
-    0,
-    1,
-    CalculatorFloat::from(0.3),
-    CalculatorFloat::from(0.2)
-)), "3.0"; "complexpm, 3.0")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)), "2.0"; "cswap, 2.0")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)), "3.0"; "cswap, 3.0")]
 #[test_case(Operation::from(GPi::new(
     0,
     CalculatorFloat::from(0.3),
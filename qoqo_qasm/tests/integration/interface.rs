@@ -16,6 +16,7 @@ use pyo3::exceptions::PyTypeError;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
 
 use qoqo::operations::convert_operation_to_pyobject;
 use qoqo::CircuitWrapper;
@@ -23,6 +24,7 @@ use qoqo::QoqoBackendError;
 use qoqo::QoqoError;
 
 use qoqo_qasm::qasm_gate_definition;
+use qoqo_qasm::qasm_operation_full;
 use qoqo_qasm::{qasm_call_circuit, qasm_call_operation};
 
 use ndarray::array;
@@ -86,7 +88,6 @@ fn test_qasm_call_circuit(qasm_version: &str, _qubits: &str, bits: &str) {
 #[test_case(Operation::from(RotateY::new(0, CalculatorFloat::from(-PI))), "ry(-3.141592653589793e0) q[0];"; "RotateY")]
 #[test_case(Operation::from(RotateZ::new(0, CalculatorFloat::from(-PI))), "rz(-3.141592653589793e0) q[0];"; "RotateZ")]
 #[test_case(Operation::from(SqrtPauliX::new(0)), "sx q[0];"; "SqrtPauliX")]
-#[test_case(Operation::from(InvSqrtPauliX::new(0)), "sxdg q[0];"; "InvSqrtPauliX")]
 #[test_case(Operation::from(Identity::new(0)), "id q[0];"; "Identity")]
 #[test_case(Operation::from(CNOT::new(0, 1)), "cx q[0],q[1];"; "CNOT")]
 #[test_case(Operation::from(ControlledPauliY::new(0, 1)), "cy q[0],q[1];"; "ControlledPauliY")]
@@ -129,6 +130,7 @@ fn test_qasm_call_operation_identical_2_3(operation: Operation, converted: &str)
 }
 
 #[test_case(Operation::from(DefinitionFloat::new("ro".to_string(), 1, true)), "creg ro[1];", "output float[1] ro;"; "DefinitionFloat output")]
+#[test_case(Operation::from(InvSqrtPauliX::new(0)), "sxdg q[0];", "inv @ sx q[0];"; "InvSqrtPauliX")]
 #[test_case(Operation::from(DefinitionFloat::new("ro".to_string(), 1, false)), "creg ro[1];", "float[1] ro;"; "DefinitionFloat")]
 #[test_case(Operation::from(DefinitionUsize::new("ro".to_string(), 1, true)), "creg ro[1];", "output uint[1] ro;"; "DefinitionUsize ouput")]
 #[test_case(Operation::from(DefinitionUsize::new("ro".to_string(), 1, false)), "creg ro[1];", "uint[1] ro;"; "DefinitionUsize")]
@@ -156,11 +158,12 @@ fn test_qasm_call_operation_different_2_3(
     })
 }
 
-#[test_case(Operation::from(PragmaGlobalPhase::new(CalculatorFloat::from(1.0))), "", "gphase 1e0;"; "PragmaGlobalPhase")]
+#[test_case(Operation::from(PragmaGlobalPhase::new(CalculatorFloat::from(1.0))), "", "gphase 1e0;", "gphase(1e0);"; "PragmaGlobalPhase")]
 fn test_qasm_call_operation_different_braket(
     operation: Operation,
     converted_2: &str,
     converted_3: &str,
+    converted_3_braket: &str,
 ) {
     pyo3::prepare_freethreaded_python();
     Python::with_gil(|py| {
@@ -171,7 +174,7 @@ fn test_qasm_call_operation_different_braket(
         );
         assert_eq!(
             qasm_call_operation(new_op.bind(py), "q", "3.0Braket").unwrap(),
-            converted_2.to_string()
+            converted_3_braket.to_string()
         );
         assert_eq!(
             qasm_call_operation(new_op.bind(py), "q", "3.0Vanilla").unwrap(),
@@ -205,7 +208,6 @@ fn test_qasm_call_operation_error_2_3(operation: Operation, converted_3: &str) {
 #[test_case(Operation::from(PragmaGetOccupationProbability::new("test".into(), None)), "", "pragma roqoqo PragmaGetOccupationProbability test ;"; "PragmaGetOccupationProbability")]
 #[test_case(Operation::from(PragmaGetPauliProduct::new(HashMap::new(), "test".into(), Circuit::new())), "", "pragma roqoqo PragmaGetPauliProduct {} test ;"; "PragmaGetPauliProduct")]
 #[test_case(Operation::from(PragmaGetStateVector::new("test".into(), None)), "", "pragma roqoqo PragmaGetStateVector test ;"; "PragmaGetStateVector")]
-#[test_case(Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 1, None)), "measure q -> ro;", "measure q -> ro;\npragma roqoqo PragmaSetNumberOfMeasurements 1 ro;"; "PragmaRepeatedMeasurement")]
 fn test_call_operation_different_2_roqoqo_3(
     operation: Operation,
     converted_2: &str,
@@ -233,7 +235,24 @@ fn test_call_operation_different_2_roqoqo_3(
     })
 }
 
-#[test_case(Operation::from(PragmaLoop::new(2.0.into(), Circuit::new() + PauliX::new(0))), "pragma roqoqo PragmaLoop 2e0 PauliX(PauliX { qubit: 0 })\n;", "for uint i in [0:2] {\n    x q[0];\n}", "x q[0];\nx q[0];\n", "x q[0];\nx q[0];\n"; "PragmaLoop")]
+#[test_case("2.0", "measure q -> ro;"; "2.0")]
+#[test_case("3.0", "measure q -> ro;"; "3.0")]
+#[test_case("3.0Roqoqo", "measure q -> ro;\npragma roqoqo PragmaSetNumberOfMeasurements 1 ro;"; "3.0Roqoqo")]
+#[test_case("3.0Braket", "measure q -> ro;\n#pragma braket result probability"; "3.0Braket")]
+fn test_pragma_repeated_measurement_result(qasm_version: &str, converted: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let operation = Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 1, None));
+        let new_op: Py<PyAny> = convert_operation_to_pyobject(operation).unwrap();
+        assert_eq!(
+            qasm_call_operation(new_op.bind(py), "q", qasm_version).unwrap(),
+            converted.to_string()
+        );
+    })
+}
+
+#[test_case(Operation::from(PragmaLoop::new(2.0.into(), Circuit::new() + PauliX::new(0))), "pragma roqoqo PragmaLoop 2e0 PauliX(PauliX { qubit: 0 })\n;", "for uint i in [0:1] {\n    x q[0];\n}", "x q[0];\nx q[0];\n", "x q[0];\nx q[0];\n"; "PragmaLoop")]
+#[test_case(Operation::from(ComplexPMInteraction::new(0, 1, 0.3.into(), 0.4.into())), "cpmint(3e-1,4e-1) q[0],q[1];", "cpmint(5e-1,9.272952180016123e-1) q[0],q[1];", "rz(9.272952180016123e-1) q[1];\nrx(pi/2) q[0];\ncnot q[0],q[1];\nrx(5e-1) q[0];\nry(5e-1) q[1];\ncnot q[0],q[1];\nrx(-pi/2) q[0];\nrz(-9.272952180016123e-1) q[1];", "cpmint(5e-1,9.272952180016123e-1) q[0],q[1];"; "ComplexPMInteraction")]
 #[test_case(Operation::from(PragmaSleep::new(vec![0,1], CalculatorFloat::from(0.3))), "pragma roqoqo PragmaSleep [0, 1] 3e-1;", "", "", "pragmasleep(3e-1) q[0];\npragmasleep(3e-1) q[1];"; "PragmaSleep")]
 fn test_call_operation_error_different_all(
     operation: Operation,
@@ -291,30 +310,8 @@ fn test_call_operation_error_2_roqoqo_3(operation: Operation, converted_3: &str)
 }
 
 /// Test qasm_call_operation, qasm_call_circuit and qasm_gate_definition errors
-#[test_case(Operation::from(Bogoliubov::new(
-    0,
-    1,
-    CalculatorFloat::from(0.2),
-    CalculatorFloat::from(0.3)
-)), "2.0"; "bog, 2.0")]
-#[test_case(Operation::from(Bogoliubov::new(
-    0,
-    1,
-    CalculatorFloat::from(0.2),
-    CalculatorFloat::from(0.3)
-)), "3.0"; "bog, 3.0")]
-#[test_case(Operation::from(ComplexPMInteraction::new(
-    0,
-    1,
-    CalculatorFloat::from(0.3),
-    CalculatorFloat::from(0.2)
-)), "2.0"; "complexpm, 2.0")]
-#[test_case(Operation::from(ComplexPMInteraction::new(
-    0,
-    1,
-    CalculatorFloat::from(0.3),
-    CalculatorFloat::from(0.2)
-)), "3.0"; "complexpm, 3.0")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)), "2.0"; "cswap, 2.0")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)), "3.0"; "cswap, 3.0")]
 #[test_case(Operation::from(GPi::new(
     0,
     CalculatorFloat::from(0.3),
@@ -432,3 +429,57 @@ fn test_qasm_gate_definition(operation: Operation, converted: &str) {
         )
     })
 }
+
+/// Test qasm_operation_full call
+#[test_case(Operation::from(Hadamard::new(0)), Some("gate h a { u2(0,pi) a; }"), "h q[0];"; "Hadamard")]
+#[test_case(Operation::from(PragmaSetNumberOfMeasurements::new(20, "ro".to_string())), None, ""; "PragmaSetNumberOfMeasurements")]
+fn test_qasm_operation_full(operation: Operation, definition: Option<&str>, converted: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let new_op: Py<PyAny> = convert_operation_to_pyobject(operation).unwrap();
+        assert_eq!(
+            qasm_operation_full(new_op.bind(py), "q", "2.0").unwrap(),
+            (definition.map(|x| x.to_string()), converted.to_string())
+        )
+    })
+}
+
+/// Test qasm_gate_definition is exposed in the qoqo_qasm python module
+#[test]
+fn test_qasm_gate_definition_module() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = wrap_pymodule!(qoqo_qasm::qoqo_qasm)(py);
+        let new_op: Py<PyAny> =
+            convert_operation_to_pyobject(Operation::from(PauliX::new(0))).unwrap();
+        let definition: String = module
+            .bind(py)
+            .call_method1("qasm_gate_definition", (new_op, "2.0"))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(definition, "gate x a { u3(pi,0,pi) a; }");
+    })
+}
+
+/// Test the accepted forms of the QASM version string
+#[test]
+fn test_qasm_version_string() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let new_op: Py<PyAny> =
+            convert_operation_to_pyobject(Operation::from(PauliX::new(0))).unwrap();
+        assert_eq!(
+            qasm_call_operation(new_op.bind(py), "q", "3").unwrap(),
+            qasm_call_operation(new_op.bind(py), "q", "3.0Vanilla").unwrap()
+        );
+
+        let error = qasm_call_operation(new_op.bind(py), "q", "3.1")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("neither 2.0 nor 3.0: 3.1"));
+        assert!(error.contains(
+            "Valid versions are 2.0, 2.0Vanilla, 2.0Qulacs, 3, 3.0, 3.0Vanilla, 3.0Roqoqo, 3.0Braket, 3.0Qiskit and 3.0IonQ"
+        ));
+    })
+}
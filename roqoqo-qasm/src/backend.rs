@@ -11,29 +11,151 @@
 // limitations under the License.
 
 use crate::{
-    call_operation, gate_definition, VariableGatherer, ALLOWED_OPERATIONS,
+    call_operation_with_options, call_operation_with_register_mapping, ctrl_modifier_gate,
+    gate_definition_with_options, inlined_basis_circuit, inv_modifier_gate, is_native_gate,
+    QasmConditionalValue, QasmOptions, VariableGatherer, VariableKind, ALLOWED_OPERATIONS,
     NO_DEFINITION_REQUIRED_OPERATIONS,
 };
 use qoqo_calculator::CalculatorFloat;
 use roqoqo::operations::*;
+use roqoqo::{measurements::Measure, QuantumProgram};
 use roqoqo::{Circuit, RoqoqoBackendError};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+// Byte order mark written at the start of the QASM files when it is enabled.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// Comment emitted after the last line when the end marker is enabled.
+const END_MARKER: &str = "// end of circuit\n";
+
+// Estimated length of a gate name of up to three characters followed by a space, the
+// terminating semicolon and the newline, as in `cx q[0],q[1];\n`.
+const ESTIMATED_GATE_NAME_LENGTH: usize = 6;
+
+// Length of the brackets and of the separating comma around each qubit index of `q[i],`.
+const QUBIT_ARGUMENT_OVERHEAD: usize = 3;
+
+// Estimated length of a floating point parameter in parentheses, such as `(1.5707963e0)`.
+const ESTIMATED_PARAMETER_LENGTH: usize = 12;
+
+// Leading keywords of the QASM statements that apply no gate, skipped by the gate statistics.
+const NON_GATE_KEYWORDS: &[&str] = &[
+    "qreg", "creg", "qubit", "bit", "input", "output", "let", "gate", "opaque", "if", "else",
+    "for", "while", "box", "barrier", "#pragma", "}",
+];
+
+/// Returns the name of the gate applied by a line of QASM, `None` for lines applying no gate.
+///
+/// The condition of a single line conditional and the `ctrl @` and `inv @` modifiers are not part
+/// of the gate name, measurements assigned to a bit are named `measure`.
+fn statement_gate_name(line: &str) -> Option<&str> {
+    let mut statement = line.trim();
+    if statement.starts_with("//") {
+        return None;
+    }
+    // The conditioned statement of a single line conditional follows the condition
+    if statement.starts_with("if") && !statement.ends_with('{') {
+        statement = statement.split_once(')')?.1.trim_start();
+    }
+    if let Some((_, gate)) = statement.rsplit_once('@') {
+        statement = gate.trim_start();
+    }
+    if statement.contains("= measure ") {
+        return Some("measure");
+    }
+    let name = statement
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ';')
+        .next()?;
+    (!name.is_empty() && !NON_GATE_KEYWORDS.contains(&name)).then_some(name)
+}
+
+/// Returns the key under which the gate definition of an operation is tracked.
+///
+/// MultiQubitZZ and MultiQubitMS are defined separately for each number of qubits in the OpenQASM 3.0
+/// dialects emitting `rzz_multi_n` and `rxx_multi_n`. Controlled gates emitted with the
+/// `ctrl @` modifier and inverse gates emitted with the `inv @` modifier share the definition of
+/// the gate they modify.
+fn definition_key(
+    operation: &Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> String {
+    if let Some(gate) = ctrl_modifier_gate(operation, qasm_version, options)
+        .or_else(|| inv_modifier_gate(operation, qasm_version))
+    {
+        return gate.hqslang().to_string();
+    }
+    // Only the OpenQASM 3.0 dialects defining rzz_multi_n and rxx_multi_n define a gate per size
+    let defined_per_size = matches!(
+        qasm_version,
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+            | QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+            | QasmVersion::V3point0(Qasm3Dialect::Qiskit)
+    );
+    match operation {
+        Operation::MultiQubitZZ(op) if defined_per_size => {
+            format!("{}{}", op.hqslang(), op.qubits().len())
+        }
+        Operation::MultiQubitMS(op) if defined_per_size => {
+            format!("{}{}", op.hqslang(), op.qubits().len())
+        }
+        _ => operation.hqslang().to_string(),
+    }
+}
+
+/// Returns the inner circuit of a control-flow pragma or the inlined basis circuit of a measurement pragma.
+fn inner_circuit<'a>(
+    operation: &'a Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> Option<&'a Circuit> {
+    match operation {
+        Operation::PragmaConditional(op) => Some(op.circuit()),
+        Operation::PragmaLoop(op) => Some(op.circuit()),
+        Operation::PragmaControlledCircuit(op) => Some(op.circuit()),
+        _ => inlined_basis_circuit(operation, qasm_version, options),
+    }
+}
+
 /// Checks for new declarations in the circuit.
+///
+/// The inner circuits of control-flow pragmas and the inlined basis circuits of measurement
+/// pragmas are checked as well, at any nesting depth.
 fn process_operation_circuit<'a>(
     circuit: impl Iterator<Item = &'a Operation>,
     qasm_version: QasmVersion,
+    options: &QasmOptions,
     already_seen_declarations: &mut Vec<String>,
     declarations: &mut String,
+    gate_definitions: &mut Vec<String>,
 ) -> Result<(), RoqoqoBackendError> {
     for operation in circuit {
-        if !already_seen_declarations.contains(&operation.hqslang().to_string()) {
-            already_seen_declarations.push(operation.hqslang().to_string());
-            declarations.push_str(&gate_definition(operation, qasm_version)?);
+        if let Some(inner_circuit) = inner_circuit(operation, qasm_version, options) {
+            process_operation_circuit(
+                inner_circuit.iter(),
+                qasm_version,
+                options,
+                already_seen_declarations,
+                declarations,
+                gate_definitions,
+            )?;
+            continue;
+        }
+        let key = definition_key(operation, qasm_version, options);
+        if !already_seen_declarations.contains(&key) {
+            already_seen_declarations.push(key);
+            if is_native_gate(operation, qasm_version, options) {
+                continue;
+            }
+            let definition = gate_definition_with_options(operation, qasm_version, options)?;
+            push_gate_definition(gate_definitions, &definition);
+            declarations.push_str(&definition);
             if !declarations.is_empty() {
                 declarations.push('\n');
             }
@@ -42,6 +164,359 @@ fn process_operation_circuit<'a>(
     Ok(())
 }
 
+/// Replaces every run of blank lines in the QASM preamble with the given number of blank lines.
+///
+/// A trailing blank line, separating the preamble from the circuit data, is replaced as well.
+fn normalize_blank_lines(preamble: &str, blank_lines: usize) -> String {
+    let mut normalized = String::with_capacity(preamble.len());
+    let mut pending_separator = false;
+    for line in preamble.lines() {
+        if line.is_empty() {
+            pending_separator = true;
+            continue;
+        }
+        if pending_separator && !normalized.is_empty() {
+            normalized.push_str(&"\n".repeat(blank_lines));
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+        pending_separator = false;
+    }
+    if pending_separator {
+        normalized.push_str(&"\n".repeat(blank_lines));
+    }
+    normalized
+}
+
+/// Adds a non-empty gate definition to the list of gate definitions if it is not already listed.
+fn push_gate_definition(gate_definitions: &mut Vec<String>, definition: &str) {
+    if !definition.is_empty() && !gate_definitions.iter().any(|seen| seen == definition) {
+        gate_definitions.push(definition.to_owned());
+    }
+}
+
+/// Replaces runs of single qubit resets covering the whole qubit register with a register reset.
+///
+/// Only a run of exactly one `reset` per qubit of the register is replaced by `reset q;`. The
+/// circuit data is pushed in chunks, so that the same collapsing is applied to the string output
+/// and to the streamed output.
+struct RegisterResetCollapser {
+    prefix: String,
+    qubit_register_name: String,
+    register_size: usize,
+    run: Vec<String>,
+    partial_line: String,
+}
+
+impl RegisterResetCollapser {
+    fn new(qubit_register_name: &str, register_size: usize) -> Self {
+        RegisterResetCollapser {
+            prefix: format!("reset {}[", qubit_register_name),
+            qubit_register_name: qubit_register_name.to_owned(),
+            register_size,
+            run: Vec::new(),
+            partial_line: String::new(),
+        }
+    }
+
+    fn reset_index(&self, line: &str) -> Option<usize> {
+        line.trim_end()
+            .strip_prefix(self.prefix.as_str())?
+            .strip_suffix("];")?
+            .parse::<usize>()
+            .ok()
+    }
+
+    fn flush_run(&mut self, collapsed: &mut String) {
+        let mut indices: Vec<usize> = self
+            .run
+            .iter()
+            .filter_map(|line| self.reset_index(line))
+            .collect();
+        indices.sort_unstable();
+        if self.register_size > 0 && indices.iter().copied().eq(0..self.register_size) {
+            collapsed.push_str(&format!("reset {};\n", self.qubit_register_name));
+        } else {
+            self.run.iter().for_each(|line| collapsed.push_str(line));
+        }
+        self.run.clear();
+    }
+
+    /// Pushes a chunk of circuit data and returns the data that can be output.
+    ///
+    /// Complete reset lines are held back until the end of their run is known.
+    fn push(&mut self, data: &str) -> String {
+        self.partial_line.push_str(data);
+        let complete_length = match self.partial_line.rfind('\n') {
+            Some(index) => index + 1,
+            None => return String::new(),
+        };
+        let remainder = self.partial_line.split_off(complete_length);
+        let complete = std::mem::replace(&mut self.partial_line, remainder);
+        let mut collapsed = String::with_capacity(complete.len());
+        for line in complete.split_inclusive('\n') {
+            if self.reset_index(line).is_some() {
+                self.run.push(line.to_owned());
+            } else {
+                self.flush_run(&mut collapsed);
+                collapsed.push_str(line);
+            }
+        }
+        collapsed
+    }
+
+    /// Returns the remaining circuit data once all chunks were pushed.
+    fn finish(mut self) -> String {
+        let mut collapsed = String::new();
+        self.flush_run(&mut collapsed);
+        collapsed.push_str(&self.partial_line);
+        collapsed
+    }
+}
+
+/// Replaces runs of single qubit resets covering the whole qubit register in the circuit data.
+fn collapse_register_resets(data: &str, qubit_register_name: &str, register_size: usize) -> String {
+    let mut collapser = RegisterResetCollapser::new(qubit_register_name, register_size);
+    let mut collapsed = collapser.push(data);
+    collapsed.push_str(&collapser.finish());
+    collapsed
+}
+
+/// How much of a circuit is translated by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CircuitTranslation {
+    /// The preamble and the circuit data are translated.
+    Full,
+    /// The operations are translated to collect the input parameters, only the preamble is kept.
+    Preamble,
+    /// Only the gate definitions and register declarations of the preamble are translated, the
+    /// operations are not translated and the input parameters are not declared.
+    Definitions,
+}
+
+/// Returns the estimated length of the QASM instruction of an operation, including its newline.
+///
+/// The estimate does not translate the operation. It accounts for a short gate name, one `q[i]`
+/// argument per involved qubit and a single floating point parameter for rotations, which
+/// matches the common one- and two-qubit gates. Operations without involved qubits are assumed
+/// to produce no output.
+fn estimated_instruction_length(operation: &Operation, qubit_register_name: &str) -> usize {
+    match operation.involved_qubits() {
+        InvolvedQubits::Set(qubits) => {
+            let arguments: usize = qubits
+                .iter()
+                .map(|qubit| {
+                    qubit_register_name.len() + qubit.to_string().len() + QUBIT_ARGUMENT_OVERHEAD
+                })
+                .sum();
+            let parameters = if operation.tags().contains(&"Rotation") {
+                ESTIMATED_PARAMETER_LENGTH
+            } else {
+                0
+            };
+            ESTIMATED_GATE_NAME_LENGTH + arguments + parameters
+        }
+        _ => 0,
+    }
+}
+
+/// Checks that a register name is a valid OpenQASM identifier matching `[a-zA-Z][a-zA-Z0-9_]*`.
+fn check_register_name(name: &str, register: &str) -> Result<(), RoqoqoBackendError> {
+    let mut characters = name.chars();
+    let valid = characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_');
+    if !valid {
+        return Err(RoqoqoBackendError::GenericError {
+            msg: format!(
+                "The {} name \"{}\" is not a valid OpenQASM identifier, it must start with a letter followed by letters, digits or underscores",
+                register, name
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a classical register definition has a valid name that does not reuse the name of the qubit register.
+fn check_register_name_collision(
+    operation: &Operation,
+    qubit_register_name: &str,
+    qasm_version: QasmVersion,
+) -> Result<(), RoqoqoBackendError> {
+    let classical_names: Vec<String> = match operation {
+        Operation::DefinitionBit(op) => vec![op.name().to_owned()],
+        Operation::DefinitionFloat(op) => vec![op.name().to_owned()],
+        Operation::DefinitionUsize(op) => vec![op.name().to_owned()],
+        Operation::DefinitionComplex(op) => match qasm_version {
+            QasmVersion::V2point0(_) => vec![op.name().to_owned()],
+            QasmVersion::V3point0(_) => {
+                vec![format!("{}_re", op.name()), format!("{}_im", op.name())]
+            }
+        },
+        _ => vec![],
+    };
+    for name in classical_names.iter() {
+        check_register_name(name, "classical register")?;
+    }
+    if classical_names
+        .iter()
+        .any(|name| name == qubit_register_name)
+    {
+        return Err(RoqoqoBackendError::GenericError {
+            msg: format!(
+                "The classical register defined by {} uses the same name as the qubit register: {}",
+                operation.hqslang(),
+                qubit_register_name
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a measurement writes inside the declared length of its classical register.
+///
+/// The lengths of the classical registers defined so far are tracked in `register_lengths`.
+fn check_readout_index(
+    operation: &Operation,
+    register_lengths: &mut HashMap<String, usize>,
+) -> Result<(), RoqoqoBackendError> {
+    match operation {
+        Operation::DefinitionBit(op) => {
+            register_lengths.insert(op.name().to_owned(), *op.length());
+        }
+        Operation::MeasureQubit(op) => {
+            if let Some(length) = register_lengths.get(op.readout()) {
+                if op.readout_index() >= length {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!(
+                            "MeasureQubit writes to index {} of the classical register {} of length {}",
+                            op.readout_index(),
+                            op.readout(),
+                            length
+                        ),
+                    });
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Checks that a measurement does not write to a classical bit that was already written.
+///
+/// The classical bits written so far are tracked in `written_bits`.
+fn check_repeated_readout(
+    operation: &Operation,
+    written_bits: &mut HashSet<(String, usize)>,
+) -> Result<(), RoqoqoBackendError> {
+    if let Operation::MeasureQubit(op) = operation {
+        if !written_bits.insert((op.readout().to_owned(), *op.readout_index())) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "MeasureQubit writes to the classical bit {}[{}] that was already written",
+                    op.readout(),
+                    op.readout_index()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Collects the lint warnings of a sequence of operations, descending into nested circuits.
+fn lint_operations<'a>(
+    circuit: impl Iterator<Item = &'a Operation>,
+    declared_registers: &mut Vec<String>,
+    defined_gates: &mut Vec<String>,
+    written_bits: &mut HashSet<(String, usize)>,
+    warnings: &mut Vec<String>,
+) {
+    for operation in circuit {
+        let declared_name = match operation {
+            Operation::DefinitionBit(op) => Some(op.name()),
+            Operation::DefinitionFloat(op) => Some(op.name()),
+            Operation::DefinitionUsize(op) => Some(op.name()),
+            Operation::DefinitionComplex(op) => Some(op.name()),
+            _ => None,
+        };
+        if let Some(name) = declared_name {
+            if declared_registers.contains(name) {
+                warnings.push(format!(
+                    "Duplicate declaration of classical register {}",
+                    name
+                ));
+            } else {
+                declared_registers.push(name.to_owned());
+            }
+        }
+        let measured_register = match operation {
+            Operation::MeasureQubit(op) => Some(op.readout()),
+            Operation::PragmaRepeatedMeasurement(op) => Some(op.readout()),
+            _ => None,
+        };
+        if let Some(name) = measured_register {
+            if !declared_registers.contains(name) {
+                warnings.push(format!(
+                    "{} measures into undeclared classical register {}",
+                    operation.hqslang(),
+                    name
+                ));
+            }
+        }
+        if let Err(RoqoqoBackendError::GenericError { msg }) =
+            check_repeated_readout(operation, written_bits)
+        {
+            warnings.push(msg);
+        }
+        let used_register = match operation {
+            Operation::PragmaConditional(op) => Some(op.condition_register()),
+            Operation::InputBit(op) => Some(op.name()),
+            _ => None,
+        };
+        if let Some(name) = used_register {
+            if !declared_registers.contains(name) {
+                warnings.push(format!(
+                    "{} uses undeclared classical register {}",
+                    operation.hqslang(),
+                    name
+                ));
+            }
+        }
+        match operation {
+            Operation::GateDefinition(op) => {
+                if defined_gates.contains(op.name()) {
+                    warnings.push(format!("Duplicate definition of gate {}", op.name()));
+                } else {
+                    defined_gates.push(op.name().to_owned());
+                }
+            }
+            Operation::CallDefinedGate(op) if !defined_gates.contains(op.gate_name()) => {
+                warnings.push(format!(
+                    "Gate {} is called before it is defined",
+                    op.gate_name()
+                ));
+            }
+            Operation::PragmaConditional(op) => lint_operations(
+                op.circuit().iter(),
+                declared_registers,
+                defined_gates,
+                written_bits,
+                warnings,
+            ),
+            Operation::PragmaLoop(op) => lint_operations(
+                op.circuit().iter(),
+                declared_registers,
+                defined_gates,
+                written_bits,
+                warnings,
+            ),
+            _ => (),
+        }
+    }
+}
+
 /// QASM backend to qoqo
 ///
 /// This backend to roqoqo produces QASM output which can be exported.
@@ -65,6 +540,47 @@ pub struct Backend {
     qubit_register_name: String,
     /// Which version of OpenQASM (2.0 or 3.0) to use
     qasm_version: QasmVersion,
+    /// Options passed on to the translation of the single operations.
+    options: QasmOptions,
+    /// Fixed size of the qubit register.
+    ///
+    /// When not set, the size of the qubit register is deduced from the largest qubit index in the circuit.
+    fixed_qubit_count: Option<usize>,
+    /// Whether a summary comment with the qubit register size and the number of gates is emitted.
+    verbose: bool,
+    /// Whether a `// end of circuit` comment is emitted after the last line.
+    end_marker: bool,
+    /// Whether a comment with the roqoqo-qasm version is emitted after the OpenQASM header.
+    include_header_comment: bool,
+    /// Whether every operation is validated against the fixed size of the qubit register.
+    validation: bool,
+    /// Whether resets of every qubit of the register are emitted as a single `reset q;` in OpenQASM 3.0.
+    register_reset: bool,
+    /// Bit widths of the `input angle` declarations of the circuit parameters.
+    ///
+    /// Parameters without an entry are declared with a width of 32 bits.
+    angle_widths: HashMap<String, u32>,
+    /// Register name and index in that register of every roqoqo qubit index.
+    ///
+    /// When set, one quantum register is declared per register name instead of the single
+    /// register named `qubit_register_name`.
+    register_mapping: Option<HashMap<usize, (String, usize)>>,
+    /// Whether writing a classical bit more than once is an error.
+    strict: bool,
+    /// Number of blank lines between the sections of the QASM output.
+    ///
+    /// When not set, the sections are separated as in the default layout.
+    section_blank_lines: Option<usize>,
+    /// Whether the qubit register is not declared when no operation involves qubits.
+    omit_unused_qubit_register: bool,
+    /// Whether the operations of every decomposition block are wrapped in an OpenQASM 3.0 `box`.
+    box_decomposition_blocks: bool,
+    /// Whether gate definitions are placed right before the first operation using them.
+    definitions_inline: bool,
+    /// Whether the QASM files start with a UTF-8 byte order mark.
+    utf8_bom: bool,
+    /// Whether `log10`, `exp2` and `cbrt` are rewritten into functions supported in OpenQASM 3.0.
+    rewrite_functions: bool,
 }
 
 impl Backend {
@@ -74,6 +590,11 @@ impl Backend {
     ///
     /// * `qubit_register_name` - The number of qubits in the backend.
     /// * `qasm_version` - The version of OpenQASM (2.0 or 3.0) to use.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Backend)` - The new QASM backend
+    /// * `RoqoqoBackendError::GenericError` - The qubit register name is not a valid OpenQASM identifier or the QASM version is not supported
     pub fn new(
         qubit_register_name: Option<String>,
         qasm_version: Option<String>,
@@ -82,6 +603,7 @@ impl Backend {
             None => "q".to_string(),
             Some(s) => s,
         };
+        check_register_name(&qubit_reg, "qubit register")?;
         let qasm_v = match qasm_version {
             None => QasmVersion::V2point0(Qasm2Dialect::Vanilla),
             Some(v) => QasmVersion::from_str(v.as_str())?,
@@ -90,176 +612,1248 @@ impl Backend {
         Ok(Self {
             qubit_register_name: qubit_reg,
             qasm_version: qasm_v,
+            options: QasmOptions::default(),
+            fixed_qubit_count: None,
+            verbose: false,
+            end_marker: false,
+            include_header_comment: false,
+            validation: false,
+            register_reset: false,
+            angle_widths: HashMap::new(),
+            register_mapping: None,
+            strict: false,
+            section_blank_lines: None,
+            omit_unused_qubit_register: false,
+            box_decomposition_blocks: false,
+            definitions_inline: false,
+            utf8_bom: false,
+            rewrite_functions: false,
         })
     }
 
-    /// Translates an iterator over operations to a valid QASM string.
+    /// Sets whether CNOT is emitted as the OpenQASM 2.0 built-in `CX`.
+    ///
+    /// When set, CNOT calls use `CX` directly, the `cx` gate is not defined and the
+    /// decompositions of the other gates use `CX` as well. Has no effect for OpenQASM 3.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `builtin_cnot` - Whether to use the built-in `CX` for CNOT.
+    pub fn with_builtin_cnot(mut self, builtin_cnot: bool) -> Self {
+        self.options.builtin_cnot = builtin_cnot;
+        self
+    }
+
+    /// Sets whether the pragmas of the Roqoqo dialect are serialized as JSON.
+    ///
+    /// When set, pragmas are emitted as `pragma roqoqo <hqslang> <json>;` where `<json>` is the
+    /// serialized pragma, which can be deserialized back into the roqoqo operation.
+    /// Has no effect for the other dialects.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_pragmas` - Whether to serialize the Roqoqo dialect pragmas as JSON.
+    pub fn with_json_pragmas(mut self, json_pragmas: bool) -> Self {
+        self.options.json_pragmas = json_pragmas;
+        self
+    }
+
+    /// Sets the maximum nesting depth of PragmaConditional and PragmaLoop operations.
+    ///
+    /// Translating a circuit with deeper nesting returns an error instead of recursing further.
+    /// Defaults to 256.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_recursion_depth` - The maximum nesting depth of the translated operations.
+    pub fn with_max_recursion_depth(mut self, max_recursion_depth: usize) -> Self {
+        self.options.max_recursion_depth = max_recursion_depth;
+        self
+    }
+
+    /// Sets whether the OpenQASM 3.0 output includes `stdgates.inc`.
+    ///
+    /// When set, the output contains `include "stdgates.inc";` and only defines the gates the
+    /// library does not provide. Has no effect for OpenQASM 2.0 and the Braket dialect, the
+    /// Qiskit dialect always includes the library.
+    ///
+    /// # Arguments
+    ///
+    /// * `stdgates_include` - Whether to include `stdgates.inc` instead of defining its gates.
+    pub fn with_stdgates_include(mut self, stdgates_include: bool) -> Self {
+        self.options.stdgates_include = stdgates_include;
+        self
+    }
+
+    /// Sets whether the vanilla OpenQASM 2.0 output includes `qelib1.inc`.
+    ///
+    /// When set, the output contains `include "qelib1.inc";` and only defines the gates the
+    /// library does not provide. ControlledPhaseShift relies on the library when it is emitted
+    /// as `cu1`. Has no effect for OpenQASM 3.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `qelib_include` - Whether to include `qelib1.inc` instead of defining its gates.
+    pub fn with_qelib_include(mut self, qelib_include: bool) -> Self {
+        self.options.qelib_include = qelib_include;
+        self
+    }
+
+    /// Sets whether ControlledPhaseShift is emitted as `cu1` instead of `cp` for OpenQASM 2.0.
+    ///
+    /// Older `qelib1.inc` files name the controlled phase gate `cu1`. Has no effect for OpenQASM 3.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `cu1_controlled_phase` - Whether to name the controlled phase gate `cu1`.
+    pub fn with_cu1_controlled_phase(mut self, cu1_controlled_phase: bool) -> Self {
+        self.options.cu1_controlled_phase = cu1_controlled_phase;
+        self
+    }
+
+    /// Sets whether the basis-rotation circuits of measurement pragmas are emitted as gates.
+    ///
+    /// When set, the circuit of PragmaGetPauliProduct, PragmaGetDensityMatrix,
+    /// PragmaGetOccupationProbability and PragmaGetStateVector is translated in place of the
+    /// pragma, so that the following measurement statements measure in the rotated basis.
+    /// Has no effect for the Roqoqo dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `inline_measurement_basis` - Whether to emit the basis-rotation circuits as gates.
+    pub fn with_inline_measurement_basis(mut self, inline_measurement_basis: bool) -> Self {
+        self.options.inline_measurement_basis = inline_measurement_basis;
+        self
+    }
+
+    /// Sets whether PragmaGlobalPhase is dropped from the output of the Braket dialect.
+    ///
+    /// By default, the global phase is emitted in the Braket syntax `gphase(phase);`. When set,
+    /// the global phase is dropped as in OpenQASM 2.0. Has no effect for the other dialects.
+    ///
+    /// # Arguments
+    ///
+    /// * `drop_unsupported_global_phase` - Whether to drop the global phase in the Braket dialect.
+    pub fn with_drop_unsupported_global_phase(
+        mut self,
+        drop_unsupported_global_phase: bool,
+    ) -> Self {
+        self.options.drop_unsupported_global_phase = drop_unsupported_global_phase;
+        self
+    }
+
+    /// Sets the number of spaces the bodies of OpenQASM 3.0 blocks are indented by.
+    ///
+    /// Applies to the blocks of PragmaConditional and PragmaLoop, nested blocks are indented
+    /// further. The default is 4.
+    ///
+    /// # Arguments
+    ///
+    /// * `indent_width` - The number of spaces per nesting level.
+    pub fn with_indent_width(mut self, indent_width: usize) -> Self {
+        self.options.indent_width = indent_width;
+        self
+    }
+
+    /// Sets the value of the classical bit that triggers the circuit of a PragmaConditional.
+    ///
+    /// # Arguments
+    ///
+    /// * `conditional_value` - The value the condition bit is compared against, `1` by default.
+    pub fn with_conditional_value(mut self, conditional_value: QasmConditionalValue) -> Self {
+        self.options.conditional_value = conditional_value;
+        self
+    }
+
+    /// Sets whether controlled gates are emitted with the OpenQASM 3.0 `ctrl @` modifier.
+    ///
+    /// When set, ControlledPauliY, ControlledPauliZ and ControlledPhaseShift are emitted as
+    /// `ctrl @ y`, `ctrl @ z` and `ctrl @ p` and only the controlled gate needs to be defined.
+    /// Only has an effect for the vanilla OpenQASM 3.0 dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctrl_modifier` - Whether to use the `ctrl @` modifier for controlled gates.
+    pub fn with_ctrl_modifier(mut self, ctrl_modifier: bool) -> Self {
+        self.options.ctrl_modifier = ctrl_modifier;
+        self
+    }
+
+    /// Sets a fixed size for the qubit register.
+    ///
+    /// The circuits translated with this backend are validated against the fixed size: an error
+    /// is returned when an operation acts on a qubit outside of the register.
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_qubit_count` - The number of qubits in the qubit register.
+    pub fn with_fixed_qubit_count(mut self, fixed_qubit_count: usize) -> Self {
+        self.fixed_qubit_count = Some(fixed_qubit_count);
+        self
+    }
+
+    /// Sets or unsets the fixed size of the qubit register.
+    ///
+    /// Unlike [Backend::with_fixed_qubit_count], the size can be removed again, the size of the
+    /// qubit register is then deduced from the circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `number_of_qubits` - The number of qubits in the qubit register, if fixed.
+    pub fn set_number_of_qubits(&mut self, number_of_qubits: Option<usize>) {
+        self.fixed_qubit_count = number_of_qubits;
+    }
+
+    /// Sets whether every operation is validated against the fixed size of the qubit register.
+    ///
+    /// When set, the translation stops at the first operation acting on a qubit outside of the
+    /// fixed qubit register and the error names that operation. Has no effect when the size of
+    /// the qubit register is not fixed.
+    ///
+    /// # Arguments
+    ///
+    /// * `validation` - Whether to validate the qubits of every operation.
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Sets whether consecutive resets covering the whole qubit register are collapsed.
+    ///
+    /// When set, a run of PragmaActiveReset operations resetting every qubit of the register
+    /// exactly once is emitted as the register reset `reset q;`. Has no effect for OpenQASM 2.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `register_reset` - Whether to collapse resets of the whole register.
+    pub fn with_register_reset(mut self, register_reset: bool) -> Self {
+        self.register_reset = register_reset;
+        self
+    }
+
+    /// Sets the bit widths of the `input angle` declarations for OpenQASM 3.0.
+    ///
+    /// Parameters of the circuit without an entry keep the default width of 32 bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `angle_widths` - The bit width of the angle declaration for each parameter name.
+    pub fn with_angle_widths(mut self, angle_widths: HashMap<String, u32>) -> Self {
+        self.angle_widths = angle_widths;
+        self
+    }
+
+    /// Sets the mapping of the roqoqo qubit indices to several quantum registers.
+    ///
+    /// Every qubit index used in the circuit is emitted as the qubit `local_index` of the
+    /// register `register_name` and each register is declared separately. Operations acting on
+    /// the whole qubit register can not be translated with a register mapping.
+    ///
+    /// # Arguments
+    ///
+    /// * `register_mapping` - The `(register_name, local_index)` pair of each qubit index.
+    pub fn with_register_mapping(
+        mut self,
+        register_mapping: HashMap<usize, (String, usize)>,
+    ) -> Self {
+        self.register_mapping = Some(register_mapping);
+        self
+    }
+
+    /// Sets whether measuring into a classical bit that was already written is an error.
+    ///
+    /// A second measurement into the same bit overwrites the first one, which usually indicates
+    /// a bug in the circuit. Only the top-level MeasureQubit operations are checked, the lint
+    /// reports the same issue as a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether to reject circuits writing a classical bit more than once.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the number of blank lines between the sections of the QASM output.
+    ///
+    /// The header, the includes, the gate definitions, the input parameters, the register
+    /// declarations and the circuit data are separated by exactly `blank_lines` blank lines,
+    /// `0` gives a compact output without blank lines. By default the sections are separated by
+    /// one or two blank lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `blank_lines` - The number of blank lines between two sections.
+    pub fn with_section_blank_lines(mut self, blank_lines: usize) -> Self {
+        self.section_blank_lines = Some(blank_lines);
+        self
+    }
+
+    /// Sets whether the qubit register is declared only when an operation involves qubits.
+    ///
+    /// By default, circuits without any qubit operation, for example with only classical
+    /// register definitions, declare a qubit register of size 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `omit_unused_qubit_register` - Whether to omit the qubit register of circuits without qubit operations.
+    pub fn with_omit_unused_qubit_register(mut self, omit_unused_qubit_register: bool) -> Self {
+        self.omit_unused_qubit_register = omit_unused_qubit_register;
+        self
+    }
+
+    /// Sets whether the decomposition blocks of the circuit are exported as OpenQASM 3.0 `box` blocks.
+    ///
+    /// The operations between a PragmaStartDecompositionBlock and the following
+    /// PragmaStopDecompositionBlock are wrapped in an indented `box { ... }`, nested blocks are
+    /// nested boxes. Only has an effect for the vanilla OpenQASM 3.0 dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `box_decomposition_blocks` - Whether to wrap the decomposition blocks in `box` blocks.
+    pub fn with_box_decomposition_blocks(mut self, box_decomposition_blocks: bool) -> Self {
+        self.box_decomposition_blocks = box_decomposition_blocks;
+        self
+    }
+
+    /// Sets whether gate definitions are emitted right before the first operation using them.
+    ///
+    /// By default, all gate definitions are collected after the OpenQASM header. When set, each
+    /// definition is placed in the circuit data in front of the first operation that requires it.
+    /// The definitions that are always emitted stay after the header. The QASM output is no
+    /// longer streamed when writing to a writer or a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions_inline` - Whether to place the gate definitions at their first use.
+    pub fn with_definitions_inline(mut self, definitions_inline: bool) -> Self {
+        self.definitions_inline = definitions_inline;
+        self
+    }
+
+    /// Sets whether the QASM files written by the backend start with a UTF-8 byte order mark.
+    ///
+    /// Some legacy tools expect the byte order mark at the start of the file. Only applies to
+    /// the files written by `circuit_to_qasm_file` and `circuit_iterator_to_qasm_file`, the QASM
+    /// strings never contain it.
+    ///
+    /// # Arguments
+    ///
+    /// * `utf8_bom` - Whether to prepend a UTF-8 byte order mark to the QASM files.
+    pub fn with_utf8_bom(mut self, utf8_bom: bool) -> Self {
+        self.utf8_bom = utf8_bom;
+        self
+    }
+
+    /// Sets whether symbolic parameters using `log10`, `exp2` or `cbrt` are rewritten.
+    ///
+    /// OpenQASM 3.0 does not provide these functions, by default circuits using them can only be
+    /// exported to the Roqoqo dialect. When set, they are emitted as `log(x)/log(10)`,
+    /// `exp(x*log(2))` and `pow(x,1.0/3.0)` in the other OpenQASM 3.0 dialects.
+    ///
+    /// # Arguments
+    ///
+    /// * `rewrite_functions` - Whether to rewrite the functions not supported in OpenQASM 3.0.
+    pub fn with_rewrite_functions(mut self, rewrite_functions: bool) -> Self {
+        self.rewrite_functions = rewrite_functions;
+        self
+    }
+
+    /// Sets whether a summary comment is emitted after the OpenQASM header.
+    ///
+    /// The comment `// qubits: N, gates: M` contains the size of the qubit register and the
+    /// number of operations with a non-empty QASM translation.
+    ///
+    /// # Arguments
+    ///
+    /// * `verbose` - Whether to emit the summary comment.
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Sets whether a `// end of circuit` comment is emitted after the last line.
+    ///
+    /// The marker helps tools splitting concatenated QASM programs.
+    ///
+    /// # Arguments
+    ///
+    /// * `end_marker` - Whether to emit the end of circuit marker.
+    pub fn with_end_marker(mut self, end_marker: bool) -> Self {
+        self.end_marker = end_marker;
+        self
+    }
+
+    /// Sets whether a comment with generation metadata is emitted after the OpenQASM header.
+    ///
+    /// The comment `// Generated by roqoqo-qasm vX.Y.Z` directly follows the `OPENQASM x.y;`
+    /// line, so parsers requiring the version first still accept the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_header_comment` - Whether to emit the header comment.
+    pub fn with_header_comment(mut self, include_header_comment: bool) -> Self {
+        self.include_header_comment = include_header_comment;
+        self
+    }
+
+    /// Returns a copy of the backend that uses a different QASM version.
+    ///
+    /// The qubit register name and all other settings are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `qasm_version` - The QASM version of the returned backend.
+    pub fn clone_with_version(&self, qasm_version: QasmVersion) -> Self {
+        Self {
+            qasm_version,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of the backend that uses a different QASM version given as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `qasm_version` - The QASM version of the returned backend, e.g. "2.0" or "3.0Roqoqo".
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Backend)` - The backend with the new QASM version
+    /// * `RoqoqoBackendError::GenericError` - The QASM version is not supported
+    pub fn clone_with_version_str(&self, qasm_version: &str) -> Result<Self, RoqoqoBackendError> {
+        Ok(self.clone_with_version(QasmVersion::from_str(qasm_version)?))
+    }
+
+    /// Places the translation of an operation in the `box` blocks of the decomposition blocks.
+    ///
+    /// Opens a `box` for every PragmaStartDecompositionBlock, closes it for the matching
+    /// PragmaStopDecompositionBlock and indents the operations in between. `box_depth` tracks the
+    /// number of open boxes across the operations of the circuit.
+    fn boxed_instruction(
+        &self,
+        operation: &Operation,
+        instruction: String,
+        box_depth: &mut usize,
+    ) -> String {
+        if !self.box_decomposition_blocks
+            || self.qasm_version != QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+        {
+            return instruction;
+        }
+        let indent = " ".repeat(self.options.indent_width * *box_depth);
+        match operation {
+            Operation::PragmaStartDecompositionBlock(_) => {
+                *box_depth += 1;
+                instruction
+                    .lines()
+                    .map(|line| format!("{}{}\n", indent, line))
+                    .collect::<String>()
+                    + &format!("{}box {{", indent)
+            }
+            Operation::PragmaStopDecompositionBlock(_) if *box_depth > 0 => {
+                *box_depth -= 1;
+                format!("{}}}", " ".repeat(self.options.indent_width * *box_depth))
+            }
+            _ if *box_depth == 0 || instruction.is_empty() => instruction,
+            _ => instruction
+                .lines()
+                .map(|line| format!("{}{}", indent, line))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+
+    /// Translates an operation into the chunk of circuit data it adds to the output.
+    ///
+    /// Shared by the string and the streamed output, so that both produce the same circuit data.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation that is translated
+    /// * `variable_gatherer` - The VariableGatherer collecting the input parameters
+    /// * `box_depth` - The number of open `box` blocks of the decomposition blocks
+    /// * `data_is_empty` - Whether the circuit data is still empty before the operation
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((String, bool))` - The chunk of circuit data and whether the operation produced an instruction
+    /// * `RoqoqoBackendError::OperationNotInBackend` - The operation is not available on the backend
+    fn data_chunk(
+        &self,
+        operation: &Operation,
+        variable_gatherer: &mut VariableGatherer,
+        box_depth: &mut usize,
+        data_is_empty: bool,
+    ) -> Result<(String, bool), RoqoqoBackendError> {
+        let instruction = self.translate_operation(operation, &mut Some(variable_gatherer))?;
+        let has_instruction = !instruction.is_empty();
+        let mut chunk = self.boxed_instruction(operation, instruction, box_depth);
+        // Ignored operations are only ended with a newline when they produce output
+        if !chunk.is_empty()
+            || !(data_is_empty || ALLOWED_OPERATIONS.contains(&operation.hqslang()))
+        {
+            chunk.push('\n');
+        }
+        Ok((chunk, has_instruction))
+    }
+
+    /// Creates the VariableGatherer collecting the input parameters of the QASM version.
+    fn variable_gatherer(&self) -> VariableGatherer {
+        // The Roqoqo dialect is not meant to be standard OpenQASM 3.0 and accepts all functions
+        match self.qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => VariableGatherer::new_permissive(),
+            _ if self.rewrite_functions => VariableGatherer::new_rewriting(),
+            _ => VariableGatherer::new(),
+        }
+    }
+
+    /// Appends the end of circuit marker to the QASM string when it is enabled.
+    fn push_end_marker(&self, qasm_string: &mut String) {
+        if self.end_marker {
+            if !qasm_string.ends_with('\n') {
+                qasm_string.push('\n');
+            }
+            qasm_string.push_str(END_MARKER);
+        }
+    }
+
+    /// Translates a single operation, addressing the qubits through the register mapping when it is set.
+    fn translate_operation(
+        &self,
+        operation: &Operation,
+        variable_gatherer: &mut Option<&mut VariableGatherer>,
+    ) -> Result<String, RoqoqoBackendError> {
+        match &self.register_mapping {
+            Some(register_mapping) => call_operation_with_register_mapping(
+                operation,
+                register_mapping,
+                self.qasm_version,
+                variable_gatherer,
+                &self.options,
+            ),
+            None => call_operation_with_options(
+                operation,
+                &self.qubit_register_name,
+                self.qasm_version,
+                variable_gatherer,
+                &self.options,
+            ),
+        }
+    }
+
+    /// Returns the declarations of the quantum registers of the register mapping.
+    ///
+    /// The registers are declared in the order of the smallest qubit index mapped to them, each
+    /// register is large enough for the largest local index mapped to it.
+    fn mapped_register_declarations(
+        &self,
+        register_mapping: &HashMap<usize, (String, usize)>,
+    ) -> String {
+        let mut qubits: Vec<&usize> = register_mapping.keys().collect();
+        qubits.sort();
+        let mut registers: Vec<(&str, usize)> = Vec::new();
+        for qubit in qubits {
+            let (name, local_index) = &register_mapping[qubit];
+            match registers.iter_mut().find(|(seen, _)| seen == name) {
+                Some((_, size)) => *size = (*size).max(local_index + 1),
+                None => registers.push((name, local_index + 1)),
+            }
+        }
+        let mut declarations = String::from("\n");
+        for (name, size) in registers {
+            match self.qasm_version {
+                QasmVersion::V2point0(_) => {
+                    declarations.push_str(&format!("qreg {}[{}];\n", name, size))
+                }
+                QasmVersion::V3point0(_) => {
+                    declarations.push_str(&format!("qubit[{}] {};\n", size, name))
+                }
+            }
+        }
+        declarations.push('\n');
+        declarations
+    }
+
+    /// Returns whether resets covering the whole qubit register are collapsed in the output.
+    fn collapses_register_resets(&self) -> bool {
+        self.register_reset && matches!(self.qasm_version, QasmVersion::V3point0(_))
+    }
+
+    /// Returns the size of the qubit register, validating a fixed size against the circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_qubit_index` - The largest qubit index used in the circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The size of the qubit register
+    /// * `RoqoqoBackendError::GenericError` - The fixed qubit register is too small for the circuit
+    fn qubit_register_size(&self, max_qubit_index: usize) -> Result<usize, RoqoqoBackendError> {
+        match self.fixed_qubit_count {
+            Some(count) if count <= max_qubit_index => Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The fixed qubit register of size {} is too small for the qubit index {} used in the circuit",
+                    count, max_qubit_index
+                ),
+            }),
+            Some(count) => Ok(count),
+            None => Ok(max_qubit_index + 1),
+        }
+    }
+
+    /// Translates an iterator over operations to a valid QASM string.
+    ///
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The iterator over [roqoqo::Operation] items that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The valid QASM string
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn circuit_iterator_to_qasm_str<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+    ) -> Result<String, RoqoqoBackendError> {
+        let (mut qasm_string, data, _) =
+            self.translate_circuit_iterator(circuit, CircuitTranslation::Full)?;
+        qasm_string.push_str(data.as_str());
+        self.push_end_marker(&mut qasm_string);
+
+        Ok(qasm_string)
+    }
+
+    /// Translates an iterator over operations into the QASM preamble and the QASM circuit data.
+    ///
+    /// The preamble contains the QASM version, the gate definitions, the input parameters and
+    /// the register declarations.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The iterator over [roqoqo::Operation] items that is translated
+    /// * `translation` - How much of the circuit is translated, the returned data is empty unless
+    ///   the circuit is fully translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((String, String, Vec<String>))` - The QASM preamble, the QASM circuit data and the
+    ///   gate definitions required by the circuit in first-seen order
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    fn translate_circuit_iterator<'a>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation>,
+        translation: CircuitTranslation,
+    ) -> Result<(String, String, Vec<String>), RoqoqoBackendError> {
+        let collect_data = translation == CircuitTranslation::Full;
+        // Initializing data structures
+        let mut definitions: String = "".to_string();
+        let mut gate_definitions: Vec<String> = Vec::new();
+        let mut data: String = "".to_string();
+        let mut number_qubits_required: usize = 0;
+        let mut uses_qubits = false;
+        let mut box_depth: usize = 0;
+        let mut number_gates: usize = 0;
+        let mut already_seen_definitions: Vec<String> = vec![
+            "RotateX".to_string(),
+            "RotateY".to_string(),
+            "RotateZ".to_string(),
+            "CNOT".to_string(),
+        ];
+        let mut variable_gatherer = self.variable_gatherer();
+
+        // Appending QASM version
+        let mut qasm_string = String::from("OPENQASM ");
+        match self.qasm_version {
+            QasmVersion::V2point0(_) => qasm_string.push_str("2.0;\n\n"),
+            QasmVersion::V3point0(_) => qasm_string.push_str("3.0;\n\n"),
+        }
+        if self.include_header_comment {
+            qasm_string.push_str(&format!(
+                "// Generated by roqoqo-qasm v{}\n\n",
+                env!("CARGO_PKG_VERSION")
+            ));
+        }
+
+        // Appending definitions that are always needed (some depend on QASM version),
+        // the stdgates.inc and qelib1.inc libraries provide all of them
+        let stdgates_include = match self.qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Qiskit) => true,
+            QasmVersion::V3point0(Qasm3Dialect::Braket)
+            | QasmVersion::V3point0(Qasm3Dialect::IonQ) => false,
+            QasmVersion::V3point0(_) => self.options.stdgates_include,
+            QasmVersion::V2point0(_) => false,
+        };
+        let qelib_include = self.options.qelib_include
+            && self.qasm_version == QasmVersion::V2point0(Qasm2Dialect::Vanilla);
+        if !stdgates_include && !qelib_include {
+            definitions.push_str("gate u3(theta,phi,lambda) q { U(theta,phi,lambda) q; }\n");
+            definitions.push_str("gate u2(phi,lambda) q { U(pi/2,phi,lambda) q; }\n");
+            definitions.push_str("gate u1(lambda) q { U(0,0,lambda) q; }\n");
+            definitions.push_str(&gate_definition_with_options(
+                &Operation::from(RotateX::new(0, CalculatorFloat::from(0.0))),
+                self.qasm_version,
+                &self.options,
+            )?);
+            definitions.push('\n');
+            definitions.push_str(&gate_definition_with_options(
+                &Operation::from(RotateY::new(0, CalculatorFloat::from(0.0))),
+                self.qasm_version,
+                &self.options,
+            )?);
+            definitions.push('\n');
+            definitions.push_str(&gate_definition_with_options(
+                &Operation::from(RotateZ::new(0, CalculatorFloat::from(0.0))),
+                self.qasm_version,
+                &self.options,
+            )?);
+            definitions.push('\n');
+            if !(self.options.builtin_cnot && matches!(self.qasm_version, QasmVersion::V2point0(_)))
+            {
+                definitions.push_str(&gate_definition_with_options(
+                    &Operation::from(CNOT::new(0, 1)),
+                    self.qasm_version,
+                    &self.options,
+                )?);
+            }
+            definitions.push_str("\n\n");
+        }
+
+        // Gate definitions are only emitted by the dialects that do not rely on a fixed gate set
+        let inline_definitions = collect_data
+            && self.definitions_inline
+            && !matches!(
+                self.qasm_version,
+                QasmVersion::V3point0(Qasm3Dialect::Braket)
+                    | QasmVersion::V3point0(Qasm3Dialect::IonQ)
+                    | QasmVersion::V2point0(Qasm2Dialect::Qulacs)
+            );
+
+        // Main loop over the circuit
+        let mut register_lengths: HashMap<String, usize> = HashMap::new();
+        let mut written_bits: HashSet<(String, usize)> = HashSet::new();
+        for op in circuit {
+            check_register_name_collision(op, &self.qubit_register_name, self.qasm_version)?;
+            if let Some(register_mapping) = &self.register_mapping {
+                for (register_name, _) in register_mapping.values() {
+                    check_register_name_collision(op, register_name, self.qasm_version)?;
+                }
+            }
+            check_readout_index(op, &mut register_lengths)?;
+            if self.strict {
+                check_repeated_readout(op, &mut written_bits)?;
+            }
+
+            // Taking note of the maximum number of qubits involved in the circuit for registers definition
+            match op.involved_qubits() {
+                InvolvedQubits::Set(involved_qubits) if involved_qubits.is_empty() => {}
+                InvolvedQubits::None => {}
+                _ => uses_qubits = true,
+            }
+            if let InvolvedQubits::Set(involved_qubits) = op.involved_qubits() {
+                if let (true, Some(count)) = (self.validation, self.fixed_qubit_count) {
+                    if let Some(qubit) = involved_qubits.iter().find(|&&qubit| qubit >= count) {
+                        return Err(RoqoqoBackendError::GenericError {
+                            msg: format!(
+                                "{} acts on qubit {} outside of the fixed qubit register of size {}",
+                                op.hqslang(),
+                                qubit,
+                                count
+                            ),
+                        });
+                    }
+                }
+                number_qubits_required =
+                    number_qubits_required.max(match involved_qubits.iter().max() {
+                        None => 0,
+                        Some(n) => *n,
+                    })
+            }
+
+            let definitions_start = definitions.len();
+            // Appending the gate definitions used in the bodies of control-flow pragmas and in the
+            // inlined measurement bases, every body is checked since they use different gates
+            if let Some(inner_circuit) = inner_circuit(op, self.qasm_version, &self.options) {
+                process_operation_circuit(
+                    inner_circuit.iter(),
+                    self.qasm_version,
+                    &self.options,
+                    &mut already_seen_definitions,
+                    &mut definitions,
+                    &mut gate_definitions,
+                )?;
+            }
+
+            // Appending gate definition if not already seen before
+            let key = definition_key(op, self.qasm_version, &self.options);
+            if !already_seen_definitions.contains(&key) {
+                let mut continue_process = false;
+                if let Operation::GateDefinition(gate_definition) = op {
+                    if !already_seen_definitions.contains(gate_definition.name()) {
+                        already_seen_definitions.push(gate_definition.name().to_owned());
+                        continue_process = true;
+                    }
+                } else {
+                    already_seen_definitions.push(key);
+                    continue_process = true;
+                }
+
+                if continue_process && !is_native_gate(op, self.qasm_version, &self.options) {
+                    if let Operation::GateDefinition(gate_definition) = op {
+                        process_operation_circuit(
+                            gate_definition.circuit().iter(),
+                            self.qasm_version,
+                            &self.options,
+                            &mut already_seen_definitions,
+                            &mut definitions,
+                            &mut gate_definitions,
+                        )?;
+                    }
+                    let definition =
+                        gate_definition_with_options(op, self.qasm_version, &self.options)?;
+                    push_gate_definition(&mut gate_definitions, &definition);
+                    definitions.push_str(&definition);
+                    if !definitions.is_empty()
+                        && !NO_DEFINITION_REQUIRED_OPERATIONS.contains(&op.hqslang())
+                    {
+                        definitions.push('\n');
+                    }
+                }
+            }
+            if inline_definitions {
+                data.push_str(&definitions.split_off(definitions_start));
+            }
+            if translation == CircuitTranslation::Definitions {
+                continue;
+            }
+            // Appending operation QASM instruction
+            let (chunk, has_instruction) =
+                self.data_chunk(op, &mut variable_gatherer, &mut box_depth, data.is_empty())?;
+            if has_instruction {
+                number_gates += 1;
+            }
+            if collect_data {
+                data.push_str(&chunk);
+            }
+        }
+
+        if box_depth > 0 {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "{} decomposition blocks are not closed by a PragmaStopDecompositionBlock and can not be exported as box blocks",
+                    box_depth
+                ),
+            });
+        }
+
+        // Building the final string: QASM version + definitions + parameters + registers + circuit data
+        let register_size = self.qubit_register_size(number_qubits_required)?;
+        if collect_data && self.collapses_register_resets() {
+            data = collapse_register_resets(&data, &self.qubit_register_name, register_size);
+        }
+        if self.verbose {
+            qasm_string.push_str(&format!(
+                "// qubits: {}, gates: {}\n\n",
+                register_size, number_gates
+            ));
+        }
+        match self.qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Braket)
+            | QasmVersion::V3point0(Qasm3Dialect::IonQ) => {}
+            QasmVersion::V2point0(Qasm2Dialect::Qulacs) => {
+                qasm_string.push_str("include \"qelib1.inc\";\n\n")
+            }
+            _ if stdgates_include => {
+                qasm_string.push_str("include \"stdgates.inc\";\n\n");
+                qasm_string.push_str(definitions.as_str());
+            }
+            _ if qelib_include => {
+                qasm_string.push_str("include \"qelib1.inc\";\n\n");
+                qasm_string.push_str(definitions.as_str());
+            }
+            _ => qasm_string.push_str(definitions.as_str()),
+        };
+
+        if let QasmVersion::V3point0(_) = self.qasm_version {
+            if !variable_gatherer.variables.is_empty() {
+                qasm_string.push('\n');
+                for var in &variable_gatherer.variables {
+                    match variable_gatherer.variable_kind(var) {
+                        Some(VariableKind::Float) => {
+                            qasm_string.push_str(format!("input float[64] {};\n", var).as_str())
+                        }
+                        _ => {
+                            let width = self.angle_widths.get(var).copied().unwrap_or(32);
+                            qasm_string
+                                .push_str(format!("input angle[{}] {};\n", width, var).as_str());
+                        }
+                    }
+                }
+                qasm_string.push('\n');
+            }
+        }
+        match (&self.register_mapping, self.qasm_version) {
+            _ if self.omit_unused_qubit_register && !uses_qubits => qasm_string.push('\n'),
+            (Some(register_mapping), _) => {
+                qasm_string.push_str(&self.mapped_register_declarations(register_mapping))
+            }
+            (None, QasmVersion::V2point0(_)) => qasm_string.push_str(
+                format!(
+                    "\nqreg {}[{}];\n\n",
+                    self.qubit_register_name, register_size,
+                )
+                .as_str(),
+            ),
+            (None, QasmVersion::V3point0(_)) => qasm_string.push_str(
+                format!(
+                    "\nqubit[{}] {};\n\n",
+                    register_size, self.qubit_register_name,
+                )
+                .as_str(),
+            ),
+        }
+
+        if let Some(blank_lines) = self.section_blank_lines {
+            qasm_string = normalize_blank_lines(&qasm_string, blank_lines);
+        }
+
+        Ok((qasm_string, data, gate_definitions))
+    }
+
+    /// Returns the number of `gate` definitions the QASM output of a Circuit contains.
+    ///
+    /// Some QASM parsers limit the number of custom gate definitions, the count can be checked
+    /// against such a limit before submitting the output. The definitions that are always added
+    /// to the output are counted as well, `opaque` declarations are not counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The number of distinct gate definitions in the QASM output
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn definition_count(&self, circuit: &Circuit) -> Result<usize, RoqoqoBackendError> {
+        let (preamble, _, _) =
+            self.translate_circuit_iterator(circuit.iter(), CircuitTranslation::Preamble)?;
+        Ok(preamble
+            .lines()
+            .filter(|line| line.starts_with("gate "))
+            .count())
+    }
+
+    /// Returns the QASM translation of every operation of a Circuit.
+    ///
+    /// Each entry contains the index of the operation in the circuit, its hqslang name and its
+    /// QASM translation, which is empty for operations that are dropped from the output. Operations
+    /// can translate to several QASM lines. The translation of each operation is the same as in
+    /// the full QASM output, except that register resets are not collapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<(usize, String, String)>)` - The index, hqslang and QASM translation of each operation
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn operation_qasm_map(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<Vec<(usize, String, String)>, RoqoqoBackendError> {
+        circuit
+            .iter()
+            .enumerate()
+            .map(|(index, op)| {
+                Ok((
+                    index,
+                    op.hqslang().to_string(),
+                    self.translate_operation(op, &mut None)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns how often each QASM gate is used in the translation of a Circuit.
+    ///
+    /// The gates are identified by the name each translated QASM statement starts with, e.g. `cx`
+    /// or `rz`, so a quick resource estimate can be made without building the full QASM output.
+    /// The `ctrl @` and `inv @` modifiers are removed from the name, measurements and resets are
+    /// counted as `measure` and `reset`. Declarations, control flow, barriers, pragmas and
+    /// comments are skipped, the gates in a loop body are counted as often as they appear in the
+    /// output. Operations that are dropped from the output are not counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HashMap<String, usize>)` - The number of occurrences of each QASM gate name
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn gate_statistics(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<HashMap<String, usize>, RoqoqoBackendError> {
+        let mut statistics: HashMap<String, usize> = HashMap::new();
+        for op in circuit.iter() {
+            let translation = self.translate_operation(op, &mut None)?;
+            let mut lines = translation.lines();
+            while let Some(line) = lines.next() {
+                // The pragmas of the Roqoqo dialect can span several lines up to their semicolon
+                if line.trim_start().starts_with("pragma ") {
+                    if !line.trim_end().ends_with(';') {
+                        for pragma_line in lines.by_ref() {
+                            if pragma_line.trim_end().ends_with(';') {
+                                break;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                if let Some(gate_name) = statement_gate_name(line) {
+                    *statistics.entry(gate_name.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// Returns the symbolic parameters of a Circuit that are declared as inputs of the QASM output.
+    ///
+    /// These are the parameters of the `input` declarations of OpenQASM 3.0, OpenQASM 2.0 has no
+    /// input declarations and no parameters are gathered.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HashSet<String>)` - The names of the symbolic parameters
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - A symbolic expression is not supported by the QASM version
+    pub fn gather_variables(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<HashSet<String>, RoqoqoBackendError> {
+        let mut variable_gatherer = self.variable_gatherer();
+        for op in circuit.iter() {
+            self.translate_operation(op, &mut Some(&mut variable_gatherer))?;
+        }
+        Ok(variable_gatherer.variables)
+    }
+
+    /// Returns the gate definitions the QASM output of a Circuit requires.
+    ///
+    /// Each definition is listed once, in the order the operations needing it first appear in
+    /// the circuit. The definitions that are always added to the output and operations without
+    /// a definition are not listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - The deduplicated gate definitions required by the circuit
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn required_gate_definitions(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<Vec<String>, RoqoqoBackendError> {
+        let (_, _, gate_definitions) =
+            self.translate_circuit_iterator(circuit.iter(), CircuitTranslation::Preamble)?;
+        Ok(gate_definitions)
+    }
+
+    /// Returns an estimate of the size in bytes of the QASM output of a Circuit.
+    ///
+    /// Only the gate definitions and register declarations of the preamble are translated, the
+    /// size of the circuit data is estimated from the operations without translating them, which
+    /// is cheaper than [Backend::circuit_to_qasm_str] for large circuits. The input declarations
+    /// of symbolic parameters are not included. The estimate can be used to preallocate buffers
+    /// or to check size limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The approximate number of bytes of the QASM output
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions in the circuit
+    pub fn estimated_output_size(&self, circuit: &Circuit) -> Result<usize, RoqoqoBackendError> {
+        let (preamble, _, _) =
+            self.translate_circuit_iterator(circuit.iter(), CircuitTranslation::Definitions)?;
+        let data_size: usize = circuit
+            .iter()
+            .map(|op| estimated_instruction_length(op, &self.qubit_register_name))
+            .sum();
+        let end_marker_size = if self.end_marker { END_MARKER.len() } else { 0 };
+        Ok(preamble.len() + data_size + end_marker_size)
+    }
+
+    /// Checks a Circuit for common issues that lead to invalid QASM output.
+    ///
+    /// The checks cover classical registers that are used or measured into without being
+    /// declared, duplicate register declarations and gate definitions, and defined gates that
+    /// are called before their definition. The circuit is not translated, so the lint also
+    /// works for circuits that the backend would reject.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is checked
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The warnings found in the circuit, empty if no issue was found
+    pub fn lint(&self, circuit: &Circuit) -> Vec<String> {
+        let mut warnings: Vec<String> = Vec::new();
+        lint_operations(
+            circuit.iter(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut HashSet::new(),
+            &mut warnings,
+        );
+        warnings
+    }
+
+    /// Translates a Circuit to QASM and streams the output to a writer.
+    ///
+    /// The circuit is traversed twice: once to collect the gate definitions, input parameters and
+    /// register sizes that precede the circuit data, and once to write the translated operations
+    /// one by one. The full QASM output is never held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    /// * `writer` - The writer the QASM output is written to
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The QASM output was correctly written
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Error while writing to the writer
+    pub fn circuit_to_qasm_writer<W: Write>(
+        &self,
+        circuit: &Circuit,
+        writer: W,
+    ) -> Result<(), RoqoqoBackendError> {
+        self.circuit_iterator_to_writer(
+            circuit
+                .definitions()
+                .iter()
+                .chain(circuit.operations().iter()),
+            writer,
+        )
+    }
+
+    /// Translates an iterator over operations to QASM and streams the output to a writer.
     ///
+    /// The iterator is cloned to traverse the operations twice: once to collect the gate
+    /// definitions, input parameters and register sizes that precede the circuit data, and once
+    /// to write the translated operations one by one.
     ///
     /// # Arguments
     ///
-    /// * `circuit` - The iterator over [roqoqo::Operation] items that is translated
+    /// * `circuit` - The re-iterable iterator over [roqoqo::Operation] items that is translated
+    /// * `writer` - The writer the QASM output is written to
     ///
     /// # Returns
     ///
-    /// * `Ok(String)` - The valid QASM string
+    /// * `Ok(())` - The QASM output was correctly written
     /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
-    pub fn circuit_iterator_to_qasm_str<'a>(
+    /// * `RoqoqoBackendError::GenericError` - Error while writing to the writer
+    pub fn circuit_iterator_to_writer<'a, W: Write>(
         &self,
-        circuit: impl Iterator<Item = &'a Operation>,
-    ) -> Result<String, RoqoqoBackendError> {
-        // Initializing data structures
-        let mut definitions: String = "".to_string();
-        let mut data: String = "".to_string();
-        let mut number_qubits_required: usize = 0;
-        let mut already_seen_definitions: Vec<String> = vec![
-            "RotateX".to_string(),
-            "RotateY".to_string(),
-            "RotateZ".to_string(),
-            "CNOT".to_string(),
-        ];
-        let mut variable_gatherer = VariableGatherer::new();
+        circuit: impl Iterator<Item = &'a Operation> + Clone,
+        writer: W,
+    ) -> Result<(), RoqoqoBackendError> {
+        let (preamble, _, _) =
+            self.translate_circuit_iterator(circuit.clone(), CircuitTranslation::Preamble)?;
+        self.write_qasm(circuit, &preamble, writer)
+    }
 
-        // Appending QASM version
-        let mut qasm_string = String::from("OPENQASM ");
-        match self.qasm_version {
-            QasmVersion::V2point0(_) => qasm_string.push_str("2.0;\n\n"),
-            QasmVersion::V3point0(_) => qasm_string.push_str("3.0;\n\n"),
+    /// Writes an already translated QASM preamble followed by the translated circuit operations.
+    fn write_qasm<'a, W: Write>(
+        &self,
+        circuit: impl Iterator<Item = &'a Operation> + Clone,
+        preamble: &str,
+        mut writer: W,
+    ) -> Result<(), RoqoqoBackendError> {
+        let write_error = |err: std::io::Error| RoqoqoBackendError::GenericError {
+            msg: format!("Error while writing QASM output: {}", err),
+        };
+        // The inline gate definitions are only known once the whole circuit is translated
+        if self.definitions_inline {
+            writer
+                .write_all(self.circuit_iterator_to_qasm_str(circuit)?.as_bytes())
+                .map_err(write_error)?;
+            return writer.flush().map_err(write_error);
         }
+        writer.write_all(preamble.as_bytes()).map_err(write_error)?;
 
-        // Appending definitions that are always needed (some depend on QASM version)
-        definitions.push_str("gate u3(theta,phi,lambda) q { U(theta,phi,lambda) q; }\n");
-        definitions.push_str("gate u2(phi,lambda) q { U(pi/2,phi,lambda) q; }\n");
-        definitions.push_str("gate u1(lambda) q { U(0,0,lambda) q; }\n");
-        definitions.push_str(&gate_definition(
-            &Operation::from(RotateX::new(0, CalculatorFloat::from(0.0))),
-            self.qasm_version,
-        )?);
-        definitions.push('\n');
-        definitions.push_str(&gate_definition(
-            &Operation::from(RotateY::new(0, CalculatorFloat::from(0.0))),
-            self.qasm_version,
-        )?);
-        definitions.push('\n');
-        definitions.push_str(&gate_definition(
-            &Operation::from(RotateZ::new(0, CalculatorFloat::from(0.0))),
-            self.qasm_version,
-        )?);
-        definitions.push('\n');
-        definitions.push_str(&gate_definition(
-            &Operation::from(CNOT::new(0, 1)),
-            self.qasm_version,
-        )?);
-        definitions.push_str("\n\n");
-
-        // Main loop over the circuit
-        for op in circuit {
-            // Taking note of the maximum number of qubits involved in the circuit for registers definition
-            if let InvolvedQubits::Set(involved_qubits) = op.involved_qubits() {
-                number_qubits_required =
-                    number_qubits_required.max(match involved_qubits.iter().max() {
-                        None => 0,
-                        Some(n) => *n,
-                    })
-            }
-
-            // Appending gate definition if not already seen before
-            if !already_seen_definitions.contains(&op.hqslang().to_string()) {
-                let mut continue_process = false;
-                if let Operation::GateDefinition(gate_definition) = op {
-                    if !already_seen_definitions.contains(gate_definition.name()) {
-                        already_seen_definitions.push(gate_definition.name().to_owned());
-                        continue_process = true;
-                    }
-                } else {
-                    already_seen_definitions.push(op.hqslang().to_string());
-                    continue_process = true;
-                }
-
-                if continue_process {
-                    match op {
-                        Operation::GateDefinition(gate_definition) => process_operation_circuit(
-                            gate_definition.circuit().iter(),
-                            self.qasm_version,
-                            &mut already_seen_definitions,
-                            &mut definitions,
-                        )?,
-                        Operation::PragmaConditional(pragma_conditional) => {
-                            process_operation_circuit(
-                                pragma_conditional.circuit().iter(),
-                                self.qasm_version,
-                                &mut already_seen_definitions,
-                                &mut definitions,
-                            )?
-                        }
-                        Operation::PragmaLoop(pragma_loop) => process_operation_circuit(
-                            pragma_loop.circuit().iter(),
-                            self.qasm_version,
-                            &mut already_seen_definitions,
-                            &mut definitions,
-                        )?,
-                        _ => {}
-                    }
-                    definitions.push_str(&gate_definition(op, self.qasm_version)?);
-                    if !definitions.is_empty()
-                        && !NO_DEFINITION_REQUIRED_OPERATIONS.contains(&op.hqslang())
-                    {
-                        definitions.push('\n');
-                    }
-                }
-            }
-            // Appending operation QASM instruction
-            data.push_str(&call_operation(
-                op,
+        // Consecutive resets are held back until it is known whether they cover the register
+        let mut collapser = if self.collapses_register_resets() {
+            let max_qubit_index = circuit
+                .clone()
+                .filter_map(|op| match op.involved_qubits() {
+                    InvolvedQubits::Set(involved_qubits) => involved_qubits.into_iter().max(),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+            Some(RegisterResetCollapser::new(
                 &self.qubit_register_name,
-                self.qasm_version,
-                &mut Some(&mut variable_gatherer),
-            )?);
-
-            if !data.is_empty() && !ALLOWED_OPERATIONS.contains(&op.hqslang()) {
-                data.push('\n');
-            }
-        }
-
-        // Building the final string: QASM version + definitions + parameters + registers + circuit data
-        match self.qasm_version {
-            QasmVersion::V3point0(Qasm3Dialect::Braket) => {}
-            QasmVersion::V2point0(Qasm2Dialect::Qulacs) => {
-                qasm_string.push_str("include \"qelib1.inc\";\n\n")
-            }
-            _ => qasm_string.push_str(definitions.as_str()),
+                self.qubit_register_size(max_qubit_index)?,
+            ))
+        } else {
+            None
         };
-
-        if let QasmVersion::V3point0(_) = self.qasm_version {
-            if !variable_gatherer.variables.is_empty() {
-                qasm_string.push('\n');
-                for var in &variable_gatherer.variables {
-                    qasm_string.push_str(format!("input angle[32] {};\n", var).as_str());
-                }
-                qasm_string.push('\n');
+        let mut variable_gatherer = self.variable_gatherer();
+        let mut box_depth: usize = 0;
+        let mut data_is_empty = true;
+        let mut ends_with_newline = true;
+        for op in circuit {
+            let (chunk, _) =
+                self.data_chunk(op, &mut variable_gatherer, &mut box_depth, data_is_empty)?;
+            if chunk.is_empty() {
+                continue;
             }
+            data_is_empty = false;
+            ends_with_newline = chunk.ends_with('\n');
+            let output = match collapser.as_mut() {
+                Some(collapser) => collapser.push(&chunk),
+                None => chunk,
+            };
+            writer.write_all(output.as_bytes()).map_err(write_error)?;
         }
-        match self.qasm_version {
-            QasmVersion::V2point0(_) => qasm_string.push_str(
-                format!(
-                    "\nqreg {}[{}];\n\n",
-                    self.qubit_register_name,
-                    number_qubits_required + 1,
-                )
-                .as_str(),
-            ),
-            QasmVersion::V3point0(_) => qasm_string.push_str(
-                format!(
-                    "\nqubit[{}] {};\n\n",
-                    number_qubits_required + 1,
-                    self.qubit_register_name,
-                )
-                .as_str(),
-            ),
+        if let Some(collapser) = collapser {
+            writer
+                .write_all(collapser.finish().as_bytes())
+                .map_err(write_error)?;
         }
-        qasm_string.push_str(data.as_str());
-
-        Ok(qasm_string)
+        if self.end_marker {
+            if !ends_with_newline {
+                writer.write_all(b"\n").map_err(write_error)?;
+            }
+            writer
+                .write_all(END_MARKER.as_bytes())
+                .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)
     }
 
     /// Translates an iterator over operations to a QASM file.
@@ -292,6 +1886,9 @@ impl Backend {
         } else {
             let f = File::create(output_path).expect("Unable to create file");
             let mut f = BufWriter::new(f);
+            if self.utf8_bom {
+                f.write_all(UTF8_BOM).expect("Unable to write file")
+            }
             f.write_all(data.as_bytes()).expect("Unable to write file")
         }
 
@@ -313,8 +1910,273 @@ impl Backend {
         self.circuit_iterator_to_qasm_str(circuit.iter())
     }
 
+    /// Translates the circuits of a QuantumProgram to QASM.
+    ///
+    /// Every measurement circuit is preceded by the constant circuit of the measurement and
+    /// translated to a complete QASM program, in the order of the measurement circuits.
+    ///
+    /// # Arguments
+    ///
+    /// * `quantum_program` - The QuantumProgram that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - One QASM program per measurement circuit
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Invalid register definitions or unsupported QuantumProgram
+    pub fn quantum_program_to_qasm_strings(
+        &self,
+        quantum_program: &QuantumProgram,
+    ) -> Result<Vec<String>, RoqoqoBackendError> {
+        let (constant_circuit, circuits): (&Option<Circuit>, Vec<&Circuit>) = match quantum_program
+        {
+            QuantumProgram::PauliZProduct { measurement, .. } => (
+                measurement.constant_circuit(),
+                measurement.circuits().collect(),
+            ),
+            QuantumProgram::CheatedPauliZProduct { measurement, .. } => (
+                measurement.constant_circuit(),
+                measurement.circuits().collect(),
+            ),
+            QuantumProgram::Cheated { measurement, .. } => (
+                measurement.constant_circuit(),
+                measurement.circuits().collect(),
+            ),
+            QuantumProgram::ClassicalRegister { measurement, .. } => (
+                measurement.constant_circuit(),
+                measurement.circuits().collect(),
+            ),
+            _ => {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: "QuantumProgram variant not supported by the QASM backend".to_string(),
+                })
+            }
+        };
+        circuits
+            .into_iter()
+            .map(|circuit| {
+                let full_circuit = match constant_circuit {
+                    Some(constant_circuit) => constant_circuit.clone() + circuit,
+                    None => circuit.clone(),
+                };
+                self.circuit_to_qasm_str(&full_circuit)
+            })
+            .collect()
+    }
+
+    /// Checks that the QASM output of a Circuit can be imported back.
+    ///
+    /// The Circuit is translated to QASM and the output is parsed again. Since the imported
+    /// Circuit differs in its definitions and pragmas, only the gate operations are compared.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - Whether the imported Circuit contains the same gate operations in the same order
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Error during the translation or the import
+    #[cfg(feature = "unstable_qasm_import")]
+    pub fn verify_roundtrip(&self, circuit: &Circuit) -> Result<bool, RoqoqoBackendError> {
+        // The measurement pragmas differ between the dialects and are not imported
+        let mut in_pragma = false;
+        let qasm_string: String = self
+            .circuit_to_qasm_str(circuit)?
+            .lines()
+            .filter(|line| {
+                let statement = line.trim_start();
+                if statement.starts_with("#pragma") {
+                    return false;
+                }
+                if statement.starts_with("pragma ") {
+                    in_pragma = true;
+                }
+                let keep = !in_pragma;
+                if in_pragma && statement.trim_end().ends_with(';') {
+                    in_pragma = false;
+                }
+                keep
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let imported = self.string_to_circuit(&qasm_string)?;
+        let gate_operations = |circuit: &Circuit| -> Vec<Operation> {
+            circuit
+                .iter()
+                .filter(|op| op.tags().contains(&"GateOperation"))
+                .cloned()
+                .collect()
+        };
+        Ok(gate_operations(circuit) == gate_operations(&imported))
+    }
+
+    /// Translates a Circuit to a valid QASM string declaring aliases for qubit subsets.
+    ///
+    /// Each alias is declared as `let name = q[start:end];` after the qubit register declaration,
+    /// in alphabetical order. The gate calls are not rewritten to use the aliases.
+    /// Aliases are only available for the vanilla OpenQASM 3.0 dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit items that is translated
+    /// * `aliases` - The names of the aliases and the (exclusive) ranges of qubits they refer to
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The valid QASM string
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Aliases are not supported for the QASM version or an alias range is empty
+    pub fn circuit_to_qasm_str_with_aliases(
+        &self,
+        circuit: &Circuit,
+        aliases: &HashMap<String, Range<usize>>,
+    ) -> Result<String, RoqoqoBackendError> {
+        if self.qasm_version != QasmVersion::V3point0(Qasm3Dialect::Vanilla) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Qubit aliases are only supported for vanilla OpenQASM 3.0, not for {:?}",
+                    self.qasm_version
+                ),
+            });
+        }
+        let mut names: Vec<&String> = aliases.keys().collect();
+        names.sort();
+
+        let (mut qasm_string, data, _) =
+            self.translate_circuit_iterator(circuit.iter(), CircuitTranslation::Full)?;
+        for name in names {
+            let range = &aliases[name];
+            if range.is_empty() {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!("The qubit range of the alias {} is empty", name),
+                });
+            }
+            qasm_string.push_str(&format!(
+                "let {} = {}[{}:{}];\n",
+                name,
+                self.qubit_register_name,
+                range.start,
+                range.end - 1
+            ));
+        }
+        if !aliases.is_empty() {
+            qasm_string.push('\n');
+        }
+        qasm_string.push_str(data.as_str());
+        self.push_end_marker(&mut qasm_string);
+
+        Ok(qasm_string)
+    }
+
+    /// Translates a Circuit to a valid QASM string containing OpenPulse calibration blocks.
+    ///
+    /// The `defcalgrammar "openpulse";` line and the calibration blocks are inserted verbatim after
+    /// the OpenQASM header, the blocks are not checked. Calibrations are only available for the
+    /// vanilla OpenQASM 3.0 dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit items that is translated
+    /// * `cal_blocks` - The `cal { ... }` blocks that are inserted
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The valid QASM string
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - Calibrations are not supported for the QASM version
+    pub fn circuit_to_qasm_str_with_cal(
+        &self,
+        circuit: &Circuit,
+        cal_blocks: &[String],
+    ) -> Result<String, RoqoqoBackendError> {
+        if self.qasm_version != QasmVersion::V3point0(Qasm3Dialect::Vanilla) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "Calibration blocks are only supported for vanilla OpenQASM 3.0, not for {:?}",
+                    self.qasm_version
+                ),
+            });
+        }
+        let mut calibrations = String::from("defcalgrammar \"openpulse\";\n");
+        for cal_block in cal_blocks {
+            calibrations.push_str(cal_block);
+            calibrations.push('\n');
+        }
+        let blank_lines = self.section_blank_lines.unwrap_or(1);
+        calibrations.push_str(&"\n".repeat(blank_lines));
+
+        let mut qasm_string = self.circuit_to_qasm_str(circuit)?;
+        // The calibrations follow the header and the comments directly after it
+        let mut position = 0;
+        for (index, line) in qasm_string.split_inclusive('\n').enumerate() {
+            let line_content = line.trim();
+            if index > 0 && !line_content.is_empty() && !line_content.starts_with("//") {
+                break;
+            }
+            position += line.len();
+        }
+        qasm_string.insert_str(position, &calibrations);
+
+        Ok(qasm_string)
+    }
+
+    /// Translates a Circuit to a valid QASM string with the qubits permuted.
+    ///
+    /// The logical qubit `i` of the circuit is emitted as the qubit `perm[i]` of the register,
+    /// for example to apply the result of a routing to a device.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit items that is translated
+    /// * `perm` - The permutation of the qubits, covering all qubits used in the circuit
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The valid QASM string
+    /// * `RoqoqoBackendError::OperationNotInBackend` - An operation is not available on the backend
+    /// * `RoqoqoBackendError::GenericError` - `perm` is not a permutation or does not cover the qubits of the circuit
+    pub fn circuit_to_qasm_str_permuted(
+        &self,
+        circuit: &Circuit,
+        perm: &[usize],
+    ) -> Result<String, RoqoqoBackendError> {
+        let mut seen = vec![false; perm.len()];
+        for &target in perm {
+            if target >= perm.len() || seen[target] {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "{:?} is not a permutation of the qubits 0..{}",
+                        perm,
+                        perm.len()
+                    ),
+                });
+            }
+            seen[target] = true;
+        }
+        for op in circuit.iter() {
+            if let InvolvedQubits::Set(involved_qubits) = op.involved_qubits() {
+                if let Some(qubit) = involved_qubits.iter().find(|&&qubit| qubit >= perm.len()) {
+                    return Err(RoqoqoBackendError::GenericError {
+                        msg: format!(
+                            "The permutation of {} qubits does not cover qubit {} used by {}",
+                            perm.len(),
+                            qubit,
+                            op.hqslang()
+                        ),
+                    });
+                }
+            }
+        }
+        let mapping: HashMap<usize, usize> = perm.iter().copied().enumerate().collect();
+        self.circuit_to_qasm_str(&circuit.remap_qubits(&mapping)?)
+    }
+
     /// Translates a Circuit to a QASM file.
     ///
+    /// The QASM output is streamed to the file without building the full string in memory.
+    ///
     /// # Arguments
     ///
     /// * `circuit` - The Circuit that is translated
@@ -333,7 +2195,106 @@ impl Backend {
         filename: &Path,
         overwrite: bool,
     ) -> Result<(), RoqoqoBackendError> {
-        self.circuit_iterator_to_qasm_file(circuit.iter(), folder_name, filename, overwrite)
+        let output_path: PathBuf = folder_name.join(filename.with_extension("qasm"));
+        if output_path.is_file() && !overwrite {
+            return Err(RoqoqoBackendError::FileAlreadyExists {
+                path: output_path.to_str().unwrap().to_string(),
+            });
+        }
+        // The preamble pass translates every operation, so no file is created on translation errors
+        let (preamble, _, _) =
+            self.translate_circuit_iterator(circuit.iter(), CircuitTranslation::Preamble)?;
+        let f = File::create(output_path).map_err(|err| RoqoqoBackendError::GenericError {
+            msg: format!("Error while writing QASM output: {}", err),
+        })?;
+        let mut writer = BufWriter::new(f);
+        if self.utf8_bom {
+            writer
+                .write_all(UTF8_BOM)
+                .map_err(|err| RoqoqoBackendError::GenericError {
+                    msg: format!("Error while writing QASM output: {}", err),
+                })?;
+        }
+        self.write_qasm(
+            circuit
+                .definitions()
+                .iter()
+                .chain(circuit.operations().iter()),
+            &preamble,
+            writer,
+        )
+    }
+
+    /// Translates a Circuit to a zip archive with a separate file for every custom gate definition.
+    ///
+    /// The archive contains the main QASM program and one `<name>.inc` file for every
+    /// GateDefinition of the circuit. The main program includes the `.inc` files in place of the
+    /// gate definitions, so that the gates they use are defined before.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The Circuit that is translated
+    /// * `folder_name` - The name of the folder that is prepended to all filenames.
+    /// * `filename` - The name of the archive, the main program is saved under the same name in the archive.
+    /// * `overwrite` - Whether to overwrite file if it already exists.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The zip archive was correctly written
+    /// * `RoqoqoBackendError::FileAlreadyExists` - The file at this location already exists
+    /// * `RoqoqoBackendError::GenericError` - The archive could not be written
+    #[cfg(feature = "zip_archive")]
+    pub fn circuit_to_qasm_archive(
+        &self,
+        circuit: &Circuit,
+        folder_name: &Path,
+        filename: &Path,
+        overwrite: bool,
+    ) -> Result<(), RoqoqoBackendError> {
+        let output_path: PathBuf = folder_name.join(filename.with_extension("zip"));
+        if output_path.is_file() && !overwrite {
+            return Err(RoqoqoBackendError::FileAlreadyExists {
+                path: output_path.to_str().unwrap().to_string(),
+            });
+        }
+        let mut qasm_string = self.circuit_to_qasm_str(circuit)?;
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for op in circuit.iter() {
+            if let Operation::GateDefinition(gate_definition) = op {
+                let definition =
+                    gate_definition_with_options(op, self.qasm_version, &self.options)?;
+                let entry_name = format!("{}.inc", gate_definition.name());
+                if definition.is_empty() || entries.iter().any(|(name, _)| name == &entry_name) {
+                    continue;
+                }
+                qasm_string =
+                    qasm_string.replacen(&definition, &format!("include \"{}\";", entry_name), 1);
+                entries.push((entry_name, format!("{}\n", definition)));
+            }
+        }
+        let main_name = filename
+            .with_extension("qasm")
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("main.qasm")
+            .to_string();
+        entries.insert(0, (main_name, qasm_string));
+
+        let zip_error = |err: zip::result::ZipError| RoqoqoBackendError::GenericError {
+            msg: format!("Could not write the zip archive: {}", err),
+        };
+        let f = File::create(output_path).map_err(|err| zip_error(err.into()))?;
+        let mut archive = zip::ZipWriter::new(f);
+        let file_options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in entries {
+            archive.start_file(name, file_options).map_err(zip_error)?;
+            archive
+                .write_all(content.as_bytes())
+                .map_err(|err| zip_error(err.into()))?;
+        }
+        archive.finish().map_err(zip_error)?;
+        Ok(())
     }
 
     /// Translates a QASM file into a qoqo Circuit instance.
@@ -392,6 +2353,10 @@ pub enum Qasm3Dialect {
     Roqoqo,
     /// With Braket's Pragma operations
     Braket,
+    /// Following the conventions of Qiskit's OpenQASM 3.0 exporter, always including `stdgates.inc`
+    Qiskit,
+    /// With IonQ's native gates only
+    IonQ,
 }
 
 impl FromStr for QasmVersion {
@@ -403,10 +2368,15 @@ impl FromStr for QasmVersion {
             "2.0Qulacs" => Ok(QasmVersion::V2point0(Qasm2Dialect::Qulacs)),
             "3.0Roqoqo" => Ok(QasmVersion::V3point0(Qasm3Dialect::Roqoqo)),
             "3.0Braket" => Ok(QasmVersion::V3point0(Qasm3Dialect::Braket)),
+            "3.0Qiskit" => Ok(QasmVersion::V3point0(Qasm3Dialect::Qiskit)),
+            "3.0IonQ" => Ok(QasmVersion::V3point0(Qasm3Dialect::IonQ)),
             "3.0Vanilla" => Ok(QasmVersion::V3point0(Qasm3Dialect::Vanilla)),
-            "3.0" => Ok(QasmVersion::V3point0(Qasm3Dialect::Vanilla)),
+            "3.0" | "3" => Ok(QasmVersion::V3point0(Qasm3Dialect::Vanilla)),
             _ => Err(RoqoqoBackendError::GenericError {
-                msg: format!("Version for OpenQASM used is neither 2.0 nor 3.0: {}", s),
+                msg: format!(
+                    "Version for OpenQASM used is neither 2.0 nor 3.0: {}. Valid versions are 2.0, 2.0Vanilla, 2.0Qulacs, 3, 3.0, 3.0Vanilla, 3.0Roqoqo, 3.0Braket, 3.0Qiskit and 3.0IonQ",
+                    s
+                ),
             }),
         }
     }
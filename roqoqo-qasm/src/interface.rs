@@ -16,11 +16,14 @@ use qoqo_calculator::{CalculatorComplex, CalculatorFloat};
 use roqoqo::operations::*;
 use roqoqo::Circuit;
 use roqoqo::RoqoqoBackendError;
+use serde::Serialize;
+use std::collections::HashMap;
 
 use crate::Qasm2Dialect;
 use crate::Qasm3Dialect;
 use crate::QasmVersion;
 use crate::VariableGatherer;
+use crate::VariableKind;
 
 // Operations that are ignored by backend and do not throw an error
 pub(crate) const ALLOWED_OPERATIONS: &[&str; 12] = &[
@@ -38,8 +41,16 @@ pub(crate) const ALLOWED_OPERATIONS: &[&str; 12] = &[
     "GateDefinition",
 ];
 
+// Operations reading out simulator results, they are handled by the measurements of a QuantumProgram
+pub(crate) const MEASUREMENT_READOUT_OPERATIONS: &[&str; 4] = &[
+    "PragmaGetDensityMatrix",
+    "PragmaGetOccupationProbability",
+    "PragmaGetPauliProduct",
+    "PragmaGetStateVector",
+];
+
 // Operations that are ignored when looking for a QASM definition
-pub(crate) const NO_DEFINITION_REQUIRED_OPERATIONS: &[&str; 12] = &[
+pub(crate) const NO_DEFINITION_REQUIRED_OPERATIONS: &[&str; 13] = &[
     "SingleQubitGate",
     "DefinitionFloat",
     "DefinitionUsize",
@@ -52,6 +63,7 @@ pub(crate) const NO_DEFINITION_REQUIRED_OPERATIONS: &[&str; 12] = &[
     "MeasureQubit",
     "PragmaLoop",
     "CallDefinedGate",
+    "PragmaControlledCircuit",
 ];
 
 // Operations that are supported for Qulacs QASM version
@@ -79,6 +91,356 @@ pub(crate) const QULCAS_SUPPORTED_OPERATIONS: &[&str; 21] = &[
     "RotateXY",
 ];
 
+// Gates that are native to the Braket dialect and do not need a gate definition
+pub(crate) const BRAKET_NATIVE_GATES: &[&str; 30] = &[
+    "RotateX",
+    "RotateY",
+    "RotateZ",
+    "Hadamard",
+    "PauliX",
+    "PauliY",
+    "PauliZ",
+    "SGate",
+    "InvSGate",
+    "TGate",
+    "InvTGate",
+    "PhaseShiftState1",
+    "SqrtPauliX",
+    "CNOT",
+    "MolmerSorensenXX",
+    "VariableMSXX",
+    "ControlledPauliY",
+    "ControlledPauliZ",
+    "ControlledPhaseShift",
+    "SWAP",
+    "ISwap",
+    "EchoCrossResonance",
+    "XY",
+    "Toffoli",
+    "ControlledControlledPauliZ",
+    "ControlledControlledPhaseShift",
+    "MultiQubitZZ",
+    "MultiQubitMS",
+    "GPi",
+    "GPi2",
+];
+
+// Gates that are native to IonQ hardware, all other gates need to be decomposed beforehand
+pub(crate) const IONQ_NATIVE_GATES: &[&str; 3] = &["GPi", "GPi2", "MolmerSorensenXX"];
+
+// Names of the gates that the OpenQASM 3.0 stdgates.inc library defines, by hqslang of the operation.
+// The gates are emitted under these names and are not defined when the library is included.
+pub(crate) const STDGATES_NAMES: &[(&str, &str); 21] = &[
+    ("RotateX", "rx"),
+    ("RotateY", "ry"),
+    ("RotateZ", "rz"),
+    ("Hadamard", "h"),
+    ("PauliX", "x"),
+    ("PauliY", "y"),
+    ("PauliZ", "z"),
+    ("SGate", "s"),
+    ("InvSGate", "sdg"),
+    ("TGate", "t"),
+    ("InvTGate", "tdg"),
+    ("PhaseShiftState1", "p"),
+    ("SqrtPauliX", "sx"),
+    ("Identity", "id"),
+    ("CNOT", "cx"),
+    ("ControlledPauliY", "cy"),
+    ("ControlledPauliZ", "cz"),
+    ("ControlledPhaseShift", "cp"),
+    ("ControlledRotateX", "crx"),
+    ("SWAP", "swap"),
+    ("Toffoli", "ccx"),
+];
+
+// Operations emitted as gates that the OpenQASM 2.0 qelib1.inc library defines.
+// The gates are not defined when the library is included.
+pub(crate) const QELIB_GATES: &[&str; 16] = &[
+    "RotateX",
+    "RotateY",
+    "RotateZ",
+    "Hadamard",
+    "PauliX",
+    "PauliY",
+    "PauliZ",
+    "SGate",
+    "InvSGate",
+    "TGate",
+    "InvTGate",
+    "Identity",
+    "CNOT",
+    "ControlledPauliY",
+    "ControlledPauliZ",
+    "Toffoli",
+];
+
+/// Options changing how qoqo operations are translated to QASM.
+///
+/// The default options reproduce the output of [call_operation].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QasmOptions {
+    /// Whether CNOT is emitted as the OpenQASM 2.0 built-in `CX` instead of the defined `cx` gate.
+    pub builtin_cnot: bool,
+    /// Whether the pragmas of the Roqoqo dialect are serialized as JSON, so that they can be parsed back.
+    pub json_pragmas: bool,
+    /// The maximum nesting depth of PragmaConditional and PragmaLoop operations.
+    pub max_recursion_depth: usize,
+    /// Whether OpenQASM 3.0 output includes `stdgates.inc` instead of defining the gates it provides.
+    /// Has no effect for OpenQASM 2.0 and the Braket dialect, the Qiskit dialect always includes it.
+    pub stdgates_include: bool,
+    /// Whether vanilla OpenQASM 2.0 output includes `qelib1.inc` instead of defining the gates it provides.
+    /// Has no effect for OpenQASM 3.0.
+    pub qelib_include: bool,
+    /// Whether the vanilla OpenQASM 3.0 dialect emits controlled gates with the `ctrl @` modifier.
+    pub ctrl_modifier: bool,
+    /// Whether OpenQASM 2.0 names the controlled phase gate `cu1` as older `qelib1.inc` files, instead of `cp`.
+    pub cu1_controlled_phase: bool,
+    /// The value of the classical bit that triggers the circuit of a PragmaConditional.
+    pub conditional_value: QasmConditionalValue,
+    /// Whether the basis-rotation circuits of the PragmaGet measurement pragmas are emitted as gates.
+    /// Has no effect for the Roqoqo dialect, which keeps the circuits in the pragmas.
+    pub inline_measurement_basis: bool,
+    /// The number of spaces the bodies of OpenQASM 3.0 blocks, such as `if` and `for`, are indented by.
+    pub indent_width: usize,
+    /// Whether the Braket dialect drops PragmaGlobalPhase instead of emitting it as `gphase`.
+    pub drop_unsupported_global_phase: bool,
+}
+
+impl Default for QasmOptions {
+    fn default() -> Self {
+        Self {
+            builtin_cnot: false,
+            json_pragmas: false,
+            max_recursion_depth: 256,
+            stdgates_include: false,
+            qelib_include: false,
+            ctrl_modifier: false,
+            cu1_controlled_phase: false,
+            conditional_value: QasmConditionalValue::default(),
+            inline_measurement_basis: false,
+            indent_width: 4,
+            drop_unsupported_global_phase: false,
+        }
+    }
+}
+
+/// The value of the classical bit a PragmaConditional compares against.
+///
+/// roqoqo executes the circuit of a PragmaConditional when the bit is set, the comparison with
+/// `0` allows exporting conditions on a bit that is not set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QasmConditionalValue {
+    /// The circuit is executed when the bit is `0`.
+    Zero,
+    /// The circuit is executed when the bit is `1`.
+    #[default]
+    One,
+}
+
+impl std::fmt::Display for QasmConditionalValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QasmConditionalValue::Zero => write!(f, "0"),
+            QasmConditionalValue::One => write!(f, "1"),
+        }
+    }
+}
+
+/// Returns the basis-rotation circuit of a measurement pragma when it is emitted as gates.
+pub(crate) fn inlined_basis_circuit<'a>(
+    operation: &'a Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> Option<&'a Circuit> {
+    if !options.inline_measurement_basis
+        || qasm_version == QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+    {
+        return None;
+    }
+    match operation {
+        Operation::PragmaGetDensityMatrix(op) => op.circuit().as_ref(),
+        Operation::PragmaGetOccupationProbability(op) => op.circuit().as_ref(),
+        Operation::PragmaGetStateVector(op) => op.circuit().as_ref(),
+        Operation::PragmaGetPauliProduct(op) => Some(op.circuit()),
+        _ => None,
+    }
+}
+
+/// Returns the name of the gate ControlledPhaseShift is emitted as.
+fn controlled_phase_name(qasm_version: QasmVersion, options: &QasmOptions) -> &'static str {
+    match qasm_version {
+        QasmVersion::V2point0(_) if options.cu1_controlled_phase => "cu1",
+        _ => "cp",
+    }
+}
+
+/// Returns whether the QASM version provides the gate of the operation without a gate definition.
+///
+/// Braket provides its gates natively and OpenQASM 3.0 provides the gates of `stdgates.inc`
+/// when it is included.
+pub(crate) fn is_native_gate(
+    operation: &Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> bool {
+    match qasm_version {
+        QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+            BRAKET_NATIVE_GATES.contains(&operation.hqslang())
+        }
+        QasmVersion::V3point0(Qasm3Dialect::IonQ) => {
+            IONQ_NATIVE_GATES.contains(&operation.hqslang())
+        }
+        // Qiskit always includes stdgates.inc
+        QasmVersion::V3point0(Qasm3Dialect::Qiskit) => stdgates_name(operation).is_some(),
+        QasmVersion::V3point0(_) if options.stdgates_include => stdgates_name(operation).is_some(),
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla) if options.qelib_include => {
+            QELIB_GATES.contains(&operation.hqslang())
+                || (options.cu1_controlled_phase
+                    && matches!(operation, Operation::ControlledPhaseShift(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the name of the gate of an operation in the OpenQASM 3.0 stdgates.inc library, if it defines the gate.
+pub(crate) fn stdgates_name(operation: &Operation) -> Option<&'static str> {
+    STDGATES_NAMES
+        .iter()
+        .find(|(hqslang, _)| *hqslang == operation.hqslang())
+        .map(|(_, name)| *name)
+}
+
+/// Returns the gate a controlled operation applies with the `ctrl @` modifier, if the modifier is used.
+///
+/// The modifier is only used for the vanilla OpenQASM 3.0 dialect when enabled in the options.
+pub(crate) fn ctrl_modifier_gate(
+    operation: &Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> Option<Operation> {
+    if !(options.ctrl_modifier
+        && matches!(qasm_version, QasmVersion::V3point0(Qasm3Dialect::Vanilla)))
+    {
+        return None;
+    }
+    match operation {
+        Operation::ControlledPauliY(op) => Some(PauliY::new(*op.target()).into()),
+        Operation::ControlledPauliZ(op) => Some(PauliZ::new(*op.target()).into()),
+        Operation::ControlledPhaseShift(op) => {
+            Some(PhaseShiftState1::new(*op.target(), op.theta().clone()).into())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the gate an inverse operation applies with the `inv @` modifier, if the modifier is used.
+///
+/// The modifier is only used for the vanilla OpenQASM 3.0 dialect, where it avoids defining the
+/// inverse gate.
+pub(crate) fn inv_modifier_gate(
+    operation: &Operation,
+    qasm_version: QasmVersion,
+) -> Option<Operation> {
+    if qasm_version != QasmVersion::V3point0(Qasm3Dialect::Vanilla) {
+        return None;
+    }
+    match operation {
+        Operation::InvSqrtPauliX(op) => Some(SqrtPauliX::new(*op.qubit()).into()),
+        _ => None,
+    }
+}
+
+/// Builds an OpenQASM 3.0 block `{ ... }` with one indented line per line of the translated operations.
+///
+/// Empty translations are skipped and blocks nested in the operations are indented further.
+fn indented_block(body: &[String], indent_width: usize) -> String {
+    let indent = " ".repeat(indent_width);
+    let mut block = String::from("{\n");
+    for instruction in body.iter().filter(|instruction| !instruction.is_empty()) {
+        for line in instruction.lines() {
+            block.push_str(&format!("{}{}\n", indent, line));
+        }
+    }
+    block.push('}');
+    block
+}
+
+/// Builds a conditional executing `if_body` when the condition holds and `else_body` otherwise.
+///
+/// OpenQASM 3.0 emits an `if(condition) { ... }` block, followed by an `else { ... }` block when
+/// an else branch is given, with one indented line per operation. OpenQASM 2.0 can only condition
+/// single operations, so every operation of the if branch is conditioned separately and an else
+/// branch is rejected.
+///
+/// # Arguments
+///
+/// * `condition` - The condition of the `if` statement, for example `c[0]==1`.
+/// * `if_body` - The translated operations executed when the condition holds.
+/// * `else_body` - The translated operations executed otherwise, if there is an else branch.
+/// * `qasm_version` - The QASM version to use.
+/// * `indent_width` - The number of spaces the OpenQASM 3.0 block bodies are indented by.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The translated conditional.
+/// * `Err(RoqoqoBackendError)` - An else branch is not supported for the QASM version.
+fn conditional_block(
+    condition: &str,
+    if_body: &[String],
+    else_body: Option<&[String]>,
+    qasm_version: QasmVersion,
+    indent_width: usize,
+) -> Result<String, RoqoqoBackendError> {
+    match qasm_version {
+        QasmVersion::V2point0(_) => {
+            if else_body.is_some() {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "OpenQASM 2.0 does not support else branches, found one for the condition {}",
+                        condition
+                    ),
+                });
+            }
+            // Every line of a multi-line translation, such as a CNOT ladder, is conditioned
+            Ok(if_body
+                .iter()
+                .flat_map(|entry| entry.split('\n'))
+                .filter(|line| !line.is_empty())
+                .map(|line| format!("if({}) {}", condition, line))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+        QasmVersion::V3point0(_) => {
+            let mut data = format!(
+                "if({}) {}",
+                condition,
+                indented_block(if_body, indent_width)
+            );
+            if let Some(else_body) = else_body {
+                data.push_str(&format!(
+                    " else {}",
+                    indented_block(else_body, indent_width)
+                ));
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// Serializes a pragma as a JSON `pragma roqoqo` line of the Roqoqo dialect.
+fn json_pragma<T: Operate + Serialize>(operation: &T) -> Result<String, RoqoqoBackendError> {
+    let json =
+        serde_json::to_string(operation).map_err(|err| RoqoqoBackendError::GenericError {
+            msg: format!(
+                "Could not serialize {} to JSON: {}",
+                operation.hqslang(),
+                err
+            ),
+        })?;
+    Ok(format!("pragma roqoqo {} {};", operation.hqslang(), json))
+}
+
 /// Calls the parsing function of the VariableGatherer, if present.
 ///
 /// # Arguments:
@@ -92,15 +454,61 @@ fn variable_gathering(
     calculator_float: &CalculatorFloat,
     qasm_version: QasmVersion,
     variable_gatherer: &mut Option<&mut VariableGatherer>,
-) {
+) -> Result<(), RoqoqoBackendError> {
+    variable_gathering_with_kind(
+        calculator_float,
+        qasm_version,
+        variable_gatherer,
+        VariableKind::Angle,
+    )
+}
+
+/// Gathers the variables of a parameter that is used as the given kind of value.
+fn variable_gathering_with_kind(
+    calculator_float: &CalculatorFloat,
+    qasm_version: QasmVersion,
+    variable_gatherer: &mut Option<&mut VariableGatherer>,
+    kind: VariableKind,
+) -> Result<(), RoqoqoBackendError> {
     if let Some(cp) = variable_gatherer {
         match qasm_version {
-            QasmVersion::V3point0(_) => {
-                let _ = cp.parse(calculator_float.to_string().as_str());
-            }
+            QasmVersion::V3point0(_) => cp
+                .parse_with_kind(calculator_float.to_string().as_str(), kind)
+                .map_err(|x| RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "Symbolic expression {} can not be translated to OpenQASM 3.0: {}",
+                        calculator_float, x
+                    ),
+                })?,
             QasmVersion::V2point0(_) => (),
         }
     }
+    Ok(())
+}
+
+/// Checks that the complex parameter of an operation is numeric outside of the Roqoqo dialect.
+///
+/// The other dialects use the absolute value and the argument of the parameter, which need the
+/// `atan2` function for symbolic parameters and OpenQASM does not provide it.
+fn check_numeric_complex_parameter(
+    operation: &Operation,
+    real: &CalculatorFloat,
+    imag: &CalculatorFloat,
+    qasm_version: QasmVersion,
+) -> Result<(), RoqoqoBackendError> {
+    match qasm_version {
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(()),
+        _ if real.is_float() && imag.is_float() => Ok(()),
+        _ => Err(RoqoqoBackendError::GenericError {
+            msg: format!(
+                "The symbolic complex parameter ({}, {}) of {} is only supported by the Roqoqo dialect, its absolute value and argument can not be expressed in {:?}",
+                real,
+                imag,
+                operation.hqslang(),
+                qasm_version
+            ),
+        }),
+    }
 }
 
 /// Translate the qoqo circuit into QASM ouput.
@@ -142,14 +550,35 @@ pub fn call_circuit(
     circuit: &Circuit,
     qubit_register_name: &str,
     qasm_version: QasmVersion,
+) -> Result<Vec<String>, RoqoqoBackendError> {
+    call_circuit_with_options(
+        circuit,
+        qubit_register_name,
+        qasm_version,
+        &QasmOptions::default(),
+        0,
+    )
+}
+
+/// Translate the qoqo circuit into QASM ouput using the given options.
+///
+/// The `depth` is the nesting depth of the operations of the circuit.
+fn call_circuit_with_options(
+    circuit: &Circuit,
+    qubit_register_name: &str,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+    depth: usize,
 ) -> Result<Vec<String>, RoqoqoBackendError> {
     let mut str_circuit: Vec<String> = Vec::new();
     for op in circuit.iter() {
-        str_circuit.push(call_operation(
+        str_circuit.push(call_operation_at_depth(
             op,
             qubit_register_name,
             qasm_version,
             &mut None,
+            options,
+            depth,
         )?);
     }
     Ok(str_circuit)
@@ -174,17 +603,206 @@ pub fn call_operation(
     qasm_version: QasmVersion,
     variable_gatherer: &mut Option<&mut VariableGatherer>,
 ) -> Result<String, RoqoqoBackendError> {
+    call_operation_with_options(
+        operation,
+        qubit_register_name,
+        qasm_version,
+        variable_gatherer,
+        &QasmOptions::default(),
+    )
+}
+
+/// Translates a qoqo operation to QASM (&str) using the given options.
+///
+/// # Arguments
+///
+/// * `operation` - The qoqo Operation that is executed.
+/// * `qubtit_register_name` - Name of the quantum register used for the roqoqo address.
+/// * `qasm_version` - The QASM version to use.
+/// * `variable_gatherer` - Optional VariableParser to call.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `Ok(&str)` - Converted operation in &str form.
+/// * `Err(RoqoqoBackendError)` - Operation not supported by QASM backend.
+pub fn call_operation_with_options(
+    operation: &Operation,
+    qubit_register_name: &str,
+    qasm_version: QasmVersion,
+    variable_gatherer: &mut Option<&mut VariableGatherer>,
+    options: &QasmOptions,
+) -> Result<String, RoqoqoBackendError> {
+    let instruction = call_operation_at_depth(
+        operation,
+        qubit_register_name,
+        qasm_version,
+        variable_gatherer,
+        options,
+        0,
+    )?;
+    // A rewriting VariableGatherer replaces unsupported functions in the emitted expressions
+    match variable_gatherer {
+        Some(gatherer) if !gatherer.rewritten_expressions.is_empty() => {
+            Ok(gatherer.rewrite_instruction(&instruction))
+        }
+        _ => Ok(instruction),
+    }
+}
+
+/// Translates a qoqo operation to its QASM gate definition and its QASM call.
+///
+/// # Arguments
+///
+/// * `operation` - The qoqo Operation that is translated.
+/// * `qubit_register_name` - Name of the quantum register used for the roqoqo address.
+/// * `qasm_version` - The QASM version to use.
+///
+/// # Returns
+///
+/// * `Ok((Option<String>, String))` - The gate definition, `None` when the operation needs none, and the call.
+/// * `Err(RoqoqoBackendError)` - Operation not supported by QASM backend.
+pub fn operation_to_qasm(
+    operation: &Operation,
+    qubit_register_name: &str,
+    qasm_version: QasmVersion,
+) -> Result<(Option<String>, String), RoqoqoBackendError> {
+    let definition = gate_definition(operation, qasm_version)?;
+    let call = call_operation(operation, qubit_register_name, qasm_version, &mut None)?;
+    Ok(((!definition.is_empty()).then_some(definition), call))
+}
+
+// Register name the operations are translated with before the qubits are mapped to their registers.
+// It contains a character that can not be part of a QASM identifier to avoid collisions.
+const MAPPED_REGISTER_PLACEHOLDER: &str = "$qubits";
+
+/// Translates a qoqo operation to QASM (&str) addressing qubits in several quantum registers.
+///
+/// Each qubit index used by the operation is replaced by the register and the index in that
+/// register given by the mapping.
+///
+/// # Arguments
+///
+/// * `operation` - The qoqo Operation that is executed.
+/// * `register_mapping` - The register name and local index of each roqoqo qubit index.
+/// * `qasm_version` - The QASM version to use.
+/// * `variable_gatherer` - Optional VariableParser to call.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `Ok(&str)` - Converted operation in &str form.
+/// * `Err(RoqoqoBackendError)` - Operation not supported by QASM backend or qubit not in the mapping.
+pub fn call_operation_with_register_mapping(
+    operation: &Operation,
+    register_mapping: &HashMap<usize, (String, usize)>,
+    qasm_version: QasmVersion,
+    variable_gatherer: &mut Option<&mut VariableGatherer>,
+    options: &QasmOptions,
+) -> Result<String, RoqoqoBackendError> {
+    let instruction = call_operation_with_options(
+        operation,
+        MAPPED_REGISTER_PLACEHOLDER,
+        qasm_version,
+        variable_gatherer,
+        options,
+    )?;
+    let mut mapped = String::with_capacity(instruction.len());
+    let mut rest = instruction.as_str();
+    while let Some(position) = rest.find(MAPPED_REGISTER_PLACEHOLDER) {
+        mapped.push_str(&rest[..position]);
+        rest = &rest[position + MAPPED_REGISTER_PLACEHOLDER.len()..];
+        let qubit = rest
+            .strip_prefix('[')
+            .and_then(|indexed| indexed.split_once(']'))
+            .and_then(|(index, _)| index.parse::<usize>().ok());
+        let qubit = match qubit {
+            Some(qubit) => qubit,
+            None => {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "{} acts on the whole qubit register and can not be translated with a register mapping",
+                        operation.hqslang()
+                    ),
+                })
+            }
+        };
+        let (register_name, local_index) =
+            register_mapping
+                .get(&qubit)
+                .ok_or_else(|| RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "Qubit {} used by {} is not in the register mapping",
+                        qubit,
+                        operation.hqslang()
+                    ),
+                })?;
+        mapped.push_str(&format!("{}[{}]", register_name, local_index));
+        rest = &rest[rest.find(']').map_or(0, |end| end + 1)..];
+    }
+    mapped.push_str(rest);
+    Ok(mapped)
+}
+
+/// Translates a qoqo operation nested at the given depth in PragmaConditional and PragmaLoop operations.
+fn call_operation_at_depth(
+    operation: &Operation,
+    qubit_register_name: &str,
+    qasm_version: QasmVersion,
+    variable_gatherer: &mut Option<&mut VariableGatherer>,
+    options: &QasmOptions,
+    depth: usize,
+) -> Result<String, RoqoqoBackendError> {
+    if depth > options.max_recursion_depth {
+        return Err(RoqoqoBackendError::GenericError {
+            msg: format!(
+                "Maximum recursion depth of {} exceeded while translating nested operations",
+                options.max_recursion_depth
+            ),
+        });
+    }
     if matches!(qasm_version, QasmVersion::V2point0(Qasm2Dialect::Qulacs))
         && !QULCAS_SUPPORTED_OPERATIONS.contains(&operation.hqslang())
     {
+        if MEASUREMENT_READOUT_OPERATIONS.contains(&operation.hqslang()) {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "{} is a measurement operation that must be handled at the QuantumProgram level, it can not be translated to QASM",
+                    operation.hqslang()
+                ),
+            });
+        }
         return Err(RoqoqoBackendError::OperationNotInBackend {
             backend: "QasmBackend version 2.0 Qulacs",
             hqslang: operation.hqslang(),
         });
     }
+    if matches!(qasm_version, QasmVersion::V3point0(Qasm3Dialect::IonQ))
+        && operation.tags().contains(&"GateOperation")
+        && !IONQ_NATIVE_GATES.contains(&operation.hqslang())
+    {
+        return Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "QasmBackend version 3.0 IonQ",
+            hqslang: operation.hqslang(),
+        });
+    }
+    // The measurement basis is rotated right before the following measurement statements
+    if let Some(basis_circuit) = inlined_basis_circuit(operation, qasm_version, options) {
+        return Ok(call_circuit_with_options(
+            basis_circuit,
+            qubit_register_name,
+            qasm_version,
+            options,
+            depth + 1,
+        )?
+        .into_iter()
+        .filter(|instruction| !instruction.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n"));
+    }
     match operation {
         Operation::RotateZ(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "rz({}) {}[{}];",
                 op.theta(),
@@ -193,7 +811,7 @@ pub fn call_operation(
             ))
         }
         Operation::RotateX(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "rx({}) {}[{}];",
                 op.theta(),
@@ -202,7 +820,7 @@ pub fn call_operation(
             ))
         }
         Operation::RotateY(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "ry({}) {}[{}];",
                 op.theta(),
@@ -216,8 +834,20 @@ pub fn call_operation(
         Operation::PauliZ(op) => Ok(format!("z {}[{}];", qubit_register_name, op.qubit())),
         Operation::SGate(op) => Ok(format!("s {}[{}];", qubit_register_name, op.qubit())),
         Operation::TGate(op) => Ok(format!("t {}[{}];", qubit_register_name, op.qubit())),
+        Operation::InvSGate(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+                Ok(format!("si {}[{}];", qubit_register_name, op.qubit()))
+            }
+            _ => Ok(format!("sdg {}[{}];", qubit_register_name, op.qubit())),
+        },
+        Operation::InvTGate(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+                Ok(format!("ti {}[{}];", qubit_register_name, op.qubit()))
+            }
+            _ => Ok(format!("tdg {}[{}];", qubit_register_name, op.qubit())),
+        },
         Operation::PhaseShiftState1(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             match qasm_version {
                 QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
                     "phaseshift({}) {}[{}];",
@@ -239,9 +869,12 @@ pub fn call_operation(
             }
             _ => Ok(format!("sx {}[{}];", qubit_register_name, op.qubit())),
         },
-        Operation::InvSqrtPauliX(op) => {
-            Ok(format!("sxdg {}[{}];", qubit_register_name, op.qubit()))
-        }
+        Operation::InvSqrtPauliX(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla) => {
+                Ok(format!("inv @ sx {}[{}];", qubit_register_name, op.qubit()))
+            }
+            _ => Ok(format!("sxdg {}[{}];", qubit_register_name, op.qubit())),
+        },
         Operation::Identity(op) => Ok(format!("id {}[{}];", qubit_register_name, op.qubit())),
         Operation::CNOT(op) => match qasm_version {
             QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
@@ -251,6 +884,13 @@ pub fn call_operation(
                 qubit_register_name,
                 op.target()
             )),
+            QasmVersion::V2point0(_) if options.builtin_cnot => Ok(format!(
+                "CX {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
             _ => Ok(format!(
                 "cx {}[{}],{}[{}];",
                 qubit_register_name,
@@ -267,6 +907,13 @@ pub fn call_operation(
                 qubit_register_name,
                 op.target()
             )),
+            QasmVersion::V3point0(Qasm3Dialect::IonQ) => Ok(format!(
+                "ms(0,0,pi/2) {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
             _ => Ok(format!(
                 "rxx(pi/2) {}[{}],{}[{}];",
                 qubit_register_name,
@@ -276,7 +923,7 @@ pub fn call_operation(
             )),
         },
         Operation::VariableMSXX(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             match qasm_version {
                 QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
                     "xx({}) {}[{}],{}[{}];",
@@ -296,22 +943,40 @@ pub fn call_operation(
                 )),
             }
         }
-        Operation::ControlledPauliY(op) => Ok(format!(
-            "cy {}[{}],{}[{}];",
-            qubit_register_name,
-            op.control(),
-            qubit_register_name,
-            op.target()
-        )),
-        Operation::ControlledPauliZ(op) => Ok(format!(
-            "cz {}[{}],{}[{}];",
-            qubit_register_name,
-            op.control(),
-            qubit_register_name,
-            op.target()
-        )),
+        Operation::ControlledPauliY(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla) if options.ctrl_modifier => Ok(format!(
+                "ctrl @ y {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
+            _ => Ok(format!(
+                "cy {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
+        },
+        Operation::ControlledPauliZ(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla) if options.ctrl_modifier => Ok(format!(
+                "ctrl @ z {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
+            _ => Ok(format!(
+                "cz {}[{}],{}[{}];",
+                qubit_register_name,
+                op.control(),
+                qubit_register_name,
+                op.target()
+            )),
+        },
         Operation::ControlledPhaseShift(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             match qasm_version {
                 QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
                     "cphaseshift({}) {}[{}],{}[{}];",
@@ -321,8 +986,19 @@ pub fn call_operation(
                     qubit_register_name,
                     op.target()
                 )),
+                QasmVersion::V3point0(Qasm3Dialect::Vanilla) if options.ctrl_modifier => {
+                    Ok(format!(
+                        "ctrl @ p({}) {}[{}],{}[{}];",
+                        op.theta(),
+                        qubit_register_name,
+                        op.control(),
+                        qubit_register_name,
+                        op.target()
+                    ))
+                }
                 _ => Ok(format!(
-                    "cp({}) {}[{}],{}[{}];",
+                    "{}({}) {}[{}],{}[{}];",
+                    controlled_phase_name(qasm_version, options),
                     op.theta(),
                     qubit_register_name,
                     op.control(),
@@ -332,7 +1008,7 @@ pub fn call_operation(
             }
         }
         Operation::ControlledRotateX(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "crx({}) {}[{}],{}[{}];",
                 op.theta(),
@@ -343,8 +1019,8 @@ pub fn call_operation(
             ))
         }
         Operation::ControlledRotateXY(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "crxy({},{}) {}[{}],{}[{}];",
                 op.theta(),
@@ -398,9 +1074,24 @@ pub fn call_operation(
             op.target()
         )),
         Operation::Fsim(op) => {
-            variable_gathering(op.t(), qasm_version, variable_gatherer);
-            variable_gathering(op.u(), qasm_version, variable_gatherer);
-            variable_gathering(op.delta(), qasm_version, variable_gatherer);
+            variable_gathering_with_kind(
+                op.t(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.u(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.delta(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
             Ok(format!(
                 "fsim({},{},{}) {}[{}],{}[{}];",
                 op.t(),
@@ -413,9 +1104,24 @@ pub fn call_operation(
             ))
         }
         Operation::Qsim(op) => {
-            variable_gathering(op.x(), qasm_version, variable_gatherer);
-            variable_gathering(op.y(), qasm_version, variable_gatherer);
-            variable_gathering(op.z(), qasm_version, variable_gatherer);
+            variable_gathering_with_kind(
+                op.x(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.y(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.z(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
             Ok(format!(
                 "qsim({},{},{}) {}[{}],{}[{}];",
                 op.x(),
@@ -428,7 +1134,12 @@ pub fn call_operation(
             ))
         }
         Operation::PMInteraction(op) => {
-            variable_gathering(op.t(), qasm_version, variable_gatherer);
+            variable_gathering_with_kind(
+                op.t(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
             Ok(format!(
                 "pmint({}) {}[{}],{}[{}];",
                 op.t(),
@@ -439,8 +1150,8 @@ pub fn call_operation(
             ))
         }
         Operation::GivensRotation(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "gvnsrot({},{}) {}[{}],{}[{}];",
                 op.theta(),
@@ -452,8 +1163,8 @@ pub fn call_operation(
             ))
         }
         Operation::GivensRotationLittleEndian(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "gvnsrotle({},{}) {}[{}],{}[{}];",
                 op.theta(),
@@ -464,8 +1175,111 @@ pub fn call_operation(
                 op.target()
             ))
         }
+        Operation::Bogoliubov(op) => {
+            check_numeric_complex_parameter(
+                operation,
+                op.delta_real(),
+                op.delta_imag(),
+                qasm_version,
+            )?;
+            variable_gathering_with_kind(
+                op.delta_real(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.delta_imag(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            let control = format!("{}[{}]", qubit_register_name, op.control());
+            let target = format!("{}[{}]", qubit_register_name, op.target());
+            // Only the Roqoqo dialect keeps the cartesian parameters, the other dialects
+            // use the absolute value and the argument of delta as the inverse trigonometric
+            // functions needed to compute them are not available in OpenQASM 2.0
+            let delta = CalculatorComplex::new(op.delta_real(), op.delta_imag());
+            match qasm_version {
+                QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
+                    "bogoliubov({},{}) {control},{target};",
+                    op.delta_real(),
+                    op.delta_imag(),
+                )),
+                // Braket does not accept gate definitions, so the decomposition used in the
+                // bogoliubov gate definition is emitted with Braket gates
+                QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+                    let delta_abs = delta.norm();
+                    let delta_arg = delta.arg();
+                    Ok([
+                        format!("rz({delta_arg}) {target};"),
+                        format!("x {target};"),
+                        format!("rx(pi/2) {control};"),
+                        format!("cnot {control},{target};"),
+                        format!("rx({}) {control};", -delta_abs.clone()),
+                        format!("ry({}) {target};", -delta_abs),
+                        format!("cnot {control},{target};"),
+                        format!("rx(-pi/2) {control};"),
+                        format!("x {target};"),
+                        format!("rz({}) {target};", -delta_arg),
+                    ]
+                    .join("\n"))
+                }
+                _ => Ok(format!(
+                    "bogoliubov({},{}) {control},{target};",
+                    delta.norm(),
+                    delta.arg(),
+                )),
+            }
+        }
+        Operation::ComplexPMInteraction(op) => {
+            check_numeric_complex_parameter(operation, op.t_real(), op.t_imag(), qasm_version)?;
+            variable_gathering_with_kind(
+                op.t_real(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.t_imag(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            let control = format!("{}[{}]", qubit_register_name, op.control());
+            let target = format!("{}[{}]", qubit_register_name, op.target());
+            // As for Bogoliubov, only the Roqoqo dialect keeps the cartesian parameters
+            let t = CalculatorComplex::new(op.t_real(), op.t_imag());
+            match qasm_version {
+                QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
+                    "cpmint({},{}) {control},{target};",
+                    op.t_real(),
+                    op.t_imag(),
+                )),
+                QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+                    let t_abs = t.norm();
+                    let t_arg = t.arg();
+                    Ok([
+                        format!("rz({t_arg}) {target};"),
+                        format!("rx(pi/2) {control};"),
+                        format!("cnot {control},{target};"),
+                        format!("rx({t_abs}) {control};"),
+                        format!("ry({t_abs}) {target};"),
+                        format!("cnot {control},{target};"),
+                        format!("rx(-pi/2) {control};"),
+                        format!("rz({}) {target};", -t_arg),
+                    ]
+                    .join("\n"))
+                }
+                _ => Ok(format!(
+                    "cpmint({},{}) {control},{target};",
+                    t.norm(),
+                    t.arg(),
+                )),
+            }
+        }
         Operation::XY(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "xy({}) {}[{}],{}[{}];",
                 op.theta(),
@@ -476,9 +1290,24 @@ pub fn call_operation(
             ))
         }
         Operation::SpinInteraction(op) => {
-            variable_gathering(op.x(), qasm_version, variable_gatherer);
-            variable_gathering(op.y(), qasm_version, variable_gatherer);
-            variable_gathering(op.z(), qasm_version, variable_gatherer);
+            variable_gathering_with_kind(
+                op.x(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.y(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
+            variable_gathering_with_kind(
+                op.z(),
+                qasm_version,
+                variable_gatherer,
+                VariableKind::Float,
+            )?;
             Ok(format!(
                 "spinint({},{},{}) {}[{}],{}[{}];",
                 op.x(),
@@ -491,8 +1320,8 @@ pub fn call_operation(
             ))
         }
         Operation::RotateXY(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             if matches!(qasm_version, QasmVersion::V2point0(Qasm2Dialect::Qulacs)) {
                 Ok(format!(
                     "u3({},{},{}) {}[{}];",
@@ -513,7 +1342,7 @@ pub fn call_operation(
             }
         }
         Operation::PhaseShiftedControlledZ(op) => {
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "pscz({}) {}[{}],{}[{}];",
                 op.phi(),
@@ -524,8 +1353,8 @@ pub fn call_operation(
             ))
         }
         Operation::PhaseShiftedControlledPhase(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            variable_gathering(op.phi(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            variable_gathering(op.phi(), qasm_version, variable_gatherer)?;
             Ok(format!(
                 "pscp({},{}) {}[{}],{}[{}];",
                 op.theta(),
@@ -537,9 +1366,10 @@ pub fn call_operation(
             ))
         }
         Operation::GPi(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             match qasm_version {
-                QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
+                QasmVersion::V3point0(Qasm3Dialect::Braket)
+                | QasmVersion::V3point0(Qasm3Dialect::IonQ) => Ok(format!(
                     "gpi({}) {}[{}];",
                     op.theta(),
                     qubit_register_name,
@@ -558,9 +1388,10 @@ pub fn call_operation(
             }
         }
         Operation::GPi2(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
             match qasm_version {
-                QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
+                QasmVersion::V3point0(Qasm3Dialect::Braket)
+                | QasmVersion::V3point0(Qasm3Dialect::IonQ) => Ok(format!(
                     "gpi2({}) {}[{}];",
                     op.theta(),
                     qubit_register_name,
@@ -585,50 +1416,241 @@ pub fn call_operation(
             let phi: CalculatorFloat = alpha.arg() * (-1.0) + beta.arg();
             let lamda: CalculatorFloat = alpha.arg() * (-1.0) - beta.arg();
 
-            Ok(format!(
-                "u3({:.15},{:.15},{:.15}) {}[{}];",
-                theta.float()?,
-                phi.float()?,
-                lamda.float()?,
-                qubit_register_name,
-                op.qubit()
-            ))
+            match qasm_version {
+                // The global phase of the gate relative to U(theta,phi,lambda) is kept with gphase
+                QasmVersion::V3point0(Qasm3Dialect::Vanilla) => {
+                    let global_phase = *(op.global_phase() + alpha.arg()).float()?;
+                    let u_gate = format!(
+                        "U({:.15},{:.15},{:.15}) {}[{}];",
+                        theta.float()?,
+                        phi.float()?,
+                        lamda.float()?,
+                        qubit_register_name,
+                        op.qubit()
+                    );
+                    if global_phase == 0.0 {
+                        Ok(u_gate)
+                    } else {
+                        Ok(format!("gphase({:.15});\n{}", global_phase, u_gate))
+                    }
+                }
+                _ => Ok(format!(
+                    "u3({:.15},{:.15},{:.15}) {}[{}];",
+                    theta.float()?,
+                    phi.float()?,
+                    lamda.float()?,
+                    qubit_register_name,
+                    op.qubit()
+                )),
+            }
         }
-        Operation::Toffoli(op) => Ok(format!(
-            "ccx {}[{}],{}[{}],{}[{}];",
-            qubit_register_name,
-            op.control_0(),
-            qubit_register_name,
-            op.control_1(),
-            qubit_register_name,
-            op.target(),
-        )),
-        Operation::ControlledControlledPauliZ(op) => Ok(format!(
-            "ccz {}[{}],{}[{}],{}[{}];",
-            qubit_register_name,
-            op.control_0(),
-            qubit_register_name,
-            op.control_1(),
-            qubit_register_name,
-            op.target(),
-        )),
-        Operation::ControlledControlledPhaseShift(op) => {
-            variable_gathering(op.theta(), qasm_version, variable_gatherer);
-            Ok(format!(
-                "ccp({}) {}[{}],{}[{}],{}[{}];",
-                op.theta(),
+        Operation::Toffoli(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
+                "ccnot {}[{}],{}[{}],{}[{}];",
                 qubit_register_name,
                 op.control_0(),
                 qubit_register_name,
                 op.control_1(),
                 qubit_register_name,
                 op.target(),
-            ))
+            )),
+            _ => Ok(format!(
+                "ccx {}[{}],{}[{}],{}[{}];",
+                qubit_register_name,
+                op.control_0(),
+                qubit_register_name,
+                op.control_1(),
+                qubit_register_name,
+                op.target(),
+            )),
+        },
+        Operation::ControlledControlledPauliZ(op) => match qasm_version {
+            // Braket has no ccz gate, the target is conjugated with Hadamard gates around a ccnot
+            QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(format!(
+                "h {}[{}];\nccnot {}[{}],{}[{}],{}[{}];\nh {}[{}];",
+                qubit_register_name,
+                op.target(),
+                qubit_register_name,
+                op.control_0(),
+                qubit_register_name,
+                op.control_1(),
+                qubit_register_name,
+                op.target(),
+                qubit_register_name,
+                op.target(),
+            )),
+            _ => Ok(format!(
+                "ccz {}[{}],{}[{}],{}[{}];",
+                qubit_register_name,
+                op.control_0(),
+                qubit_register_name,
+                op.control_1(),
+                qubit_register_name,
+                op.target(),
+            )),
+        },
+        Operation::ControlledControlledPhaseShift(op) => {
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            match qasm_version {
+                // Braket does not define ccp and does not accept gate definitions, so the
+                // decomposition used in the ccp gate definition is emitted with Braket gates
+                QasmVersion::V3point0(Qasm3Dialect::Braket) => {
+                    let quarter: CalculatorFloat = op.theta().clone() / 4.0;
+                    let minus_quarter: CalculatorFloat = op.theta().clone() / (-4.0);
+                    let control_0 = format!("{}[{}]", qubit_register_name, op.control_0());
+                    let control_1 = format!("{}[{}]", qubit_register_name, op.control_1());
+                    let target = format!("{}[{}]", qubit_register_name, op.target());
+                    Ok([
+                        format!("phaseshift({quarter}) {control_1};"),
+                        format!("cnot {control_1},{target};"),
+                        format!("phaseshift({minus_quarter}) {target};"),
+                        format!("cnot {control_1},{target};"),
+                        format!("phaseshift({quarter}) {target};"),
+                        format!("cnot {control_0},{control_1};"),
+                        format!("phaseshift({minus_quarter}) {control_1};"),
+                        format!("cnot {control_1},{target};"),
+                        format!("phaseshift({quarter}) {target};"),
+                        format!("cnot {control_1},{target};"),
+                        format!("phaseshift({minus_quarter}) {target};"),
+                        format!("cnot {control_0},{control_1};"),
+                        format!("phaseshift({quarter}) {control_0};"),
+                        format!("cnot {control_0},{target};"),
+                        format!("phaseshift({minus_quarter}) {target};"),
+                        format!("cnot {control_0},{target};"),
+                        format!("phaseshift({quarter}) {target};"),
+                    ]
+                    .join("\n"))
+                }
+                _ => Ok(format!(
+                    "ccp({}) {}[{}],{}[{}],{}[{}];",
+                    op.theta(),
+                    qubit_register_name,
+                    op.control_0(),
+                    qubit_register_name,
+                    op.control_1(),
+                    qubit_register_name,
+                    op.target(),
+                )),
+            }
+        }
+        Operation::MultiQubitZZ(op) => {
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            let qubits = op.qubits();
+            if qubits.len() < 2 {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "MultiQubitZZ needs at least 2 qubits to be translated to rzz gates, got {}",
+                        qubits.len()
+                    ),
+                });
+            }
+            let (zz_name, cnot_name) = match qasm_version {
+                // OpenQASM 3.0 uses the gate defined for the number of qubits
+                QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+                | QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+                | QasmVersion::V3point0(Qasm3Dialect::Qiskit)
+                | QasmVersion::V3point0(Qasm3Dialect::IonQ) => {
+                    return Ok(format!(
+                        "rzz_multi_{}({}) {};",
+                        qubits.len(),
+                        op.theta(),
+                        qubits
+                            .iter()
+                            .map(|qubit| format!("{}[{}]", qubit_register_name, qubit))
+                            .collect::<Vec<String>>()
+                            .join(",")
+                    ));
+                }
+                QasmVersion::V3point0(Qasm3Dialect::Braket) => ("zz", "cnot"),
+                QasmVersion::V2point0(_) if options.builtin_cnot => ("rzz", "CX"),
+                QasmVersion::V2point0(_) => ("rzz", "cx"),
+            };
+            // The parity of the first qubits is collected on the second to last qubit with a
+            // CNOT ladder, so that a single two-qubit ZZ rotation acts on all qubits
+            let ladder: Vec<String> = qubits
+                .windows(2)
+                .take(qubits.len() - 2)
+                .map(|pair| {
+                    format!(
+                        "{} {}[{}],{}[{}];",
+                        cnot_name, qubit_register_name, pair[0], qubit_register_name, pair[1]
+                    )
+                })
+                .collect();
+            let mut output: Vec<String> = ladder.clone();
+            output.push(format!(
+                "{}({}) {}[{}],{}[{}];",
+                zz_name,
+                op.theta(),
+                qubit_register_name,
+                qubits[qubits.len() - 2],
+                qubit_register_name,
+                qubits[qubits.len() - 1]
+            ));
+            output.extend(ladder.into_iter().rev());
+            Ok(output.join("\n"))
+        }
+        Operation::MultiQubitMS(op) => {
+            variable_gathering(op.theta(), qasm_version, variable_gatherer)?;
+            let qubits = op.qubits();
+            if qubits.is_empty() {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: "MultiQubitMS needs at least 1 qubit to be translated".to_string(),
+                });
+            }
+            let (basis_change, cnot_name) = match qasm_version {
+                // OpenQASM 3.0 uses the gate defined for the number of qubits
+                QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+                | QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+                | QasmVersion::V3point0(Qasm3Dialect::Qiskit)
+                | QasmVersion::V3point0(Qasm3Dialect::IonQ) => {
+                    return Ok(format!(
+                        "rxx_multi_{}({}) {};",
+                        qubits.len(),
+                        op.theta(),
+                        qubits
+                            .iter()
+                            .map(|qubit| format!("{}[{}]", qubit_register_name, qubit))
+                            .collect::<Vec<String>>()
+                            .join(",")
+                    ));
+                }
+                QasmVersion::V3point0(Qasm3Dialect::Braket) => ("h", "cnot"),
+                QasmVersion::V2point0(_) if options.builtin_cnot => ("u2(0,pi)", "CX"),
+                QasmVersion::V2point0(_) => ("u2(0,pi)", "cx"),
+            };
+            // The XX...X rotation is the ZZ...Z rotation in the Hadamard basis, the parity is
+            // collected on the last qubit with a CNOT ladder around a single Z rotation
+            let basis: Vec<String> = qubits
+                .iter()
+                .map(|qubit| format!("{} {}[{}];", basis_change, qubit_register_name, qubit))
+                .collect();
+            let ladder: Vec<String> = qubits
+                .windows(2)
+                .map(|pair| {
+                    format!(
+                        "{} {}[{}],{}[{}];",
+                        cnot_name, qubit_register_name, pair[0], qubit_register_name, pair[1]
+                    )
+                })
+                .collect();
+            let mut output: Vec<String> = basis.clone();
+            output.extend(ladder.clone());
+            output.push(format!(
+                "rz({}) {}[{}];",
+                op.theta(),
+                qubit_register_name,
+                qubits[qubits.len() - 1]
+            ));
+            output.extend(ladder.into_iter().rev());
+            output.extend(basis);
+            Ok(output.join("\n"))
         }
         Operation::PragmaActiveReset(op) => {
             Ok(format!("reset {}[{}];", qubit_register_name, op.qubit(),))
         }
         Operation::PragmaBoostNoise(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {};",
                 op.hqslang(),
@@ -645,58 +1667,62 @@ pub fn call_operation(
                 }
             }
         },
-        Operation::PragmaConditional(op) => match qasm_version {
-            QasmVersion::V2point0(_) => {
-                let mut ite = op.circuit().iter().peekable();
-                let mut data = "".to_string();
-                while let Some(int_op) = ite.next() {
-                    if int_op.tags().contains(&"PragmaConditional") {
-                        return Err(RoqoqoBackendError::GenericError { msg: "For OpenQASM 2.0 we cannot have nested PragmaConditional operations".to_string() });
-                    }
-                    if ite.peek().is_none() {
-                        data.push_str(&format!(
-                            "if({}[{}]==1) {}",
-                            op.condition_register(),
-                            op.condition_index(),
-                            call_operation(
-                                int_op,
-                                qubit_register_name,
-                                qasm_version,
-                                variable_gatherer
-                            )?
-                        ));
-                    } else {
-                        data.push_str(&format!(
-                            "if({}[{}]==1) {}\n",
-                            op.condition_register(),
-                            op.condition_index(),
-                            call_operation(
-                                int_op,
-                                qubit_register_name,
-                                qasm_version,
-                                variable_gatherer
-                            )?
-                        ));
-                    }
-                }
-                Ok(data)
+        Operation::PragmaConditional(op) => {
+            if let Some(measurement) = op
+                .circuit()
+                .iter()
+                .find(|int_op| int_op.tags().contains(&"Measurement"))
+            {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "Conditional measurements are not supported in OpenQASM, found {} inside PragmaConditional",
+                        measurement.hqslang()
+                    ),
+                });
             }
-            QasmVersion::V3point0(_) => {
-                let mut data = "".to_string();
-                let circuit_vec = call_circuit(op.circuit(), qubit_register_name, qasm_version)?;
-                data.push_str(&format!(
-                    "if({}[{}]==1) {{\n",
-                    op.condition_register(),
-                    op.condition_index(),
-                ));
-                for string in circuit_vec {
-                    data.push_str(string.as_str());
+            let condition = format!(
+                "{}[{}]=={}",
+                op.condition_register(),
+                op.condition_index(),
+                options.conditional_value
+            );
+            let if_body = match qasm_version {
+                QasmVersion::V2point0(_) => {
+                    let mut if_body: Vec<String> = Vec::new();
+                    for int_op in op.circuit().iter() {
+                        if int_op.tags().contains(&"PragmaConditional") {
+                            return Err(RoqoqoBackendError::GenericError { msg: "For OpenQASM 2.0 we cannot have nested PragmaConditional operations".to_string() });
+                        }
+                        if_body.push(call_operation_at_depth(
+                            int_op,
+                            qubit_register_name,
+                            qasm_version,
+                            variable_gatherer,
+                            options,
+                            depth + 1,
+                        )?);
+                    }
+                    if_body
                 }
-                data.push('}');
-                Ok(data)
-            }
-        },
+                QasmVersion::V3point0(_) => call_circuit_with_options(
+                    op.circuit(),
+                    qubit_register_name,
+                    qasm_version,
+                    options,
+                    depth + 1,
+                )?,
+            };
+            // roqoqo's PragmaConditional has no else branch
+            conditional_block(
+                &condition,
+                &if_body,
+                None,
+                qasm_version,
+                options.indent_width,
+            )
+        }
         Operation::PragmaDamping(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {} {};",
                 op.hqslang(),
@@ -716,6 +1742,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaDephasing(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {} {};",
                 op.hqslang(),
@@ -735,6 +1762,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaDepolarising(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {} {};",
                 op.hqslang(),
@@ -754,6 +1782,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGeneralNoise(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {} {};",
                 op.hqslang(),
@@ -773,6 +1802,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGetDensityMatrix(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {};",
                 op.hqslang(),
@@ -791,6 +1821,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGetOccupationProbability(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {};",
                 op.hqslang(),
@@ -809,6 +1840,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGetPauliProduct(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {:?} {} {};",
                 op.hqslang(),
@@ -828,6 +1860,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGetStateVector(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {};",
                 op.hqslang(),
@@ -846,8 +1879,15 @@ pub fn call_operation(
             }
         },
         Operation::PragmaGlobalPhase(op) => match qasm_version {
-            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!("gphase {};", op.phase(),)),
-            QasmVersion::V3point0(Qasm3Dialect::Vanilla) => Ok(format!("gphase {};", op.phase(),)),
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+            | QasmVersion::V3point0(Qasm3Dialect::Vanilla) => {
+                variable_gathering(op.phase(), qasm_version, variable_gatherer)?;
+                Ok(format!("gphase {};", op.phase(),))
+            }
+            QasmVersion::V3point0(Qasm3Dialect::Braket) if !options.drop_unsupported_global_phase => {
+                variable_gathering(op.phase(), qasm_version, variable_gatherer)?;
+                Ok(format!("gphase({});", op.phase()))
+            }
             _ => {
                 if ALLOWED_OPERATIONS.contains(&operation.hqslang()) {
                     Ok("".to_string())
@@ -860,22 +1900,33 @@ pub fn call_operation(
             }
         },
         Operation::PragmaLoop(op) => match qasm_version {
+            _ if matches!(op.repetitions(), CalculatorFloat::Float(x) if *x < 0.0) => {
+                Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "Used PragmaLoop with a negative number of repetitions {}, the number of repetitions must not be negative",
+                        op.repetitions()
+                    ),
+                })
+            }
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {};",
                 op.hqslang(),
                 op.repetitions(),
                 op.circuit()
             )),
-            QasmVersion::V3point0(Qasm3Dialect::Vanilla) => {
+            // A loop without repetitions has no effect and is not emitted
+            _ if matches!(op.repetitions(), CalculatorFloat::Float(x) if *x == 0.0) => {
+                Ok("".to_string())
+            }
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+            | QasmVersion::V3point0(Qasm3Dialect::Qiskit) => {
                 let mut data = "".to_string();
                 match op.repetitions() {
                     CalculatorFloat::Float(x) => {
-                        data.push_str(format!("for uint i in [0:{x}] {{\n").as_str());
-                        let circuit_vec = call_circuit(op.circuit(), qubit_register_name, qasm_version)?;
-                        for string in circuit_vec {
-                            data.push_str(format!("    {string}").as_str());
-                        }
-                        data.push_str("\n}");
+                        let circuit_vec = call_circuit_with_options(op.circuit(), qubit_register_name, qasm_version, options, depth + 1)?;
+                        // The OpenQASM 3.0 ranges include their end
+                        data.push_str(format!("for uint i in [0:{}] {}", x - 1.0, indented_block(&circuit_vec, options.indent_width)).as_str());
                         Ok(data)
                     },
                     CalculatorFloat::Str(x) => Err(RoqoqoBackendError::GenericError { msg: format!("Used PragmaLoop with a string {x} for repetitions and a qasm-version that is incompatible: {qasm_version:?}") })
@@ -886,7 +1937,7 @@ pub fn call_operation(
                 match op.repetitions() {
                     CalculatorFloat::Float(x) => {
                         for _ in 0_usize..(*x as usize) {
-                            let circuit_vec = call_circuit(op.circuit(), qubit_register_name, qasm_version)?;
+                            let circuit_vec = call_circuit_with_options(op.circuit(), qubit_register_name, qasm_version, options, depth + 1)?;
                             for string in circuit_vec {
                                 data.push_str(string.as_str());
                                 data.push('\n');
@@ -898,7 +1949,60 @@ pub fn call_operation(
                 }
             }
         },
+        Operation::PragmaControlledCircuit(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
+                "pragma roqoqo {} {} {};",
+                op.hqslang(),
+                op.controlling_qubit(),
+                op.circuit()
+            )),
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla) => {
+                // Each gate of the circuit is a single call, the controlling qubit is added in
+                // front of its qubit arguments
+                let qubits_start = format!(" {}[", qubit_register_name);
+                let mut data: Vec<String> = Vec::new();
+                for inner_operation in op.circuit().iter() {
+                    let instruction = call_operation_at_depth(
+                        inner_operation,
+                        qubit_register_name,
+                        qasm_version,
+                        variable_gatherer,
+                        options,
+                        depth + 1,
+                    )?;
+                    match instruction.find(&qubits_start) {
+                        Some(position)
+                            if inner_operation.tags().contains(&"GateOperation")
+                                && instruction.lines().count() == 1 =>
+                        {
+                            data.push(format!(
+                                "ctrl @ {} {}[{}],{}",
+                                &instruction[..position],
+                                qubit_register_name,
+                                op.controlling_qubit(),
+                                &instruction[position + 1..]
+                            ))
+                        }
+                        _ => {
+                            return Err(RoqoqoBackendError::GenericError {
+                                msg: format!(
+                                    "The ctrl @ modifier can not be applied to {} inside PragmaControlledCircuit",
+                                    inner_operation.hqslang()
+                                ),
+                            })
+                        }
+                    }
+                }
+                Ok(data.join("\n"))
+            }
+            _ => Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: "QASM",
+                hqslang: operation.hqslang(),
+            }),
+        },
         Operation::PragmaOverrotation(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {:?} {} {};",
                 op.hqslang(),
@@ -919,6 +2023,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaRandomNoise(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {} {} {};",
                 op.hqslang(),
@@ -939,6 +2044,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaRepeatGate(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {};",
                 op.hqslang(),
@@ -974,19 +2080,49 @@ pub fn call_operation(
                     }
                 }
             }
+            // Braket returns the measurement probabilities as a result type of the program
+            if qasm_version == QasmVersion::V3point0(Qasm3Dialect::Braket) {
+                match op.qubit_mapping() {
+                    None => output_string.push_str("\n#pragma braket result probability"),
+                    Some(qm) => {
+                        let mut qubits: Vec<&usize> = qm.keys().collect();
+                        qubits.sort();
+                        output_string.push_str(
+                            format!(
+                                "#pragma braket result probability {}",
+                                qubits
+                                    .iter()
+                                    .map(|qubit| format!("{}[{}]", qubit_register_name, qubit))
+                                    .collect::<Vec<String>>()
+                                    .join(", ")
+                            )
+                            .as_str(),
+                        );
+                    }
+                }
+            }
             if qasm_version == QasmVersion::V3point0(Qasm3Dialect::Roqoqo) {
-                output_string.push_str(
-                    format!(
-                        "\npragma roqoqo PragmaSetNumberOfMeasurements {} {};",
-                        op.number_measurements(),
-                        op.readout(),
-                    )
-                    .as_str(),
-                );
+                if options.json_pragmas {
+                    output_string.push('\n');
+                    output_string.push_str(&json_pragma(&PragmaSetNumberOfMeasurements::new(
+                        *op.number_measurements(),
+                        op.readout().clone(),
+                    ))?);
+                } else {
+                    output_string.push_str(
+                        format!(
+                            "\npragma roqoqo PragmaSetNumberOfMeasurements {} {};",
+                            op.number_measurements(),
+                            op.readout(),
+                        )
+                        .as_str(),
+                    );
+                }
             };
             Ok(output_string)
         }
         Operation::PragmaSetDensityMatrix(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {};",
                 op.hqslang(),
@@ -1004,6 +2140,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaSetNumberOfMeasurements(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {} {};",
                 op.hqslang(),
@@ -1022,6 +2159,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaSetStateVector(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {};",
                 op.hqslang(),
@@ -1039,6 +2177,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaSleep(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {:?} {};",
                 op.hqslang(),
@@ -1075,12 +2214,29 @@ pub fn call_operation(
             }
         },
         Operation::PragmaStartDecompositionBlock(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {:?} {:?};",
                 op.hqslang(),
                 op.qubits(),
                 op.reordering_dictionary()
             )),
+            // The reordering is kept as a human-readable comment
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+                if !op.reordering_dictionary().is_empty() =>
+            {
+                let mut reordering: Vec<(&usize, &usize)> =
+                    op.reordering_dictionary().iter().collect();
+                reordering.sort();
+                Ok(format!(
+                    "// reorder: {}",
+                    reordering
+                        .iter()
+                        .map(|(from, to)| format!("{}->{}", from, to))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ))
+            }
             _ => {
                 if ALLOWED_OPERATIONS.contains(&operation.hqslang()) {
                     Ok("".to_string())
@@ -1093,6 +2249,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaStopDecompositionBlock(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => {
                 Ok(format!("pragma roqoqo {} {:?};", op.hqslang(), op.qubits()))
             }
@@ -1108,6 +2265,7 @@ pub fn call_operation(
             }
         },
         Operation::PragmaStopParallelBlock(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) if options.json_pragmas => json_pragma(op),
             QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
                 "pragma roqoqo {} {:?} {};",
                 op.hqslang(),
@@ -1212,31 +2370,33 @@ pub fn call_operation(
             QasmVersion::V3point0(_) => {
                 Ok(format!("{}[{}] = {};", op.name(), op.index(), op.value()))
             }
-            _ => {
-                if ALLOWED_OPERATIONS.contains(&operation.hqslang()) {
-                    Ok("".to_string())
-                } else {
-                    Err(RoqoqoBackendError::OperationNotInBackend {
-                        backend: "QASM",
-                        hqslang: operation.hqslang(),
-                    })
-                }
-            }
+            QasmVersion::V2point0(_) => Err(RoqoqoBackendError::GenericError {
+                msg: "InputBit requires OpenQASM 3.0".to_string(),
+            }),
         },
-        Operation::CallDefinedGate(op) => Ok(format!(
-            "{}({}) {};",
-            op.gate_name(),
-            op.free_parameters()
-                .iter()
-                .map(|param| param.to_string())
-                .collect::<Vec<String>>()
-                .join(","),
-            op.qubits()
+        Operation::CallDefinedGate(op) => {
+            let qubits = op
+                .qubits()
                 .iter()
                 .map(|qubit| format!("{}[{}]", qubit_register_name, qubit))
                 .collect::<Vec<String>>()
-                .join(",")
-        )),
+                .join(",");
+            // Empty parentheses are rejected by some parsers, they are omitted without parameters
+            if op.free_parameters().is_empty() {
+                Ok(format!("{} {};", op.gate_name(), qubits))
+            } else {
+                Ok(format!(
+                    "{}({}) {};",
+                    op.gate_name(),
+                    op.free_parameters()
+                        .iter()
+                        .map(|param| param.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    qubits
+                ))
+            }
+        }
         Operation::SqrtPauliY(op) => Ok(format!("sy {}[{}];", qubit_register_name, op.qubit())),
         Operation::InvSqrtPauliY(op) => {
             Ok(format!("sydg {}[{}];", qubit_register_name, op.qubit()))
@@ -1256,6 +2416,8 @@ pub fn call_operation(
 
 /// Outputs the QASM gate definition of many qoqo operations.
 ///
+/// Gates that the QASM version provides natively have an empty definition.
+///
 /// # Arguments
 ///
 /// * `operation` - The roqoqo Operation to be defined.
@@ -1268,7 +2430,43 @@ pub fn gate_definition(
     operation: &Operation,
     qasm_version: QasmVersion,
 ) -> Result<String, RoqoqoBackendError> {
-    match operation {
+    gate_definition_with_options(operation, qasm_version, &QasmOptions::default())
+}
+
+/// Outputs the QASM gate definition of many qoqo operations using the given options.
+///
+/// Gates that the QASM version provides natively have an empty definition.
+///
+/// # Arguments
+///
+/// * `operation` - The roqoqo Operation to be defined.
+/// * `qasm_version` - The QASM version to use.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The gate QASM gate definition.
+/// * `RoqoqoBackendError::OperationNotInBackend` - Operation not supported by QASM backend.
+pub fn gate_definition_with_options(
+    operation: &Operation,
+    qasm_version: QasmVersion,
+    options: &QasmOptions,
+) -> Result<String, RoqoqoBackendError> {
+    if is_native_gate(operation, qasm_version, options) {
+        return Ok(String::new());
+    }
+    if let Some(gate) = ctrl_modifier_gate(operation, qasm_version, options)
+        .or_else(|| inv_modifier_gate(operation, qasm_version))
+    {
+        return gate_definition_with_options(&gate, qasm_version, options);
+    }
+    // The decompositions call the built-in CX of OpenQASM 2.0 instead of cx when requested
+    let cx = if options.builtin_cnot && matches!(qasm_version, QasmVersion::V2point0(_)) {
+        "CX"
+    } else {
+        "cx"
+    };
+    let definition = match operation {
         Operation::RotateX(_) => Ok(String::from(
             "gate rx(theta) a { u3(theta,-pi/2,pi/2) a; }"
         )),
@@ -1290,9 +2488,15 @@ pub fn gate_definition(
         Operation::SGate(_) => Ok(String::from(
             "gate s a { u1(pi/2) a; }"
         )),
+        Operation::InvSGate(_) => Ok(String::from(
+            "gate sdg a { u1(-pi/2) a; }"
+        )),
         Operation::TGate(_) => Ok(String::from(
             "gate t a { u1(pi/4) a; }"
         )),
+        Operation::InvTGate(_) => Ok(String::from(
+            "gate tdg a { u1(-pi/4) a; }"
+        )),
         Operation::Hadamard(_) => Ok(String::from(
             "gate h a { u2(0,pi) a; }"
         )),
@@ -1316,80 +2520,158 @@ pub fn gate_definition(
         Operation::Identity(_) => Ok(String::from(
             "gate id a { U(0,0,0) a; }"
         )),
-        Operation::MolmerSorensenXX(_) | Operation::VariableMSXX(_) => Ok(String::from(
-            "gate rxx(theta) a,b { u3(pi/2,theta,0) a; u2(0,pi) b; cx a,b; u1(-theta) b; cx a,b; u2(0,pi) b; u2(-pi,pi-theta) a; }"
+        Operation::MolmerSorensenXX(_) | Operation::VariableMSXX(_) => Ok(format!(
+            "gate rxx(theta) a,b {{ u3(pi/2,theta,0) a; u2(0,pi) b; {cx} a,b; u1(-theta) b; {cx} a,b; u2(0,pi) b; u2(-pi,pi-theta) a; }}"
         )),
-        Operation::ControlledPauliY(_) => Ok(String::from(
-            "gate cy a,b { u1(-pi/2) b; cx a,b; u1(pi/2) b; }"
+        Operation::MultiQubitZZ(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+            | QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+            | QasmVersion::V3point0(Qasm3Dialect::Qiskit) => {
+                // The parity of all qubits is collected on the last qubit with a CNOT ladder
+                // around a single Z rotation
+                let qubits: Vec<String> = (0..op.qubits().len())
+                    .map(|qubit| format!("a{}", qubit))
+                    .collect();
+                let ladder: Vec<String> = qubits
+                    .windows(2)
+                    .map(|pair| format!("cx {},{};", pair[0], pair[1]))
+                    .collect();
+                let mut body: Vec<String> = ladder.clone();
+                if let Some(last) = qubits.last() {
+                    body.push(format!("rz(theta) {};", last));
+                }
+                body.extend(ladder.into_iter().rev());
+                Ok(format!(
+                    "gate rzz_multi_{}(theta) {} {{ {} }}",
+                    qubits.len(),
+                    qubits.join(","),
+                    body.join(" ")
+                ))
+            }
+            _ => Ok(format!(
+                "gate rzz(theta) a,b {{ {cx} a,b; rz(theta) b; {cx} a,b; }}"
+            )),
+        },
+        Operation::MultiQubitMS(op) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla)
+            | QasmVersion::V3point0(Qasm3Dialect::Roqoqo)
+            | QasmVersion::V3point0(Qasm3Dialect::Qiskit) => {
+                let qubits: Vec<String> = (0..op.qubits().len())
+                    .map(|qubit| format!("a{}", qubit))
+                    .collect();
+                let basis: Vec<String> = qubits
+                    .iter()
+                    .map(|qubit| format!("u3(pi/2,0,pi) {};", qubit))
+                    .collect();
+                let ladder: Vec<String> = qubits
+                    .windows(2)
+                    .map(|pair| format!("cx {},{};", pair[0], pair[1]))
+                    .collect();
+                let mut body: Vec<String> = basis.clone();
+                body.extend(ladder.clone());
+                if let Some(last) = qubits.last() {
+                    body.push(format!("rz(theta) {};", last));
+                }
+                body.extend(ladder.into_iter().rev());
+                body.extend(basis);
+                Ok(format!(
+                    "gate rxx_multi_{}(theta) {} {{ {} }}",
+                    qubits.len(),
+                    qubits.join(","),
+                    body.join(" ")
+                ))
+            }
+            // The other versions decompose MultiQubitMS into gates that are always defined
+            _ => Ok(String::new()),
+        },
+        Operation::ControlledPauliY(_) => Ok(format!(
+            "gate cy a,b {{ u1(-pi/2) b; {cx} a,b; u1(pi/2) b; }}"
         )),
-        Operation::ControlledPauliZ(_) => Ok(String::from(
-            "gate cz a,b { u2(0,pi) b; cx a,b; u2(0,pi) b; }"
+        Operation::ControlledPauliZ(_) => Ok(format!(
+            "gate cz a,b {{ u2(0,pi) b; {cx} a,b; u2(0,pi) b; }}"
         )),
-        Operation::EchoCrossResonance(_) => Ok(String::from(
-            "gate ecr a,b { u1(pi/2) a; u1(pi/2) a; u3(pi/2,0,0) a; u3(pi,-pi/2,pi/2) b; u3(-pi/2,0,0) a; cx a,b; u3(-pi/2,-pi/2,pi/2) b; u1(-pi/2) a; u3(pi/2,0,0) a; u3(-pi/2,0,0) a; u3(pi,0,pi) a; }"
+        Operation::EchoCrossResonance(_) => Ok(format!(
+            "gate ecr a,b {{ u1(pi/2) a; u1(pi/2) a; u3(pi/2,0,0) a; u3(pi,-pi/2,pi/2) b; u3(-pi/2,0,0) a; {cx} a,b; u3(-pi/2,-pi/2,pi/2) b; u1(-pi/2) a; u3(pi/2,0,0) a; u3(-pi/2,0,0) a; u3(pi,0,pi) a; }}"
         )),
-        Operation::ControlledPhaseShift(_) => Ok(String::from(
-            "gate cp(lambda) a,b { U(0,0,lambda/2) a; cx a,b; U(0,0,-lambda/2) b; cx a,b; U(0,0,lambda/2) b; }"
+        Operation::ControlledPhaseShift(_) => Ok(format!(
+            "gate {}(lambda) a,b {{ U(0,0,lambda/2) a; {cx} a,b; U(0,0,-lambda/2) b; {cx} a,b; U(0,0,lambda/2) b; }}",
+            controlled_phase_name(qasm_version, options)
         )),
-        Operation::ControlledRotateX(_) => Ok(String::from(
-            "gate crx(theta) a,b { u2(0,pi) b; u1(theta/2) b; cx a,b; u1(-theta/2) b; cx a,b; u2(0,pi) b; }"
+        Operation::ControlledRotateX(_) => Ok(format!(
+            "gate crx(theta) a,b {{ u2(0,pi) b; u1(theta/2) b; {cx} a,b; u1(-theta/2) b; {cx} a,b; u2(0,pi) b; }}"
         )),
-        Operation::ControlledRotateXY(_) => Ok(String::from(
-            "gate crxy(theta,phi) a,b { u1(-phi) b; u2(0,pi) b; u1(theta/2) b; cx a,b; u1(-theta/2) b; cx a,b; u2(0,pi) b; u1(phi) b; }"
+        Operation::ControlledRotateXY(_) => Ok(format!(
+            "gate crxy(theta,phi) a,b {{ u1(-phi) b; u2(0,pi) b; u1(theta/2) b; {cx} a,b; u1(-theta/2) b; {cx} a,b; u2(0,pi) b; u1(phi) b; }}"
         )),
-        Operation::SWAP(_) => Ok(String::from(
-            "gate swap a,b { cx a,b; cx b,a; cx a,b; }"
+        Operation::SWAP(_) => Ok(format!(
+            "gate swap a,b {{ {cx} a,b; {cx} b,a; {cx} a,b; }}"
         )),
-        Operation::ISwap(_) => Ok(String::from(
-            "gate iswap a,b { rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }"
+        Operation::ISwap(_) => Ok(format!(
+            "gate iswap a,b {{ rx(pi/2) a; {cx} a,b; rx(-pi/2) a; ry(-pi/2) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::SqrtISwap(_) => Ok(String::from(
-            "gate siswap a,b { rx(pi/2) a; cx a,b; rx(-pi/4) a; ry(-pi/4) b; cx a,b; rx(-pi/2) a; }"
+        Operation::SqrtISwap(_) => Ok(format!(
+            "gate siswap a,b {{ rx(pi/2) a; {cx} a,b; rx(-pi/4) a; ry(-pi/4) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::InvSqrtISwap(_) => Ok(String::from(
-            "gate siswapdg a,b { rx(pi/2) a; cx a,b; rx(pi/4) a; ry(pi/4) b; cx a,b; rx(-pi/2) a; }"
+        Operation::InvSqrtISwap(_) => Ok(format!(
+            "gate siswapdg a,b {{ rx(pi/2) a; {cx} a,b; rx(pi/4) a; ry(pi/4) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::FSwap(_) => Ok(String::from(
-            "gate fswap a,b { rz(-pi/2) a; rz(-pi/2) b; rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }"
+        Operation::FSwap(_) => Ok(format!(
+            "gate fswap a,b {{ rz(-pi/2) a; rz(-pi/2) b; rx(pi/2) a; {cx} a,b; rx(-pi/2) a; ry(-pi/2) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::Fsim(_) => Ok(String::from(
-            "gate fsim(t,u,delta) a,b { rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; ry(-t+delta+pi/2) a; rx(pi) a; ry(-pi/2) b; rz((u-pi)/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(pi) a; ry(t+delta+pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; rz((-u-pi)/2) a; rz((-u-pi)/2) b; }"
+        Operation::Fsim(_) => Ok(format!(
+            "gate fsim(t,u,delta) a,b {{ rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; ry(-t+delta+pi/2) a; rx(pi) a; ry(-pi/2) b; rz((u-pi)/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(pi) a; ry(t+delta+pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; rz((-u-pi)/2) a; rz((-u-pi)/2) b; }}"
         )),
-        Operation::PMInteraction(_) => Ok(String::from(
-            "gate pmint(theta) a,b { rx(pi/2) a; cx a,b; rx(theta) a; ry(theta) b; cx a,b; rx(-pi/2) a; }"
+        Operation::PMInteraction(_) => Ok(format!(
+            "gate pmint(theta) a,b {{ rx(pi/2) a; {cx} a,b; rx(theta) a; ry(theta) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::GivensRotation(_) => Ok(String::from(
-            "gate gvnsrot(theta,phi) a,b { rz(phi+pi/2) b; rx(pi/2) a; cx a,b; rx(-theta) a; ry(-theta) b; cx a,b; rx(-pi/2) a; rz(-pi/2) b; }"
+        Operation::GivensRotation(_) => Ok(format!(
+            "gate gvnsrot(theta,phi) a,b {{ rz(phi+pi/2) b; rx(pi/2) a; {cx} a,b; rx(-theta) a; ry(-theta) b; {cx} a,b; rx(-pi/2) a; rz(-pi/2) b; }}"
         )),
-        Operation::GivensRotationLittleEndian(_) => Ok(String::from(
-            "gate gvnsrotle(theta,phi) a,b { rz(-pi/2) a; rx(pi/2) a; cx a,b; rx(-theta) a; ry(-theta) b; cx a,b; rx(-pi/2) a; rz(phi+pi/2) a; }"
+        Operation::GivensRotationLittleEndian(_) => Ok(format!(
+            "gate gvnsrotle(theta,phi) a,b {{ rz(-pi/2) a; rx(pi/2) a; {cx} a,b; rx(-theta) a; ry(-theta) b; {cx} a,b; rx(-pi/2) a; rz(phi+pi/2) a; }}"
         )),
-        Operation::Qsim(_) => Ok(String::from(
-            "gate qsim(xc,yc,zc) a,b { rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; ry(-2*xc+pi/2) a; rx(pi) a; ry(-pi/2) b; rz(2*zc-pi) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(pi) a; ry(2*yc+pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; }"
+        Operation::Bogoliubov(_) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
+                "gate bogoliubov(delta_real,delta_imag) a,b {{ rz(atan2(delta_imag,delta_real)) b; u3(pi,0,pi) b; rx(pi/2) a; {cx} a,b; rx(-sqrt(delta_real^2+delta_imag^2)) a; ry(-sqrt(delta_real^2+delta_imag^2)) b; {cx} a,b; rx(-pi/2) a; u3(pi,0,pi) b; rz(-atan2(delta_imag,delta_real)) b; }}"
+            )),
+            _ => Ok(format!(
+                "gate bogoliubov(delta_abs,delta_arg) a,b {{ rz(delta_arg) b; u3(pi,0,pi) b; rx(pi/2) a; {cx} a,b; rx(-delta_abs) a; ry(-delta_abs) b; {cx} a,b; rx(-pi/2) a; u3(pi,0,pi) b; rz(-delta_arg) b; }}"
+            )),
+        },
+        Operation::ComplexPMInteraction(_) => match qasm_version {
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo) => Ok(format!(
+                "gate cpmint(t_real,t_imag) a,b {{ rz(atan2(t_imag,t_real)) b; rx(pi/2) a; {cx} a,b; rx(sqrt(t_real^2+t_imag^2)) a; ry(sqrt(t_real^2+t_imag^2)) b; {cx} a,b; rx(-pi/2) a; rz(-atan2(t_imag,t_real)) b; }}"
+            )),
+            _ => Ok(format!(
+                "gate cpmint(t_abs,t_arg) a,b {{ rz(t_arg) b; rx(pi/2) a; {cx} a,b; rx(t_abs) a; ry(t_abs) b; {cx} a,b; rx(-pi/2) a; rz(-t_arg) b; }}"
+            )),
+        },
+        Operation::Qsim(_) => Ok(format!(
+            "gate qsim(xc,yc,zc) a,b {{ rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; ry(-2*xc+pi/2) a; rx(pi) a; ry(-pi/2) b; rz(2*zc-pi) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(pi) a; ry(2*yc+pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; }}"
         )),
-        Operation::XY(_) => Ok(String::from(
-            "gate xy(theta) a,b { rx(pi/2) a; cx a,b; rx(-theta/2) a; ry(-theta/2) b; cx a,b; rx(-pi/2) a; }"
+        Operation::XY(_) => Ok(format!(
+            "gate xy(theta) a,b {{ rx(pi/2) a; {cx} a,b; rx(-theta/2) a; ry(-theta/2) b; {cx} a,b; rx(-pi/2) a; }}"
         )),
-        Operation::SpinInteraction(_) => Ok(String::from(
-            "gate spinint(xc,yc,zc) a,b { rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; ry(-2*xc) a; rx(pi) a; ry(-pi/2) b; rz(2*zc-pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(pi) a; ry(2*yc+pi) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; cx a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; }"
+        Operation::SpinInteraction(_) => Ok(format!(
+            "gate spinint(xc,yc,zc) a,b {{ rz(-pi/2) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; ry(-2*xc) a; rx(pi) a; ry(-pi/2) b; rz(2*zc-pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(pi) a; ry(2*yc+pi) a; rz(pi) b; ry(pi/2) b; u2(0,pi) b; {cx} a,b; u2(0,pi) b; rz(-pi/2) b; rx(-pi/2) b; }}"
         )),
-        Operation::PhaseShiftedControlledZ(_) => Ok(String::from(
-            "gate pscz(phi) a,b { rz(pi/2) a; rz(pi/2) b; ry(pi/2) b; cx a,b; rx(-pi/2) b; rz(-pi/2) a; ry(-pi/2) b; rz(phi) a; rz(phi) b; }"
+        Operation::PhaseShiftedControlledZ(_) => Ok(format!(
+            "gate pscz(phi) a,b {{ rz(pi/2) a; rz(pi/2) b; ry(pi/2) b; {cx} a,b; rx(-pi/2) b; rz(-pi/2) a; ry(-pi/2) b; rz(phi) a; rz(phi) b; }}"
         )),
-        Operation::PhaseShiftedControlledPhase(_) => Ok(String::from(
-            "gate pscp(theta,phi) a,b { rz(theta/2) a; rz(theta/2) b; cx a,b; rz(-theta/2) b; cx a,b; rz(phi) a; rz(phi) b; }"
+        Operation::PhaseShiftedControlledPhase(_) => Ok(format!(
+            "gate pscp(theta,phi) a,b {{ rz(theta/2) a; rz(theta/2) b; {cx} a,b; rz(-theta/2) b; {cx} a,b; rz(phi) a; rz(phi) b; }}"
         )),
         Operation::RotateXY(_) => Ok(String::from(
             "gate rxy(theta,phi) q { u3(theta,phi-pi/2,pi/2-phi) q; }"
         )),
-        Operation::Toffoli(_) => Ok(String::from(
-            "gate ccx a,b,c { u2(0,pi) c; cx b,c; u1(-pi/4) c; cx a,c; u1(pi/4) c; cx b,c; u1(-pi/4) c; cx a,c; u1(pi/4) b; u1(pi/4) c; u2(0,pi) c; cx a,b; u1(pi/4) a; u1(-pi/4) b; cx a,b; }"
+        Operation::Toffoli(_) => Ok(format!(
+            "gate ccx a,b,c {{ u2(0,pi) c; {cx} b,c; u1(-pi/4) c; {cx} a,c; u1(pi/4) c; {cx} b,c; u1(-pi/4) c; {cx} a,c; u1(pi/4) b; u1(pi/4) c; u2(0,pi) c; {cx} a,b; u1(pi/4) a; u1(-pi/4) b; {cx} a,b; }}"
         )),
-        Operation::ControlledControlledPauliZ(_) => Ok(String::from(
-            "gate ccz a,b,c { U(0,0,pi/4) b; cx b,c; U(0,0,-pi/4) c; cx b,c; U(0,0,pi/4) c; cx a,b; U(0,0,-pi/4) b; cx b,c; U(0,0,pi/4) c; cx b,c; U(0,0,-pi/4) c; cx a,b; U(0,0,pi/4) a; cx a,c; U(0,0,-pi/4) c; cx a,c; U(0,0,pi/4) c; }"
+        Operation::ControlledControlledPauliZ(_) => Ok(format!(
+            "gate ccz a,b,c {{ U(0,0,pi/4) b; {cx} b,c; U(0,0,-pi/4) c; {cx} b,c; U(0,0,pi/4) c; {cx} a,b; U(0,0,-pi/4) b; {cx} b,c; U(0,0,pi/4) c; {cx} b,c; U(0,0,-pi/4) c; {cx} a,b; U(0,0,pi/4) a; {cx} a,c; U(0,0,-pi/4) c; {cx} a,c; U(0,0,pi/4) c; }}"
         )),
-        Operation::ControlledControlledPhaseShift(_) => Ok(String::from(
-            "gate ccp(theta) a,b,c { U(0,0,theta/4) b; cx b,c; U(0,0,-theta/4) c; cx b,c; U(0,0,theta/4) c; cx a,b; U(0,0,-theta/4) b; cx b,c; U(0,0,theta/4) c; cx b,c; U(0,0,-theta/4) c; cx a,b; U(0,0,theta/4) a; cx a,c; U(0,0,-theta/4) c; cx a,c; U(0,0,theta/4) c; }"
+        Operation::ControlledControlledPhaseShift(_) => Ok(format!(
+            "gate ccp(theta) a,b,c {{ U(0,0,theta/4) b; {cx} b,c; U(0,0,-theta/4) c; {cx} b,c; U(0,0,theta/4) c; {cx} a,b; U(0,0,-theta/4) b; {cx} b,c; U(0,0,theta/4) c; {cx} b,c; U(0,0,-theta/4) c; {cx} a,b; U(0,0,theta/4) a; {cx} a,c; U(0,0,-theta/4) c; {cx} a,c; U(0,0,theta/4) c; }}"
         )),
         Operation::GPi(_) => match qasm_version {
             QasmVersion::V3point0(Qasm3Dialect::Braket) => Ok(String::from(
@@ -1413,10 +2695,15 @@ pub fn gate_definition(
             "opaque pragmasleep(param) a;"
         )),
         Operation::GateDefinition(gate_definition) => {
+            let parameters = if gate_definition.free_parameters().is_empty() {
+                String::new()
+            } else {
+                format!("({})", gate_definition.free_parameters().join(","))
+            };
             let mut definition_str = format!(
-                "gate {}({}) {}\n{{\n",
+                "gate {}{} {}\n{{\n",
                 gate_definition.name(),
-                gate_definition.free_parameters().join(","),
+                parameters,
                 gate_definition
                     .qubits()
                     .iter()
@@ -1426,11 +2713,12 @@ pub fn gate_definition(
             );
             for operation in gate_definition.circuit().iter() {
                 definition_str.push_str("    ");
-                definition_str.push_str(&call_operation(
+                definition_str.push_str(&call_operation_with_options(
                     operation,
                     "replace_me",
                     qasm_version,
                     &mut None,
+                    options,
                 )?);
                 definition_str.push('\n');
             }
@@ -1457,5 +2745,6 @@ pub fn gate_definition(
                 })
             }
         }
-    }
+    }?;
+    Ok(definition)
 }
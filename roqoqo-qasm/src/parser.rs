@@ -12,9 +12,11 @@
 //
 //! The roqoqo-qasm Parser translates qasm files in Qoqo Circuit instances.
 
+use crate::QasmVersion;
 use num_complex::Complex64;
 use qoqo_calculator::Calculator;
 use roqoqo::RoqoqoBackendError;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
@@ -23,22 +25,191 @@ use qoqo_calculator::CalculatorFloat;
 use roqoqo::operations::*;
 use roqoqo::Circuit;
 
-use pest::error::Error;
-use pest::iterators::Pair;
-use pest::Parser;
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::{Pair, Pairs};
+use pest::{Parser, Span};
 
 /// Pest Parser for QASM -> qoqo translation.
+///
+/// The OpenQASM 3.0 grammar reuses the statements of the OpenQASM 2.0 grammar.
 #[derive(Parser, Debug)]
 #[grammar = "grammars/qasm2_0.pest"]
+#[grammar = "grammars/qasm3_0.pest"]
 struct QoqoQASMParser;
 
+/// Options changing how QASM files are translated to qoqo Circuits.
+///
+/// The default options reproduce the output of [file_to_circuit] and [string_to_circuit].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Whether `gate` definitions redefining a built-in gate name are kept.
+    ///
+    /// When set, the definition is imported as a GateDefinition and the subsequent calls of the
+    /// gate are imported as CallDefinedGate operations. Otherwise the definition is ignored and
+    /// the calls are imported as the built-in qoqo operation.
+    pub honor_builtin_redefinitions: bool,
+    /// The version assumed for input without an `OPENQASM` header.
+    ///
+    /// When set, input that does not start with an `OPENQASM` header is parsed as if it started
+    /// with the header of the given version. Otherwise the header is required.
+    pub assume_version: Option<QasmVersion>,
+    /// Whether the `//` comments directly above a `gate` definition are captured.
+    ///
+    /// When set, the comment lines immediately preceding a `gate` definition are collected as the
+    /// docstring of the defined gate and returned by [string_to_circuit_with_docstrings] and
+    /// [file_to_circuit_with_docstrings]. Otherwise no docstrings are collected.
+    pub capture_gate_docstrings: bool,
+    /// Whether `barrier` statements are kept.
+    ///
+    /// When set, a barrier is imported as a PragmaStopParallelBlock on the qubits of the barrier.
+    /// Otherwise barriers are skipped.
+    pub keep_barriers: bool,
+}
+
+/// The docstrings of the gates defined in a QASM file, keyed by gate name.
+pub type GateDocstrings = HashMap<String, String>;
+
+/// Reads the `ctrl @` or `inv @` modifier of a called gate, if any, and the name of the gate.
+fn modifier_and_gate_name<'i>(inner_pairs: &mut Pairs<'i, Rule>) -> (Option<&'i str>, &'i str) {
+    let modifier = match inner_pairs.peek() {
+        Some(pair) if pair.as_rule() == Rule::modifier => inner_pairs
+            .next()
+            .map(|pair| pair.as_str().trim_end_matches('@').trim_end()),
+        _ => None,
+    };
+    (modifier, inner_pairs.next().unwrap().as_str())
+}
+
+/// Creates the error for a modifier applied to a gate without a matching qoqo operation.
+fn unsupported_modifier_error(modifier: &str, name: &str, span: Span) -> Box<Error<Rule>> {
+    Box::new(Error::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!(
+                "The {} @ modifier is not supported for the gate {}, qoqo has no operation for it",
+                modifier, name
+            ),
+        },
+        span,
+    ))
+}
+
+/// Dispatch function for gates called with a `ctrl @` or `inv @` modifier.
+///
+/// The modified gate is mapped to the qoqo operation implementing it, `None` is returned when qoqo
+/// has no such operation.
+fn modified_gate_dispatch(
+    modifier: &str,
+    name: &str,
+    params: &[String],
+    qubits: &[usize],
+    defined_custom_gates: &[(String, usize, usize)],
+    options: &ParserOptions,
+) -> Option<Operation> {
+    if modifier == "ctrl" {
+        let controlled_name = match name {
+            "x" => "cx",
+            "y" => "cy",
+            "z" => "cz",
+            "p" | "u1" => "cp",
+            "rx" => "crx",
+            "rxy" | "r" => "crxy",
+            "cx" | "CX" => "ccx",
+            "cz" => "ccz",
+            "cp" | "cu1" => "ccp",
+            "swap" => {
+                return Some(Operation::from(ControlledSWAP::new(
+                    qubits[0], qubits[1], qubits[2],
+                )))
+            }
+            _ => return None,
+        };
+        return gate_dispatch(
+            controlled_name,
+            params,
+            qubits,
+            defined_custom_gates,
+            options,
+        );
+    }
+    let inverse_name = match name {
+        "h" | "x" | "y" | "z" | "cx" | "CX" | "cy" | "cz" | "swap" | "fswap" | "ecr" | "ccx"
+        | "ccnot" | "ccz" => name,
+        "s" => "sdg",
+        "sdg" => "s",
+        "t" => "tdg",
+        "tdg" => "t",
+        "sx" => "sxdg",
+        "sxdg" => "sx",
+        "sy" => "sydg",
+        "sydg" => "sy",
+        "siswap" => "siswapdg",
+        "siswapdg" => "siswap",
+        // Rotations are inverted by negating their angle
+        "rx" | "ry" | "rz" | "p" | "u1" | "cp" | "cu1" | "crx" | "rxx" | "xy" | "pmint" | "ccp" => {
+            let mut inverse_params = params.to_vec();
+            inverse_params[0] = (-CalculatorFloat::from(&params[0])).to_string();
+            return gate_dispatch(name, &inverse_params, qubits, defined_custom_gates, options);
+        }
+        _ => return None,
+    };
+    gate_dispatch(inverse_name, params, qubits, defined_custom_gates, options)
+}
+
+/// Evaluates the expression of a gate parameter, symbolic expressions are kept.
+fn evaluate_parameter(expression: &str) -> String {
+    // Handle 'pi' constant and math functions renames (Calculator)
+    let mut param_str = expression.replace("pi", "3.141592653589793");
+    param_str = param_str.replace("ln", "log");
+    // Pass the parsed expression (now float) as String
+    match Calculator::new().parse_str(&param_str) {
+        Ok(parsed) => parsed.to_string(),
+        Err(_) => param_str,
+    }
+}
+
+/// Creates the call of a custom gate defined in the QASM file, if it was defined.
+fn call_defined_gate(
+    name: &str,
+    params: &[String],
+    qubits: &[usize],
+    defined_custom_gates: &[(String, usize, usize)],
+) -> Option<Operation> {
+    defined_custom_gates
+        .contains(&(name.to_owned(), qubits.len(), params.len()))
+        .then(|| {
+            Operation::from(CallDefinedGate::new(
+                name.to_owned(),
+                qubits.to_vec(),
+                params
+                    .iter()
+                    .map(|param| {
+                        let mut param_str = param.replace("pi", "3.141592653589793");
+                        param_str = param_str.replace("ln", "log");
+                        // Numeric expressions are evaluated like the parameters of built-in gates,
+                        // symbolic ones are kept
+                        match Calculator::new().parse_str(&param_str) {
+                            Ok(value) => CalculatorFloat::from(value),
+                            Err(_) => CalculatorFloat::from(param_str),
+                        }
+                    })
+                    .collect(),
+            ))
+        })
+}
+
 /// Dispatch function for qoqo operations.
 fn gate_dispatch(
     name: &str,
     params: &[String],
     qubits: &[usize],
     defined_custom_gates: &[(String, usize, usize)],
+    options: &ParserOptions,
 ) -> Option<Operation> {
+    if options.honor_builtin_redefinitions {
+        if let Some(operation) = call_defined_gate(name, params, qubits, defined_custom_gates) {
+            return Some(operation);
+        }
+    }
     match name {
         "rz" => Some(Operation::from(RotateZ::new(
             qubits[0],
@@ -58,13 +229,19 @@ fn gate_dispatch(
         "z" => Some(Operation::from(PauliZ::new(qubits[0]))),
         "s" => Some(Operation::from(SGate::new(qubits[0]))),
         "t" => Some(Operation::from(TGate::new(qubits[0]))),
+        "sdg" => Some(Operation::from(InvSGate::new(qubits[0]))),
+        "tdg" => Some(Operation::from(InvTGate::new(qubits[0]))),
         "p" => Some(Operation::from(PhaseShiftState1::new(
             qubits[0],
             CalculatorFloat::from(&params[0]),
         ))),
         "sx" => Some(Operation::from(SqrtPauliX::new(qubits[0]))),
         "sxdg" => Some(Operation::from(InvSqrtPauliX::new(qubits[0]))),
-        "cx" => Some(Operation::from(CNOT::new(qubits[0], qubits[1]))),
+        "sy" => Some(Operation::from(SqrtPauliY::new(qubits[0]))),
+        "sydg" => Some(Operation::from(InvSqrtPauliY::new(qubits[0]))),
+        // CX is the OpenQASM 2.0 built-in, used in definitions such as the one of cx, cnot is
+        // the name of the Braket dialect
+        "cx" | "CX" | "cnot" => Some(Operation::from(CNOT::new(qubits[0], qubits[1]))),
         "rxx" => {
             if let Ok(float) = CalculatorFloat::from(&params[0]).float() {
                 if is_close(float.into(), CalculatorFloat::PI.float().unwrap().into()) {
@@ -79,7 +256,7 @@ fn gate_dispatch(
         }
         "cy" => Some(Operation::from(ControlledPauliY::new(qubits[0], qubits[1]))),
         "cz" => Some(Operation::from(ControlledPauliZ::new(qubits[0], qubits[1]))),
-        "cp" => Some(Operation::from(ControlledPhaseShift::new(
+        "cp" | "cu1" => Some(Operation::from(ControlledPhaseShift::new(
             qubits[0],
             qubits[1],
             CalculatorFloat::from(&params[0]),
@@ -162,7 +339,8 @@ fn gate_dispatch(
             CalculatorFloat::from(&params[0]),
             CalculatorFloat::from(&params[1]),
         ))),
-        "u3" => {
+        // OpenQASM 3.0 names the three parameter gate u, and U for the built-in
+        "u3" | "u" | "U" => {
             let theta = CalculatorFloat::from(&params[0]);
             let phi = CalculatorFloat::from(&params[1]);
             let lambda = CalculatorFloat::from(&params[2]);
@@ -219,7 +397,7 @@ fn gate_dispatch(
                 CalculatorFloat::ZERO,
             )))
         }
-        "ccx" => Some(Operation::from(Toffoli::new(
+        "ccx" | "ccnot" => Some(Operation::from(Toffoli::new(
             qubits[0], qubits[1], qubits[2],
         ))),
         "ccz" => Some(Operation::from(ControlledControlledPauliZ::new(
@@ -231,38 +409,65 @@ fn gate_dispatch(
             qubits[2],
             CalculatorFloat::from(&params[0]),
         ))),
-        _ => defined_custom_gates
-            .contains(&(name.to_owned(), qubits.len(), params.len()))
-            .then(|| {
-                Operation::from(CallDefinedGate::new(
-                    name.to_owned(),
-                    qubits.to_vec(),
-                    params
-                        .iter()
-                        .map(|param| {
-                            let mut param_str = param.replace("pi", "3.141592653589793");
-                            param_str = param_str.replace("ln", "log");
-                            CalculatorFloat::from(param_str)
-                        })
-                        .collect(),
-                ))
-            }),
+        _ => call_defined_gate(name, params, qubits, defined_custom_gates),
     }
 }
 
 /// Main parse function method.
-fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
-    let pairs = QoqoQASMParser::parse(Rule::openqasm, file)?;
+///
+/// Returns the circuit and the docstrings of the defined gates, keyed by gate name.
+fn parse_qasm_file(
+    file: &str,
+    options: &ParserOptions,
+) -> Result<(Circuit, GateDocstrings), Box<Error<Rule>>> {
+    // CRLF and lone CR line endings are normalized, so that the grammar only sees LF
+    let mut file = file.replace("\r\n", "\n").replace('\r', "\n");
+    if let Some(version) = options.assume_version {
+        if !file.trim_start().starts_with("OPENQASM") {
+            let header = match version {
+                QasmVersion::V2point0(_) => "OPENQASM 2.0;\n",
+                QasmVersion::V3point0(_) => "OPENQASM 3.0;\n",
+            };
+            file.insert_str(0, header);
+        }
+    }
+    // The grammar is selected by the version in the header
+    let is_qasm3 = file
+        .trim_start()
+        .strip_prefix("OPENQASM")
+        .is_some_and(|rest| rest.trim_start().starts_with('3'));
+    let rule = if is_qasm3 {
+        Rule::openqasm3
+    } else {
+        Rule::openqasm
+    };
+    let pairs = QoqoQASMParser::parse(rule, &file)?;
     let mut circuit = Circuit::new();
     let mut defined_custom_gates: Vec<(String, usize, usize)> = vec![];
+    let mut qubit_registers: HashMap<String, usize> = HashMap::new();
     /// The parsing works like an AST traversal. The structure is defined by the grammar.
     ///     - pair.as_rule() represents the rule itself, to get into the inner ones, `.into_inner()` is called
     ///     - from the new inner instance we can further move to the right in the rule by calling `.next().unwrap()[.as_str()]`
     fn parse_single_rule(
         pair: Pair<Rule>,
         defined_custom_gates: &mut Vec<(String, usize, usize)>,
-    ) -> Option<Operation> {
-        match pair.as_rule() {
+        qubit_registers: &mut HashMap<String, usize>,
+        options: &ParserOptions,
+    ) -> Result<Option<Operation>, Box<Error<Rule>>> {
+        let op = match pair.as_rule() {
+            Rule::q_decl | Rule::qubit_decl => {
+                // The register sizes are only needed to expand barriers on whole registers
+                let mut id = "";
+                let mut size = 0;
+                for inner_pair in pair.into_inner() {
+                    match inner_pair.as_rule() {
+                        Rule::id => id = inner_pair.as_str(),
+                        _ => size = inner_pair.as_str().parse::<usize>().unwrap(),
+                    }
+                }
+                qubit_registers.insert(id.to_owned(), size);
+                None
+            }
             Rule::c_decl => {
                 let mut inner_pairs = pair.into_inner();
                 let id = inner_pairs.next().unwrap().as_str();
@@ -278,9 +483,31 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                     true,
                 )))
             }
-            Rule::gate => {
+            Rule::bit_decl => {
                 let mut inner_pairs = pair.into_inner();
+                let is_output = inner_pairs
+                    .peek()
+                    .is_some_and(|pair| pair.as_rule() == Rule::output);
+                if is_output {
+                    inner_pairs.next();
+                }
+                let integer = inner_pairs
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .parse::<usize>()
+                    .unwrap();
                 let id = inner_pairs.next().unwrap().as_str();
+                Some(Operation::from(DefinitionBit::new(
+                    id.to_string(),
+                    integer,
+                    is_output,
+                )))
+            }
+            Rule::gate | Rule::gate3 => {
+                let span = pair.as_span();
+                let mut inner_pairs = pair.into_inner();
+                let (modifier, id) = modifier_and_gate_name(&mut inner_pairs);
                 let mut params: Vec<String> = vec![];
                 let mut qubits: Vec<usize> = vec![];
                 for pair in inner_pairs.clone() {
@@ -288,15 +515,26 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                         Rule::parameter_list => {
                             let params_list = inner_pairs.next().unwrap().into_inner();
                             for param in params_list {
-                                // Handle 'pi' constant and math functions renames (Calculator)
-                                let mut param_str =
-                                    param.as_str().replace("pi", "3.141592653589793");
-                                param_str = param_str.replace("ln", "log");
-                                // Parse the mathematical expression
-                                let calc = Calculator::new();
-                                let parsed = calc.parse_str(&param_str).unwrap();
-                                // Pass the parsed expression (now float) as String
-                                params.push(parsed.to_string());
+                                // Classical bits in parameters are only known at runtime
+                                if let Some(bit) = param
+                                    .clone()
+                                    .into_inner()
+                                    .flatten()
+                                    .find(|inner| inner.as_rule() == Rule::argument)
+                                {
+                                    return Err(Box::new(Error::new_from_span(
+                                        ErrorVariant::CustomError {
+                                            message: format!(
+                                                "Dynamic classical-parameterized gates are not supported, found classical bit {} in parameter {} of gate {}",
+                                                bit.as_str(),
+                                                param.as_str(),
+                                                id
+                                            ),
+                                        },
+                                        param.as_span(),
+                                    )));
+                                }
+                                params.push(evaluate_parameter(param.as_str()));
                             }
                         }
                         Rule::qubit_list => {
@@ -316,7 +554,20 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                         _ => continue,
                     }
                 }
-                gate_dispatch(id, &params, &qubits, defined_custom_gates)
+                match modifier {
+                    Some(modifier) => Some(
+                        modified_gate_dispatch(
+                            modifier,
+                            id,
+                            &params,
+                            &qubits,
+                            defined_custom_gates,
+                            options,
+                        )
+                        .ok_or_else(|| unsupported_modifier_error(modifier, id, span))?,
+                    ),
+                    None => gate_dispatch(id, &params, &qubits, defined_custom_gates, options),
+                }
             }
             Rule::measurement => {
                 let mut inner_pairs = pair.into_inner();
@@ -332,6 +583,37 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                     second_integer.parse::<usize>().unwrap(),
                 )))
             }
+            Rule::measure_all => {
+                // Every qubit is measured, so the name of the quantum register is not needed
+                let mut inner_pairs = pair.into_inner();
+                let _qubit_register = inner_pairs.next().unwrap().as_str();
+                let readout = inner_pairs.next().unwrap().as_str();
+                Some(Operation::from(PragmaRepeatedMeasurement::new(
+                    readout.to_string(),
+                    1,
+                    None,
+                )))
+            }
+            Rule::measure_assign => {
+                let mut inner_pairs = pair.into_inner();
+                let mut bit_argument = inner_pairs.next().unwrap().into_inner();
+                let bit_id = bit_argument.next().unwrap().as_str();
+                let bit_integer = bit_argument.next().unwrap().as_str();
+                let mut qubit_argument = inner_pairs.next().unwrap().into_inner();
+                let _qubit_id = qubit_argument.next().unwrap().as_str();
+                let qubit_integer = qubit_argument.next().unwrap().as_str();
+                Some(Operation::from(MeasureQubit::new(
+                    qubit_integer.parse::<usize>().unwrap(),
+                    bit_id.to_string(),
+                    bit_integer.parse::<usize>().unwrap(),
+                )))
+            }
+            Rule::gphase => {
+                let phase = pair.into_inner().next().unwrap().as_str();
+                Some(Operation::from(PragmaGlobalPhase::new(
+                    CalculatorFloat::from(evaluate_parameter(phase)),
+                )))
+            }
             Rule::reset => {
                 let mut inner_pairs = pair.into_inner();
                 let mut first_argument = inner_pairs.next().unwrap().into_inner();
@@ -341,23 +623,149 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                     first_integer.parse::<usize>().unwrap(),
                 )))
             }
-            Rule::gate_def => {
+            Rule::barrier if !options.keep_barriers => None,
+            Rule::barrier => {
+                let mut qubits: Vec<usize> = vec![];
+                for qbt_rule in pair.into_inner() {
+                    match qbt_rule.as_rule() {
+                        Rule::argument => {
+                            let mut inner_pairs = qbt_rule.into_inner();
+                            let _id = inner_pairs.next().unwrap().as_str();
+                            qubits.push(
+                                inner_pairs
+                                    .next()
+                                    .unwrap()
+                                    .as_str()
+                                    .parse::<usize>()
+                                    .unwrap(),
+                            );
+                        }
+                        // A barrier on a whole register acts on all of its qubits
+                        _ => match qubit_registers.get(qbt_rule.as_str()) {
+                            Some(size) => qubits.extend(0..*size),
+                            None => {
+                                return Err(Box::new(Error::new_from_span(
+                                    ErrorVariant::CustomError {
+                                        message: format!(
+                                            "Barrier on the undeclared qubit register {}",
+                                            qbt_rule.as_str()
+                                        ),
+                                    },
+                                    qbt_rule.as_span(),
+                                )))
+                            }
+                        },
+                    }
+                }
+                Some(Operation::from(PragmaStopParallelBlock::new(
+                    qubits,
+                    CalculatorFloat::ZERO,
+                )))
+            }
+            Rule::conditional | Rule::if_block => {
+                let span = pair.as_span();
                 let mut inner_pairs = pair.into_inner();
-                let id = inner_pairs.next().unwrap().as_str();
-                if gate_dispatch(
-                    id,
-                    &[
-                        "0.0".to_owned(),
-                        "0.0".to_owned(),
-                        "0.0".to_owned(),
-                        "0.0".to_owned(),
-                    ],
-                    &[0_usize, 1_usize, 2_usize, 3_usize],
+                let condition = inner_pairs.next().unwrap();
+                let value = inner_pairs.next().unwrap().as_str();
+                let (register, index) = match condition.as_rule() {
+                    Rule::argument => {
+                        let mut argument = condition.into_inner();
+                        let register = argument.next().unwrap().as_str();
+                        let index = argument.next().unwrap().as_str().parse::<usize>().unwrap();
+                        (register, index)
+                    }
+                    _ => {
+                        return Err(Box::new(Error::new_from_span(
+                            ErrorVariant::CustomError {
+                                message: format!(
+                                    "Conditions on the whole classical register {} are not supported, only conditions on single bits such as {}[0]==1",
+                                    condition.as_str(),
+                                    condition.as_str()
+                                ),
+                            },
+                            span,
+                        )))
+                    }
+                };
+                if value.parse::<usize>().unwrap() != 1 {
+                    return Err(Box::new(Error::new_from_span(
+                        ErrorVariant::CustomError {
+                            message: format!(
+                                "Only conditions on a bit being set are supported, found {}[{}]=={}",
+                                register, index, value
+                            ),
+                        },
+                        span,
+                    )));
+                }
+                let body = inner_pairs.next().unwrap();
+                let conditional_circuit = match body.as_rule() {
+                    Rule::block => parse_block(
+                        body.into_inner(),
+                        defined_custom_gates,
+                        qubit_registers,
+                        options,
+                    )?,
+                    // The OpenQASM 2.0 form conditions a single statement
+                    _ => parse_block(
+                        std::iter::once(body),
+                        defined_custom_gates,
+                        qubit_registers,
+                        options,
+                    )?,
+                };
+                Some(Operation::from(PragmaConditional::new(
+                    register.to_string(),
+                    index,
+                    conditional_circuit,
+                )))
+            }
+            Rule::for_loop => {
+                let mut inner_pairs = pair.into_inner();
+                let _loop_variable = inner_pairs.next().unwrap().as_str();
+                let start = inner_pairs
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .parse::<usize>()
+                    .unwrap();
+                let end = inner_pairs
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .parse::<usize>()
+                    .unwrap();
+                let loop_circuit = parse_block(
+                    inner_pairs.next().unwrap().into_inner(),
                     defined_custom_gates,
-                )
-                .is_some()
+                    qubit_registers,
+                    options,
+                )?;
+                // The OpenQASM 3.0 ranges include their end
+                Some(Operation::from(PragmaLoop::new(
+                    CalculatorFloat::from(((end + 1).saturating_sub(start)) as f64),
+                    loop_circuit,
+                )))
+            }
+            Rule::gate_def | Rule::gate_def3 => {
+                let mut inner_pairs = pair.into_inner();
+                let id = inner_pairs.next().unwrap().as_str();
+                if !options.honor_builtin_redefinitions
+                    && gate_dispatch(
+                        id,
+                        &[
+                            "0.0".to_owned(),
+                            "0.0".to_owned(),
+                            "0.0".to_owned(),
+                            "0.0".to_owned(),
+                        ],
+                        &[0_usize, 1_usize, 2_usize, 3_usize],
+                        defined_custom_gates,
+                        options,
+                    )
+                    .is_some()
                 {
-                    return None;
+                    return Ok(None);
                 }
                 let mut params: Vec<String> = vec![];
                 let mut qubits: Vec<String> = vec![];
@@ -378,10 +786,11 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                                 .map(|qbt_pair| qbt_pair.as_str().to_owned())
                                 .collect();
                         }
-                        Rule::gates_definition => {
+                        Rule::gates_definition | Rule::gates_definition3 => {
                             for gate_pair in inner_pairs.next().unwrap().into_inner() {
+                                let span = gate_pair.as_span();
                                 let mut inner_gate_pairs = gate_pair.into_inner();
-                                let id = inner_gate_pairs.next().unwrap().as_str();
+                                let (modifier, id) = modifier_and_gate_name(&mut inner_gate_pairs);
                                 let mut gate_params: Vec<String> = vec![];
                                 let mut gate_qubits: Vec<usize> = vec![];
                                 for gate_token in inner_gate_pairs.clone() {
@@ -409,12 +818,29 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                                         _ => continue,
                                     }
                                 }
-                                if let Some(gate) = gate_dispatch(
-                                    id,
-                                    &gate_params,
-                                    &gate_qubits,
-                                    defined_custom_gates,
-                                ) {
+                                let gate = match modifier {
+                                    Some(modifier) => Some(
+                                        modified_gate_dispatch(
+                                            modifier,
+                                            id,
+                                            &gate_params,
+                                            &gate_qubits,
+                                            defined_custom_gates,
+                                            options,
+                                        )
+                                        .ok_or_else(
+                                            || unsupported_modifier_error(modifier, id, span),
+                                        )?,
+                                    ),
+                                    None => gate_dispatch(
+                                        id,
+                                        &gate_params,
+                                        &gate_qubits,
+                                        defined_custom_gates,
+                                        options,
+                                    ),
+                                };
+                                if let Some(gate) = gate {
                                     definition_circuit.add_operation(gate);
                                 }
                             }
@@ -431,16 +857,72 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
                 )))
             }
             _ => None,
+        };
+        Ok(op)
+    }
+
+    /// Parses the statements of a block into a circuit.
+    fn parse_block<'i>(
+        statements: impl Iterator<Item = Pair<'i, Rule>>,
+        defined_custom_gates: &mut Vec<(String, usize, usize)>,
+        qubit_registers: &mut HashMap<String, usize>,
+        options: &ParserOptions,
+    ) -> Result<Circuit, Box<Error<Rule>>> {
+        let mut circuit = Circuit::new();
+        for statement in statements {
+            if let Some(op) =
+                parse_single_rule(statement, defined_custom_gates, qubit_registers, options)?
+            {
+                circuit.add_operation(op);
+            }
         }
+        Ok(circuit)
     }
 
+    let mut docstrings: GateDocstrings = HashMap::new();
+    let mut comment_lines: Vec<String> = vec![];
+    let mut last_comment_line: usize = 0;
     for pair in pairs {
-        if let Some(op) = parse_single_rule(pair, &mut defined_custom_gates) {
+        if options.capture_gate_docstrings {
+            let line = pair.line_col().0;
+            match pair.as_rule() {
+                Rule::comment => {
+                    // Only an uninterrupted block of comment lines forms a docstring
+                    if line != last_comment_line + 1 {
+                        comment_lines.clear();
+                    }
+                    let text = pair.as_str().trim_start_matches("//");
+                    comment_lines
+                        .push(text.strip_prefix(' ').unwrap_or(text).trim_end().to_owned());
+                    last_comment_line = line;
+                }
+                Rule::gate_def | Rule::gate_def3 => {
+                    if !comment_lines.is_empty() && line == last_comment_line + 1 {
+                        let id = pair.clone().into_inner().next().unwrap().as_str();
+                        docstrings.insert(id.to_owned(), comment_lines.join("\n"));
+                    }
+                    comment_lines.clear();
+                }
+                _ => comment_lines.clear(),
+            }
+        }
+        if let Some(op) = parse_single_rule(
+            pair,
+            &mut defined_custom_gates,
+            &mut qubit_registers,
+            options,
+        )? {
             circuit.add_operation(op);
         }
     }
+    // Docstrings of ignored definitions (built-in gates) are dropped
+    docstrings.retain(|name, _| {
+        defined_custom_gates
+            .iter()
+            .any(|(gate_name, _, _)| gate_name == name)
+    });
 
-    Ok(circuit)
+    Ok((circuit, docstrings))
 }
 
 /// Translates a QASM file into a qoqo Circuit instance.
@@ -454,12 +936,50 @@ fn parse_qasm_file(file: &str) -> Result<Circuit, Box<Error<Rule>>> {
 /// * `Circuit` - The translated qoqo Circuit.
 /// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
 pub fn file_to_circuit(file: File) -> Result<Circuit, RoqoqoBackendError> {
+    file_to_circuit_with_options(file, &ParserOptions::default())
+}
+
+/// Translates a QASM file into a qoqo Circuit instance using the given options.
+///
+/// # Arguments
+///
+/// * `file` - The '.qasm' file to translate.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `Circuit` - The translated qoqo Circuit.
+/// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
+pub fn file_to_circuit_with_options(
+    file: File,
+    options: &ParserOptions,
+) -> Result<Circuit, RoqoqoBackendError> {
+    file_to_circuit_with_docstrings(file, options).map(|(circuit, _)| circuit)
+}
+
+/// Translates a QASM file into a qoqo Circuit instance and the docstrings of the defined gates.
+///
+/// The docstrings are only collected when `capture_gate_docstrings` is set in the options.
+///
+/// # Arguments
+///
+/// * `file` - The '.qasm' file to translate.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `(Circuit, GateDocstrings)` - The translated qoqo Circuit and the gate docstrings keyed by gate name.
+/// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
+pub fn file_to_circuit_with_docstrings(
+    file: File,
+    options: &ParserOptions,
+) -> Result<(Circuit, GateDocstrings), RoqoqoBackendError> {
     let unparsed_file = BufReader::new(file)
         .lines()
         .map(|line| line.unwrap() + "\n")
         .collect::<String>();
 
-    parse_qasm_file(&unparsed_file).map_err(|x| RoqoqoBackendError::GenericError {
+    parse_qasm_file(&unparsed_file, options).map_err(|x| RoqoqoBackendError::GenericError {
         msg: format!("Error during conversion: {}", x),
     })
 }
@@ -475,8 +995,46 @@ pub fn file_to_circuit(file: File) -> Result<Circuit, RoqoqoBackendError> {
 /// * `Circuit` - The translated qoqo Circuit.
 /// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
 pub fn string_to_circuit(input: &str) -> Result<Circuit, RoqoqoBackendError> {
+    string_to_circuit_with_options(input, &ParserOptions::default())
+}
+
+/// Translates a QASM string into a qoqo Circuit instance using the given options.
+///
+/// # Arguments
+///
+/// * `input` - The QASM string to translate.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `Circuit` - The translated qoqo Circuit.
+/// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
+pub fn string_to_circuit_with_options(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<Circuit, RoqoqoBackendError> {
+    string_to_circuit_with_docstrings(input, options).map(|(circuit, _)| circuit)
+}
+
+/// Translates a QASM string into a qoqo Circuit instance and the docstrings of the defined gates.
+///
+/// The docstrings are only collected when `capture_gate_docstrings` is set in the options.
+///
+/// # Arguments
+///
+/// * `input` - The QASM string to translate.
+/// * `options` - The options changing the translation.
+///
+/// # Returns
+///
+/// * `(Circuit, GateDocstrings)` - The translated qoqo Circuit and the gate docstrings keyed by gate name.
+/// * `RoqoqoBackendError::GenericError` - Error encountered while parsing.
+pub fn string_to_circuit_with_docstrings(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<(Circuit, GateDocstrings), RoqoqoBackendError> {
     let with_newline = input.to_owned() + "\n";
-    parse_qasm_file(&with_newline).map_err(|x| RoqoqoBackendError::GenericError {
+    parse_qasm_file(&with_newline, options).map_err(|x| RoqoqoBackendError::GenericError {
         msg: format!("Error during conversion: {}", x),
     })
 }
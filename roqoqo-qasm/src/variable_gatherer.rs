@@ -1,5 +1,5 @@
 use qoqo_calculator::CalculatorError;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 static ATOL: f64 = f64::EPSILON;
 
@@ -173,11 +173,99 @@ fn function_2_arguments(input: &str, arg0: f64, arg1: f64) -> Result<f64, Calcul
     }
 }
 
+/// Match name of function to number of arguments, including functions not supported in OpenQASM 3.0.
+/// Returns result with CalculatorError when function name is not known.
+fn all_function_argument_numbers(input: &str) -> Result<usize, CalculatorError> {
+    match input {
+        "atan2" | "hypot" | "pow" | "max" | "min" => Ok(2),
+        "sin" | "cos" | "abs" | "tan" | "acos" | "asin" | "atan" | "cosh" | "sinh" | "tanh"
+        | "acosh" | "asinh" | "atanh" | "arcosh" | "arsinh" | "artanh" | "exp" | "exp2"
+        | "expm1" | "log" | "log10" | "sqrt" | "cbrt" | "ceil" | "floor" | "fract" | "round"
+        | "sign" | "delta" | "theta" => Ok(1),
+        _ => Err(CalculatorError::FunctionNotFound {
+            fct: input.to_string(),
+        }),
+    }
+}
+
+/// Returns the rewriting of a function not supported in OpenQASM 3.0 into supported functions.
+///
+/// The returned prefix and suffix enclose the argument of the function.
+fn rewritten_function(input: &str) -> Option<(&'static str, &'static str)> {
+    match input {
+        "log10" => Some(("(log(", ")/log(10))")),
+        "exp2" => Some(("exp((", ")*log(2))")),
+        "cbrt" => Some(("pow(", ",1.0/3.0)")),
+        _ => None,
+    }
+}
+
+/// Rewrites the calls of `log10`, `exp2` and `cbrt` in an expression into supported functions.
+fn rewrite_functions(expression: &str) -> String {
+    let mut rewritten = String::with_capacity(expression.len());
+    let mut rest = expression;
+    while let Some((start, name, prefix, suffix)) = ["log10", "exp2", "cbrt"]
+        .iter()
+        .filter_map(|name| {
+            let (prefix, suffix) = rewritten_function(name)?;
+            rest.match_indices(&format!("{}(", name))
+                .find(|(start, _)| {
+                    !rest[..*start]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                })
+                .map(|(start, _)| (start, *name, prefix, suffix))
+        })
+        .min_by_key(|(start, ..)| *start)
+    {
+        let argument_start = start + name.len() + 1;
+        let mut depth = 1;
+        let argument_end = rest[argument_start..]
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => (),
+                }
+                depth == 0
+            })
+            .map_or(rest.len(), |(end, _)| argument_start + end);
+        rewritten.push_str(&rest[..start]);
+        rewritten.push_str(prefix);
+        rewritten.push_str(&rewrite_functions(&rest[argument_start..argument_end]));
+        rewritten.push_str(suffix);
+        rest = rest.get(argument_end + 1..).unwrap_or("");
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// How a variable is used in the Circuit, which decides the type of its input declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    /// The variable is only used in rotation angles and is declared as an `angle`.
+    Angle,
+    /// The variable is used as a general parameter, for example an interaction strength, and is declared as a `float`.
+    Float,
+}
+
 /// Struct to keep track of variables present in input Circuit.
 #[derive(Debug, Clone)]
 pub struct VariableGatherer {
     ///  HashSet of variables in current Circuit
     pub variables: HashSet<String>,
+    /// Whether functions not supported in OpenQASM 3.0 are accepted
+    pub allow_all_functions: bool,
+    /// Whether `log10`, `exp2` and `cbrt` are rejected instead of rewritten into supported functions
+    pub strict: bool,
+    /// How each variable in the current Circuit is used
+    pub variable_kinds: HashMap<String, VariableKind>,
+    /// The expressions rewritten by [VariableGatherer::parse_for_output] and their rewriting
+    pub rewritten_expressions: HashMap<String, String>,
+    /// The kind the variables of the expression that is parsed are registered with
+    current_kind: VariableKind,
 }
 
 impl Default for VariableGatherer {
@@ -191,6 +279,37 @@ impl VariableGatherer {
     pub fn new() -> Self {
         VariableGatherer {
             variables: HashSet::new(),
+            allow_all_functions: false,
+            strict: true,
+            variable_kinds: HashMap::new(),
+            rewritten_expressions: HashMap::new(),
+            current_kind: VariableKind::Angle,
+        }
+    }
+
+    /// Create a new CircuitParser instance accepting all functions.
+    ///
+    /// Functions such as `cosh` are not supported in OpenQASM 3.0 and rejected by [VariableGatherer::new],
+    /// this mode is meant for the Roqoqo dialect which does not need to be standard OpenQASM 3.0.
+    pub fn new_permissive() -> Self {
+        VariableGatherer {
+            variables: HashSet::new(),
+            allow_all_functions: true,
+            strict: true,
+            variable_kinds: HashMap::new(),
+            rewritten_expressions: HashMap::new(),
+            current_kind: VariableKind::Angle,
+        }
+    }
+
+    /// Create a new CircuitParser instance rewriting functions not supported in OpenQASM 3.0.
+    ///
+    /// `log10`, `exp2` and `cbrt` are accepted and [VariableGatherer::parse_for_output] rewrites
+    /// them into `log`, `exp` and `pow`, the other unsupported functions are still rejected.
+    pub fn new_rewriting() -> Self {
+        VariableGatherer {
+            strict: false,
+            ..Self::new()
         }
     }
 
@@ -202,6 +321,93 @@ impl VariableGatherer {
     ///
     pub fn register_variable(&mut self, name: &str) {
         self.variables.insert(name.to_string());
+        self.register_variable_kind(name, self.current_kind);
+    }
+
+    /// Register how a variable is used.
+    ///
+    /// A variable used both as an angle and as a general parameter is classified as a float.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the variable
+    /// * `kind` - How the variable is used
+    ///
+    pub fn register_variable_kind(&mut self, name: &str, kind: VariableKind) {
+        let entry = self.variable_kinds.entry(name.to_string()).or_insert(kind);
+        if kind == VariableKind::Float {
+            *entry = VariableKind::Float;
+        }
+    }
+
+    /// Returns how a variable is used, if it was registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the variable
+    ///
+    pub fn variable_kind(&self, name: &str) -> Option<VariableKind> {
+        self.variable_kinds.get(name).copied()
+    }
+
+    ///  Parse a string expression, registering its variables with the given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - Expression that is parsed
+    /// * `kind` - How the variables of the expression are used
+    ///
+    pub fn parse_with_kind(
+        &mut self,
+        expression: &str,
+        kind: VariableKind,
+    ) -> Result<(), CalculatorError> {
+        self.current_kind = kind;
+        let result = self.parse_for_output(expression);
+        self.current_kind = VariableKind::Angle;
+        result.map(|_| ())
+    }
+
+    ///  Parse a string expression and return the expression to emit in the QASM output.
+    ///
+    /// Unless the VariableGatherer is strict, the calls of `log10`, `exp2` and `cbrt` are rewritten
+    /// into `log(x)/log(10)`, `exp(x*log(2))` and `pow(x,1.0/3.0)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - Expression that is parsed
+    ///
+    pub fn parse_for_output(&mut self, expression: &str) -> Result<String, CalculatorError> {
+        self.parse(expression)?;
+        if self.strict {
+            return Ok(expression.to_string());
+        }
+        let rewritten = rewrite_functions(expression);
+        if rewritten != expression {
+            self.rewritten_expressions
+                .insert(expression.to_string(), rewritten.clone());
+        }
+        Ok(rewritten)
+    }
+
+    /// Replaces the expressions rewritten by [VariableGatherer::parse_for_output] in a QASM instruction.
+    ///
+    /// Longer expressions are replaced first, so that expressions containing other rewritten
+    /// expressions are replaced as a whole.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The QASM instruction using the original expressions
+    ///
+    pub fn rewrite_instruction(&self, instruction: &str) -> String {
+        let mut expressions: Vec<(&String, &String)> = self.rewritten_expressions.iter().collect();
+        expressions.sort_by_key(|(expression, _)| std::cmp::Reverse(expression.len()));
+        expressions.into_iter().fold(
+            instruction.to_string(),
+            |instruction, (expression, rewritten)| {
+                instruction.replace(expression.as_str(), rewritten)
+            },
+        )
     }
 
     ///  Parse a string expression allowing variable assignments.
@@ -645,14 +851,28 @@ where
             Token::Variable(ref vs) => {
                 let vsnew = vs.to_owned();
                 self.next_token();
-                self.register_variable(&vsnew);
-                Ok(0.0)
+                // The OpenQASM 3.0 built-in constants are not input parameters
+                match vsnew.as_str() {
+                    "pi" => Ok(std::f64::consts::PI),
+                    "tau" => Ok(std::f64::consts::TAU),
+                    "euler" => Ok(std::f64::consts::E),
+                    _ => {
+                        self.register_variable(&vsnew);
+                        Ok(0.0)
+                    }
+                }
             }
             Token::Function(ref vs) => {
                 let vsnew = vs.to_owned();
                 self.next_token();
                 let mut heap = Vec::new();
-                let number_arguments = function_argument_numbers(&vsnew)?;
+                let number_arguments = if self.circuit_parser.allow_all_functions {
+                    all_function_argument_numbers(&vsnew)?
+                } else if !self.circuit_parser.strict && rewritten_function(&vsnew).is_some() {
+                    1
+                } else {
+                    function_argument_numbers(&vsnew)?
+                };
                 for argument_number in 0..number_arguments {
                     heap.push(
                         self.evaluate_init()?
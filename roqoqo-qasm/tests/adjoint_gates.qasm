@@ -0,0 +1,7 @@
+OPENQASM 3.0;
+include "stdgates.inc";
+
+qubit[2] q;
+
+sdg q[0];
+tdg q[1];
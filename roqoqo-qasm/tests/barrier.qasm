@@ -0,0 +1,12 @@
+OPENQASM 2.0;
+include "qelib1.inc";
+
+qreg q[3];
+creg c[2];
+
+h q[0];
+barrier q[0],q[1];
+cx q[0],q[1];
+barrier q;
+
+measure q[0] -> c[0];
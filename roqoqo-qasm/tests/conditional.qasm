@@ -0,0 +1,9 @@
+OPENQASM 2.0;
+include "qelib1.inc";
+
+qreg q[2];
+creg c[2];
+
+measure q[0] -> c[0];
+if(c[0]==1) x q[1];
+if (c[1] == 1) rx(0.5) q[0];
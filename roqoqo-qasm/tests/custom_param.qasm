@@ -0,0 +1,17 @@
+OPENQASM 2.0;
+
+gate mygate(theta) a
+{
+    rx(theta) a;
+}
+
+gate outer(phi) a,b
+{
+    mygate(phi/2) a;
+    mygate(2*pi/4) b;
+}
+
+qreg q[2];
+
+mygate(pi/2) q[0];
+outer(pi) q[0],q[1];
@@ -14,6 +14,8 @@ t q[1];
 p(0.6) q[2];
 sx q[1];
 sxdg q[0];
+sy q[2];
+sydg q[1];
 cx q[0],q[1];
 rxx(3.14159265359) q[1],q[2];
 rxx(0.7) q[0],q[2];
@@ -39,6 +41,7 @@ ecr q[0],q[2];
 pscz(0.3) q[0],q[2];
 pscp(1,1.9) q[0],q[1];
 ccx q[0],q[2],q[1];
+ccnot q[1],q[2],q[0];
 ccz q[2],q[1],q[0];
 ccp(0.3) q[1],q[0],q[2];
 reset q[1];
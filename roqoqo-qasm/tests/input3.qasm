@@ -0,0 +1,11 @@
+OPENQASM 3.0;
+output bit[2] c;
+qubit[3] q;
+
+x q[0];
+h q[1];
+rx(2.3) q[2];
+cx q[0],q[1];
+
+measure q[0] -> c[0];
+c[1] = measure q[1];
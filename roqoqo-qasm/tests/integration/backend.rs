@@ -12,14 +12,16 @@
 //
 //! Testing the roqoqo-qasm Backend
 
+use std::collections::{HashMap, HashSet};
 use std::env::temp_dir;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 use qoqo_calculator::CalculatorFloat;
 use roqoqo::prelude::*;
 use roqoqo::{operations::*, Circuit};
-use roqoqo_qasm::Backend;
+use roqoqo_qasm::{gate_definition, Backend, Qasm3Dialect, QasmVersion};
 
 use test_case::test_case;
 
@@ -89,6 +91,107 @@ fn test_simple_circuit_iterator_to_file(qasm_version: &str, qubits: &str, bits:
     assert_eq!(lines, extracted.unwrap());
 }
 
+/// Test that the QASM files start with a UTF-8 byte order mark when it is enabled
+#[test_case(false; "without bom")]
+#[test_case(true; "with bom")]
+fn test_utf8_bom(utf8_bom: bool) {
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_utf8_bom(utf8_bom);
+    let circuit = Circuit::new() + PauliX::new(0);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.starts_with("OPENQASM"));
+    let expected = match utf8_bom {
+        true => [b"\xEF\xBB\xBF".as_slice(), qasm.as_bytes()].concat(),
+        false => qasm.as_bytes().to_vec(),
+    };
+
+    let file_name = format!("test_utf8_bom_{}", utf8_bom);
+    let read_in_path = temp_dir().join(format!("{}.qasm", file_name));
+    backend
+        .circuit_to_qasm_file(&circuit, temp_dir().as_path(), Path::new(&file_name), true)
+        .unwrap();
+    assert_eq!(fs::read(&read_in_path).unwrap(), expected);
+    backend
+        .circuit_iterator_to_qasm_file(
+            circuit.iter(),
+            temp_dir().as_path(),
+            Path::new(&file_name),
+            true,
+        )
+        .unwrap();
+    assert_eq!(fs::read(&read_in_path).unwrap(), expected);
+    fs::remove_file(&read_in_path).unwrap();
+}
+
+/// Test that the qubit register name must be a valid OpenQASM identifier
+#[test_case("q", true; "single letter")]
+#[test_case("Qr_2", true; "letters digits underscores")]
+#[test_case("2q", false; "leading digit")]
+#[test_case("my reg", false; "space")]
+#[test_case("_q", false; "leading underscore")]
+#[test_case("q-1", false; "dash")]
+#[test_case("", false; "empty")]
+fn test_qubit_register_name_validation(qubit_register_name: &str, valid: bool) {
+    let backend = Backend::new(Some(qubit_register_name.to_string()), None);
+    match valid {
+        true => assert!(backend.is_ok()),
+        false => assert_eq!(
+            backend,
+            Err(RoqoqoBackendError::GenericError {
+                msg: format!("The qubit register name \"{}\" is not a valid OpenQASM identifier, it must start with a letter followed by letters, digits or underscores", qubit_register_name)
+            })
+        ),
+    }
+}
+
+/// Test that the classical register names must be valid OpenQASM identifiers
+#[test]
+fn test_classical_register_name_validation() {
+    let backend = Backend::new(None, None).unwrap();
+    let circuit = Circuit::new() + DefinitionBit::new("ro 1".to_string(), 1, true);
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "The classical register name \"ro 1\" is not a valid OpenQASM identifier, it must start with a letter followed by letters, digits or underscores".to_string()
+        })
+    );
+}
+
+/// Test the gate statistics of a circuit
+#[test_case("2.0", vec![("h", 5), ("cx", 2), ("cz", 1), ("x", 1), ("measure", 2), ("reset", 1)]; "2.0")]
+#[test_case("3.0", vec![("h", 4), ("cx", 2), ("z", 1), ("x", 1), ("measure", 2), ("reset", 1)]; "3.0")]
+#[test_case("3.0Roqoqo", vec![("h", 3), ("cx", 2), ("cz", 1), ("x", 1), ("measure", 2), ("reset", 1)]; "3.0Roqoqo")]
+fn test_gate_statistics(qasm_version: &str, expected: Vec<(&str, usize)>) {
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_ctrl_modifier(true);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += Hadamard::new(1);
+    circuit += CNOT::new(1, 0);
+    circuit += Hadamard::new(0);
+    circuit += ControlledPauliZ::new(0, 1);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += PragmaActiveReset::new(0);
+    circuit += PragmaConditional::new("ro".to_string(), 0, Circuit::new() + PauliX::new(1));
+    circuit += PragmaLoop::new(2.0.into(), Circuit::new() + Hadamard::new(1));
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 10, None);
+
+    let statistics = backend.gate_statistics(&circuit).unwrap();
+    let expected: HashMap<String, usize> = expected
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    assert_eq!(statistics, expected);
+
+    let circuit = Circuit::new() + PragmaChangeDevice::new(&PragmaActiveReset::new(0)).unwrap();
+    assert!(backend.gate_statistics(&circuit).is_err());
+}
+
 /// Test duplicate gates definitions
 #[test_case("2.0", "qreg qr[2]", "creg ro[2]"; "2.0")]
 #[test_case("3.0", "qubit[2] qr", "bit[2] ro"; "3.0")]
@@ -137,6 +240,23 @@ fn test_run_error() {
     );
 }
 
+/// Test that backend returns error instead of panicking when the file can not be created
+#[test]
+fn test_file_creation_error() {
+    let backend = Backend::new(None, None).unwrap();
+    let circuit = Circuit::new() + PauliX::new(0);
+    let missing_folder = temp_dir().join("missing_qasm_folder");
+    assert!(matches!(
+        backend.circuit_to_qasm_file(
+            &circuit,
+            missing_folder.as_path(),
+            Path::new("test_simple"),
+            true,
+        ),
+        Err(RoqoqoBackendError::GenericError { .. })
+    ));
+}
+
 /// Test correct order of qasm elements in output str
 #[test]
 fn test_str_order() {
@@ -162,7 +282,7 @@ fn test_debug_clone_partialeq() {
     // Test Debug trait
     assert_eq!(
         format!("{backend:?}"),
-        "Backend { qubit_register_name: \"qtest\", qasm_version: V2point0(Vanilla) }"
+        "Backend { qubit_register_name: \"qtest\", qasm_version: V2point0(Vanilla), options: QasmOptions { builtin_cnot: false, json_pragmas: false, max_recursion_depth: 256, stdgates_include: false, qelib_include: false, ctrl_modifier: false, cu1_controlled_phase: false, conditional_value: One, inline_measurement_basis: false, indent_width: 4, drop_unsupported_global_phase: false }, fixed_qubit_count: None, verbose: false, end_marker: false, include_header_comment: false, validation: false, register_reset: false, angle_widths: {}, register_mapping: None, strict: false, section_blank_lines: None, omit_unused_qubit_register: false, box_decomposition_blocks: false, definitions_inline: false, utf8_bom: false, rewrite_functions: false }"
     );
 
     // Test Clone trait
@@ -191,6 +311,177 @@ fn test_parametric_gates() {
     assert!(str.contains("1/cos(alpha)"));
 }
 
+/// Test that variables used as general parameters are declared as floats
+#[test]
+fn test_float_input_declarations() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, "alpha".into());
+    circuit += PMInteraction::new(0, 1, "beta".into());
+    circuit += RotateX::new(1, "beta".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.contains("input angle[32] alpha;"));
+    assert!(str.contains("input float[64] beta;"));
+    assert!(!str.contains("input angle[32] beta;"));
+}
+
+/// Test that a symbolic global phase is declared as an input
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Roqoqo"; "3.0Roqoqo")]
+fn test_symbolic_global_phase(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += PragmaGlobalPhase::new("alpha".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.contains("input angle[32] alpha;"));
+    assert!(str.contains("gphase alpha;"));
+}
+
+/// Test that the Braket dialect emits or drops the global phase depending on the option
+#[test_case(false, "gphase(alpha);\n"; "emitted")]
+#[test_case(true, ""; "dropped")]
+fn test_drop_unsupported_global_phase(drop_unsupported_global_phase: bool, data: &str) {
+    let backend = Backend::new(None, Some("3.0Braket".to_string()))
+        .unwrap()
+        .with_drop_unsupported_global_phase(drop_unsupported_global_phase);
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += PragmaGlobalPhase::new("alpha".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.ends_with(&format!("x q[0];\n{}", data)));
+    assert_eq!(
+        str.contains("input angle[32] alpha;"),
+        !drop_unsupported_global_phase
+    );
+}
+
+/// Test that resets of the whole qubit register are collapsed in OpenQASM 3.0
+#[test_case("3.0", "reset q;\nh q[1];\nreset q[0];\nreset q[1];\nreset q[1];\n"; "3.0")]
+#[test_case("2.0", "reset q[1];\nreset q[0];\nh q[1];\nreset q[0];\nreset q[1];\nreset q[1];\n"; "2.0")]
+fn test_register_reset(qasm_version: &str, data: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_register_reset(true);
+    let mut circuit = Circuit::new();
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaActiveReset::new(0);
+    circuit += Hadamard::new(1);
+    circuit += PragmaActiveReset::new(0);
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaActiveReset::new(1);
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(str.ends_with(data));
+
+    let mut written: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut written)
+        .unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), str);
+
+    let default_backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    assert!(!default_backend
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("reset q;"));
+}
+
+/// Test the per-parameter widths of the input angle declarations
+#[test]
+fn test_angle_widths() {
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_angle_widths(HashMap::from([
+            ("alpha".to_string(), 64),
+            ("unused".to_string(), 16),
+        ]));
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, "alpha".into());
+    circuit += RotateX::new(0, "beta".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.contains("input angle[64] alpha;"));
+    assert!(str.contains("input angle[32] beta;"));
+    assert!(!str.contains("unused"));
+}
+
+/// Test that the Roqoqo dialect gathers the parameters of functions not supported in OpenQASM 3.0
+#[test]
+fn test_parametric_gates_permissive() {
+    let backend = Backend::new(None, Some("3.0Roqoqo".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, "cosh(alpha)".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.contains("input angle[32] alpha;"));
+    assert!(str.contains("rz(cosh(alpha)) q[0];"));
+}
+
+/// Test that the export to vanilla OpenQASM 3.0 fails for functions it does not support
+#[test]
+fn test_parametric_gates_unsupported_function() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, "cosh(alpha)".into());
+
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Symbolic expression cosh(alpha) can not be translated to OpenQASM 3.0: Parsing error: \"Function cosh is not supported in OpenQASM 3.0.\"".to_string()
+        })
+    );
+}
+
+/// Test that log10, exp2 and cbrt are rewritten into functions supported in OpenQASM 3.0
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Braket"; "3.0Braket")]
+fn test_parametric_gates_rewrite_functions(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, "log10(alpha)".into());
+    circuit += RotateY::new(0, "2*exp2(beta)".into());
+    circuit += RotateZ::new(0, "cbrt(gamma)".into());
+    assert!(backend.circuit_to_qasm_str(&circuit).is_err());
+
+    let backend = backend.with_rewrite_functions(true);
+    let data = "rx((log(alpha)/log(10))) q[0];\nry(2*exp((beta)*log(2))) q[0];\nrz(pow(gamma,1.0/3.0)) q[0];\n";
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(str.contains("input angle[32] alpha;"));
+    assert!(str.ends_with(data));
+    assert!(!str.contains("log10") && !str.contains("exp2") && !str.contains("cbrt"));
+
+    // The streamed output rewrites the functions as well
+    let mut output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut output)
+        .unwrap();
+    assert!(String::from_utf8(output).unwrap().ends_with(data));
+}
+
+/// Test symbolic ControlledControlledPhaseShift handling in the Braket dialect
+#[test]
+fn test_symbolic_ccp_braket() {
+    let backend = Backend::new(None, Some("3.0Braket".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += ControlledControlledPhaseShift::new(0, 1, 2, "theta".into());
+
+    let str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(str.contains("input angle[32] theta;"));
+    assert!(!str.contains("ccp"));
+    assert!(str.contains("phaseshift((theta / 4e0)) q[1];\ncnot q[1],q[2];"));
+    assert!(str.contains("phaseshift((theta / -4e0)) q[2];"));
+}
+
 #[test]
 fn test_parsing_methods() {
     use std::fs::File;
@@ -279,3 +570,1425 @@ fn test_qulacs_version() {
     assert!(qasm_str.contains("include \"qelib1.inc\";"));
     assert!(!qasm_str.contains("gate"));
 }
+
+/// Test the specific error for measurement readout operations in the Qulacs version
+#[test]
+fn test_qulacs_measurement_operation_error() {
+    let backend = Backend::new(None, Some("2.0Qulacs".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += PragmaGetStateVector::new("ro".to_string(), None);
+
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "PragmaGetStateVector is a measurement operation that must be handled at the QuantumProgram level, it can not be translated to QASM".to_string()
+        })
+    );
+}
+
+/// Test that CNOT uses the built-in CX when requested for OpenQASM 2.0
+#[test]
+fn test_builtin_cnot() {
+    let backend = Backend::new(None, Some("2.0".to_string()))
+        .unwrap()
+        .with_builtin_cnot(true);
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 1);
+    circuit += ControlledPauliZ::new(1, 0);
+
+    let qasm_str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(!qasm_str.contains("gate cx"));
+    assert!(qasm_str.contains("gate cz a,b { u2(0,pi) b; CX a,b; u2(0,pi) b; }"));
+    assert!(qasm_str.contains("\nCX q[0],q[1];\ncz q[1],q[0];\n"));
+
+    // The decompositions and the bodies of custom gates call the built-in CX as well
+    let mut custom_circuit = Circuit::new();
+    custom_circuit += GateDefinition::new(
+        Circuit::new() + CNOT::new(0, 1) + SWAP::new(0, 1),
+        "custom".to_owned(),
+        vec![0, 1],
+        vec![],
+    );
+    custom_circuit += CallDefinedGate::new("custom".to_owned(), vec![0, 1], vec![]);
+    let qasm_str = backend.circuit_to_qasm_str(&custom_circuit).unwrap();
+    assert!(qasm_str.contains("gate swap a,b { CX a,b; CX b,a; CX a,b; }"));
+    assert!(qasm_str.contains("CX qb_0,qb_1;"));
+    assert!(!qasm_str.contains("cx "));
+
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_builtin_cnot(true);
+    let qasm_str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(qasm_str.contains("gate cx c,t { ctrl @ x c,t; }"));
+    assert!(qasm_str.contains("\ncx q[0],q[1];\n"));
+}
+
+/// Test that the CNOT ladder of MultiQubitZZ uses the built-in CX when requested for OpenQASM 2.0
+#[test]
+fn test_builtin_cnot_multi_qubit_zz() {
+    let backend = Backend::new(None, Some("2.0".to_string()))
+        .unwrap()
+        .with_builtin_cnot(true);
+    let circuit = Circuit::new() + MultiQubitZZ::new(vec![0, 1, 2], 0.5.into());
+
+    let qasm_str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(!qasm_str.contains("cx"));
+    assert!(qasm_str.contains("gate rzz(theta) a,b { CX a,b; rz(theta) b; CX a,b; }"));
+    assert!(qasm_str.ends_with("CX q[0],q[1];\nrzz(5e-1) q[1],q[2];\nCX q[0],q[1];\n"));
+}
+
+/// Test that the CNOT ladder of MultiQubitMS uses the built-in CX when requested for OpenQASM 2.0
+#[test]
+fn test_builtin_cnot_multi_qubit_ms() {
+    let backend = Backend::new(None, Some("2.0".to_string()))
+        .unwrap()
+        .with_builtin_cnot(true);
+    let circuit = Circuit::new() + MultiQubitMS::new(vec![0, 1], 0.5.into());
+
+    let qasm_str = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(!qasm_str.contains("cx"));
+    assert!(qasm_str.ends_with(
+        "u2(0,pi) q[0];\nu2(0,pi) q[1];\nCX q[0],q[1];\nrz(5e-1) q[1];\nCX q[0],q[1];\nu2(0,pi) q[0];\nu2(0,pi) q[1];\n"
+    ));
+}
+
+/// Test that a classical register named like the qubit register is rejected
+#[test_case("2.0", Operation::from(DefinitionBit::new("q".to_string(), 2, true)), true; "bit_2.0")]
+#[test_case("3.0", Operation::from(DefinitionBit::new("q".to_string(), 2, true)), true; "bit_3.0")]
+#[test_case("3.0", Operation::from(DefinitionFloat::new("q".to_string(), 2, true)), true; "float_3.0")]
+#[test_case("2.0", Operation::from(DefinitionComplex::new("q".to_string(), 1, true)), true; "complex_2.0")]
+#[test_case("3.0", Operation::from(DefinitionComplex::new("q".to_string(), 1, true)), false; "complex_3.0")]
+#[test_case("3.0", Operation::from(DefinitionBit::new("ro".to_string(), 2, true)), false; "no_collision")]
+fn test_register_name_collision(qasm_version: &str, definition: Operation, collides: bool) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += definition.clone();
+    circuit += PauliX::new(0);
+
+    let result = backend.circuit_to_qasm_str(&circuit);
+    if collides {
+        assert_eq!(
+            result,
+            Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The classical register defined by {} uses the same name as the qubit register: q",
+                    definition.hqslang()
+                )
+            })
+        );
+    } else {
+        assert!(result.is_ok());
+    }
+}
+
+/// Test that streaming to a writer produces the same output as the string translation
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Roqoqo"; "3.0Roqoqo")]
+#[test_case("3.0Braket"; "3.0Braket")]
+fn test_circuit_to_qasm_writer(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_register_reset(true);
+    let mut circuit = Circuit::new();
+    circuit += PragmaSetNumberOfMeasurements::new(20, "ro".to_string());
+    circuit += DefinitionBit::new("ro".to_string(), 2, false);
+    circuit += RotateZ::new(0, "alpha".into());
+    circuit += Hadamard::new(1);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+    circuit += CNOT::new(0, 1);
+    // Two rounds of resets separated by decomposition blocks that print nothing in most dialects
+    circuit += PragmaActiveReset::new(0);
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaStartDecompositionBlock::new(vec![0, 1], HashMap::new());
+    circuit += PragmaActiveReset::new(0);
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaStopDecompositionBlock::new(vec![0, 1]);
+    circuit += PragmaActiveReset::new(1);
+    circuit += PragmaActiveReset::new(0);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 20, None);
+
+    let mut output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        backend.circuit_to_qasm_str(&circuit).unwrap()
+    );
+}
+
+/// Test that streaming an iterator to a writer produces the same output as the string translation
+#[test]
+fn test_circuit_iterator_to_writer() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let operations: Vec<Operation> = vec![
+        DefinitionBit::new("ro".to_string(), 2, true).into(),
+        RotateX::new(0, "theta".into()).into(),
+        Hadamard::new(1).into(),
+        CNOT::new(0, 1).into(),
+        MeasureQubit::new(0, "ro".to_string(), 0).into(),
+        MeasureQubit::new(1, "ro".to_string(), 1).into(),
+    ];
+
+    let mut output: Vec<u8> = Vec::new();
+    backend
+        .circuit_iterator_to_writer(operations.iter(), &mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        backend
+            .circuit_iterator_to_qasm_str(operations.iter())
+            .unwrap()
+    );
+}
+
+/// Test the validation of a fixed qubit register size
+#[test_case(3, true; "large enough")]
+#[test_case(5, true; "larger")]
+#[test_case(2, false; "too small")]
+fn test_fixed_qubit_count(fixed_qubit_count: usize, valid: bool) {
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_fixed_qubit_count(fixed_qubit_count);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += CNOT::new(0, 2);
+    circuit += MeasureQubit::new(2, "ro".to_string(), 2);
+
+    let result = backend.circuit_to_qasm_str(&circuit);
+    if valid {
+        assert!(result
+            .unwrap()
+            .contains(&format!("qubit[{fixed_qubit_count}] q;")));
+    } else {
+        assert_eq!(
+            result,
+            Err(RoqoqoBackendError::GenericError {
+                msg: "The fixed qubit register of size 2 is too small for the qubit index 2 used in the circuit".to_string()
+            })
+        );
+    }
+}
+
+/// Test the number of gate definitions in the QASM output
+#[test_case("2.0", 11; "2.0")]
+#[test_case("3.0", 11; "3.0")]
+#[test_case("3.0Braket", 0; "3.0Braket")]
+fn test_definition_count(qasm_version: &str, expected: usize) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut gate_circuit = Circuit::new();
+    gate_circuit += Hadamard::new(0);
+    let mut circuit = Circuit::new();
+    circuit += GateDefinition::new(gate_circuit.clone(), "custom_a".to_owned(), vec![0], vec![]);
+    circuit += GateDefinition::new(gate_circuit, "custom_b".to_owned(), vec![0], vec![]);
+    circuit += CallDefinedGate::new("custom_a".to_owned(), vec![0], vec![]);
+    circuit += CallDefinedGate::new("custom_b".to_owned(), vec![1], vec![]);
+    circuit += CallDefinedGate::new("custom_a".to_owned(), vec![1], vec![]);
+    circuit += PauliX::new(0);
+    circuit += PauliX::new(1);
+
+    // u3, u2, u1, rx, ry, rz and cx are always defined, h is required by the custom gates, x by PauliX
+    assert_eq!(backend.definition_count(&circuit).unwrap(), expected);
+}
+
+/// Test the deduplicated list of required gate definitions
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0Roqoqo"; "3.0Roqoqo")]
+fn test_required_gate_definitions(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += Hadamard::new(1);
+    circuit += PauliX::new(1);
+    circuit += CNOT::new(0, 1);
+
+    let qasm_version = QasmVersion::from_str(qasm_version).unwrap();
+    assert_eq!(
+        backend.required_gate_definitions(&circuit).unwrap(),
+        vec![
+            gate_definition(&Operation::from(PauliX::new(0)), qasm_version).unwrap(),
+            gate_definition(&Operation::from(Hadamard::new(0)), qasm_version).unwrap(),
+        ]
+    );
+}
+
+/// Test that the estimated output size is close to the size of the QASM output
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_estimated_output_size(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 12, true);
+    for qubit in 0..12 {
+        circuit += Hadamard::new(qubit);
+        circuit += RotateZ::new(qubit, (0.1 * qubit as f64).into());
+        circuit += CNOT::new(qubit, (qubit + 1) % 12);
+        circuit += MeasureQubit::new(qubit, "ro".to_string(), qubit);
+    }
+
+    let actual = backend.circuit_to_qasm_str(&circuit).unwrap().len();
+    let estimate = backend.estimated_output_size(&circuit).unwrap();
+    assert!(estimate.abs_diff(actual) * 10 <= actual);
+}
+
+/// Test that the estimate does not translate the operations and accounts for the end marker
+#[test]
+fn test_estimated_output_size_without_translation() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    // The translation fails for the unsupported cosh function, the estimate does not translate it
+    let circuit = Circuit::new() + RotateZ::new(0, "cosh(alpha)".into());
+    assert!(backend.circuit_to_qasm_str(&circuit).is_err());
+    let estimate = backend.estimated_output_size(&circuit).unwrap();
+
+    let backend = backend.with_end_marker(true);
+    assert_eq!(
+        backend.estimated_output_size(&circuit).unwrap(),
+        estimate + "// end of circuit\n".len()
+    );
+}
+
+/// Test the mapping of the operations to their QASM translation
+#[test]
+fn test_operation_qasm_map() {
+    let backend = Backend::new(None, Some("2.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += Hadamard::new(0);
+    circuit += PragmaSetNumberOfMeasurements::new(10, "ro".to_string());
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    assert_eq!(
+        backend.operation_qasm_map(&circuit).unwrap(),
+        vec![
+            (0, "DefinitionBit".to_string(), "creg ro[1];".to_string()),
+            (1, "Hadamard".to_string(), "h q[0];".to_string()),
+            (
+                2,
+                "PragmaSetNumberOfMeasurements".to_string(),
+                "".to_string()
+            ),
+            (
+                3,
+                "MeasureQubit".to_string(),
+                "measure q[0] -> ro[0];".to_string()
+            ),
+        ]
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += PragmaDamping::new(0, 1.0.into(), 1.0.into());
+    assert!(backend.operation_qasm_map(&circuit).is_err());
+}
+
+/// Test the summary comment of the verbose output
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_verbose_summary(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_verbose(true);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 3, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 2);
+    circuit += PragmaSetNumberOfMeasurements::new(20, "ro".to_string());
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 20, None);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(qasm.starts_with(&format!(
+        "OPENQASM {qasm_version};\n\n// qubits: 3, gates: 4\n\n"
+    )));
+    assert!(!Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("// qubits"));
+}
+
+/// Test the generation metadata comment after the OpenQASM header
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_header_comment(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_header_comment(true);
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    let lines: Vec<&str> = qasm.lines().collect();
+    assert_eq!(lines[0], format!("OPENQASM {qasm_version};"));
+    assert_eq!(
+        lines[2],
+        format!("// Generated by roqoqo-qasm v{}", env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(qasm.matches("// Generated by").count(), 1);
+    assert_eq!(backend.string_to_circuit(&qasm).unwrap(), circuit);
+    assert!(!Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("// Generated by"));
+}
+
+/// Test the export of a circuit to two quantum registers
+#[test_case("2.0", "qreg a[1];\nqreg b[1];\n"; "2.0")]
+#[test_case("3.0", "qubit[1] a;\nqubit[1] b;\n"; "3.0")]
+fn test_register_mapping(qasm_version: &str, declarations: &str) {
+    let register_mapping: HashMap<usize, (String, usize)> =
+        HashMap::from([(0, ("a".to_string(), 0)), (1, ("b".to_string(), 0))]);
+    let backend = Backend::new(None, Some(qasm_version.to_string()))
+        .unwrap()
+        .with_register_mapping(register_mapping);
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains(declarations));
+    assert!(!qasm.contains("q["));
+    assert!(qasm.contains("h a[0];\ncx a[0],b[0];\n"));
+    assert!(qasm.contains("measure b[0]"));
+
+    let mut writer_output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut writer_output)
+        .unwrap();
+    assert_eq!(String::from_utf8(writer_output).unwrap(), qasm);
+
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(2);
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Qubit 2 used by PauliX is not in the register mapping".to_string()
+        })
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 20, None);
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "PragmaRepeatedMeasurement acts on the whole qubit register and can not be translated with a register mapping".to_string()
+        })
+    );
+}
+
+/// Test the number of blank lines between the sections of the output
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_section_blank_lines(qasm_version: &str) {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += RotateX::new(1, "theta".into());
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let default_qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(default_qasm.contains("\n\n"));
+
+    let backend = backend.with_section_blank_lines(0);
+    let compact_qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!compact_qasm.contains("\n\n"));
+    assert!(compact_qasm.starts_with(&format!("OPENQASM {qasm_version};\ngate u3(")));
+    assert_eq!(
+        compact_qasm,
+        default_qasm
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| format!("{line}\n"))
+            .collect::<String>()
+    );
+    let mut writer_output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut writer_output)
+        .unwrap();
+    assert_eq!(String::from_utf8(writer_output).unwrap(), compact_qasm);
+
+    let backend = backend.with_section_blank_lines(1);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!qasm.contains("\n\n\n"));
+    assert_eq!(qasm.replace("\n\n", "\n"), compact_qasm);
+}
+
+/// Test the declaration of qubit aliases
+#[test]
+fn test_qubit_aliases() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 3);
+    let aliases = HashMap::from([("rest".to_string(), 2..4), ("first".to_string(), 0..2)]);
+
+    let qasm = backend
+        .circuit_to_qasm_str_with_aliases(&circuit, &aliases)
+        .unwrap();
+
+    assert!(qasm.contains(
+        "qubit[4] q;\n\nlet first = q[0:1];\nlet rest = q[2:3];\n\nh q[0];\ncx q[0],q[3];\n"
+    ));
+
+    let empty_alias = HashMap::from([("none".to_string(), 2..2)]);
+    assert_eq!(
+        backend.circuit_to_qasm_str_with_aliases(&circuit, &empty_alias),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "The qubit range of the alias none is empty".to_string()
+        })
+    );
+
+    let backend_2 = Backend::new(None, Some("2.0".to_string())).unwrap();
+    assert_eq!(
+        backend_2.circuit_to_qasm_str_with_aliases(&circuit, &aliases),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Qubit aliases are only supported for vanilla OpenQASM 3.0, not for V2point0(Vanilla)"
+                .to_string()
+        })
+    );
+}
+
+/// Test that calibration blocks are inserted verbatim after the header
+#[test]
+fn test_calibration_blocks() {
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_stdgates_include(true);
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    let cal_blocks = vec![
+        "cal {\n    extern port d0;\n}".to_string(),
+        "cal { frame f0 = newframe(d0, 5.0e9, 0); }".to_string(),
+    ];
+
+    let qasm = backend
+        .circuit_to_qasm_str_with_cal(&circuit, &cal_blocks)
+        .unwrap();
+
+    assert!(qasm.starts_with(
+        "OPENQASM 3.0;\n\ndefcalgrammar \"openpulse\";\ncal {\n    extern port d0;\n}\ncal { frame f0 = newframe(d0, 5.0e9, 0); }\n\ninclude \"stdgates.inc\";\n"
+    ));
+    assert!(qasm.ends_with("h q[0];\n"));
+
+    let backend_roqoqo = Backend::new(None, Some("3.0Roqoqo".to_string())).unwrap();
+    assert_eq!(
+        backend_roqoqo.circuit_to_qasm_str_with_cal(&circuit, &cal_blocks),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Calibration blocks are only supported for vanilla OpenQASM 3.0, not for V3point0(Roqoqo)"
+                .to_string()
+        })
+    );
+}
+
+/// Test that calibration blocks are inserted after the comments following the header
+#[test_case(true, false, "// Generated by roqoqo-qasm v{}\n\n"; "header comment")]
+#[test_case(false, true, "// qubits: 1, gates: 1\n\n"; "verbose")]
+#[test_case(true, true, "// Generated by roqoqo-qasm v{}\n\n// qubits: 1, gates: 1\n\n"; "both")]
+fn test_calibration_blocks_after_comments(header_comment: bool, verbose: bool, comments: &str) {
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_header_comment(header_comment)
+        .with_verbose(verbose);
+    let circuit = Circuit::new() + Hadamard::new(0);
+    let cal_blocks = vec!["cal {\n    extern port d0;\n}".to_string()];
+
+    let qasm = backend
+        .circuit_to_qasm_str_with_cal(&circuit, &cal_blocks)
+        .unwrap();
+
+    let comments = comments.replace("{}", env!("CARGO_PKG_VERSION"));
+    assert!(qasm.starts_with(&format!(
+        "OPENQASM 3.0;\n\n{}defcalgrammar \"openpulse\";\ncal {{\n    extern port d0;\n}}\n\ngate u3(",
+        comments
+    )));
+}
+
+/// Test that the stdgates.inc include replaces the definitions of the gates it provides
+#[test]
+fn test_stdgates_include() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += ControlledPhaseShift::new(0, 1, 0.5.into());
+    circuit += SqrtPauliX::new(1);
+    circuit += ISwap::new(0, 1);
+
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    assert_eq!(backend.definition_count(&circuit).unwrap(), 11);
+
+    let backend = backend.with_stdgates_include(true);
+    assert_eq!(backend.definition_count(&circuit).unwrap(), 1);
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit).unwrap(),
+        "OPENQASM 3.0;\n\ninclude \"stdgates.inc\";\n\ngate iswap a,b { rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }\n\nqubit[2] q;\n\nh q[0];\ncx q[0],q[1];\ncp(5e-1) q[0],q[1];\nsx q[1];\niswap q[0],q[1];\n"
+    );
+
+    // The Braket dialect does not define its native gates, with or without the include
+    let backend = Backend::new(None, Some("3.0Braket".to_string()))
+        .unwrap()
+        .with_stdgates_include(true);
+    assert!(!backend
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("include"));
+    assert_eq!(backend.definition_count(&circuit).unwrap(), 0);
+}
+
+/// Test that the qelib1.inc include replaces the definitions of the gates it provides
+#[test]
+fn test_qelib_include() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += ControlledPhaseShift::new(0, 1, 0.5.into());
+    circuit += ISwap::new(0, 1);
+
+    let backend = Backend::new(None, Some("2.0".to_string()))
+        .unwrap()
+        .with_qelib_include(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.starts_with("OPENQASM 2.0;\n\ninclude \"qelib1.inc\";\n\n"));
+    assert!(!qasm.contains("gate u3"));
+    assert!(!qasm.contains("gate h "));
+    assert!(qasm.contains("gate cp(lambda) a,b {"));
+    assert!(qasm.contains("gate iswap a,b {"));
+    assert_eq!(backend.definition_count(&circuit).unwrap(), 2);
+
+    // qelib1.inc provides ControlledPhaseShift as cu1
+    let backend = backend.with_cu1_controlled_phase(true);
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit).unwrap(),
+        "OPENQASM 2.0;\n\ninclude \"qelib1.inc\";\n\ngate iswap a,b { rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }\n\nqreg q[2];\n\nh q[0];\ncx q[0],q[1];\ncu1(5e-1) q[0],q[1];\niswap q[0],q[1];\n"
+    );
+
+    // OpenQASM 3.0 keeps the inline definitions
+    let qasm = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_qelib_include(true)
+        .circuit_to_qasm_str(&circuit)
+        .unwrap();
+    assert!(!qasm.contains("qelib1.inc"));
+    assert!(qasm.contains("gate u3(theta,phi,lambda) q {"));
+}
+
+/// Test that the Qiskit dialect always includes stdgates.inc
+#[test]
+fn test_qiskit_dialect() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += InvSqrtPauliX::new(1);
+    circuit += MultiQubitMS::new(vec![0, 1, 2], 0.5.into());
+
+    let backend = Backend::new(None, Some("3.0Qiskit".to_string())).unwrap();
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.starts_with("OPENQASM 3.0;\n\ninclude \"stdgates.inc\";\n\n"));
+    assert!(!qasm.contains("gate u3"));
+    assert!(!qasm.contains("gate h "));
+    assert!(qasm.contains("gate sxdg a {"));
+    assert!(qasm.contains("gate rxx_multi_3(theta) a0,a1,a2 {"));
+    assert!(
+        qasm.ends_with("h q[0];\ncx q[0],q[1];\nsxdg q[1];\nrxx_multi_3(5e-1) q[0],q[1],q[2];\n")
+    );
+    assert_eq!(
+        backend
+            .with_stdgates_include(false)
+            .circuit_to_qasm_str(&circuit)
+            .unwrap(),
+        qasm
+    );
+}
+
+/// Test that the IonQ dialect emits native gates without any gate definitions
+#[test]
+fn test_ionq_dialect() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += GPi::new(0, 0.5.into());
+    circuit += GPi2::new(1, 0.25.into());
+    circuit += MolmerSorensenXX::new(0, 1);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    let backend = Backend::new(None, Some("3.0IonQ".to_string())).unwrap();
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit).unwrap(),
+        "OPENQASM 3.0;\n\n\nqubit[2] q;\n\noutput bit[2] ro;\ngpi(5e-1) q[0];\ngpi2(2.5e-1) q[1];\nms(0,0,pi/2) q[0],q[1];\nmeasure q[0] -> ro[0];\n"
+    );
+
+    circuit += Hadamard::new(0);
+    assert!(backend.circuit_to_qasm_str(&circuit).is_err());
+}
+
+/// Test that the gates used in the bodies of control-flow pragmas are defined in the preamble
+#[test]
+fn test_nested_gate_definitions() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("c".to_string(), 2, false);
+    circuit += PragmaConditional::new(
+        "c".to_string(),
+        0,
+        Circuit::new() + MolmerSorensenXX::new(0, 1),
+    );
+    circuit += PragmaConditional::new(
+        "c".to_string(),
+        1,
+        Circuit::new() + PragmaLoop::new(2.0.into(), Circuit::new() + ISwap::new(0, 1)),
+    );
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    let (preamble, data) = qasm.split_once("qubit[2] q;").unwrap();
+    assert!(preamble.contains("gate rxx(theta) a,b {"));
+    assert!(preamble.contains("gate iswap a,b {"));
+    assert!(data.contains("rxx(pi/2) q[0],q[1];"));
+    assert!(data.contains("iswap q[0],q[1];"));
+}
+
+/// Test that the basis rotation of a Pauli-X measurement is emitted before the measurement
+#[test]
+fn test_inline_measurement_basis() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += RotateY::new(0, 0.5.into());
+    circuit += PragmaGetPauliProduct::new(
+        HashMap::from([(0, 0)]),
+        "ro".to_string(),
+        Circuit::new() + Hadamard::new(0),
+    );
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let qasm = backend
+        .clone()
+        .with_inline_measurement_basis(true)
+        .circuit_to_qasm_str(&circuit)
+        .unwrap();
+    assert!(qasm.contains("gate h a { u2(0,pi) a; }"));
+    assert!(qasm.ends_with("ry(5e-1) q[0];\nh q[0];\nmeasure q[0] -> ro[0];\n"));
+
+    // The basis rotation is dropped by default
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!qasm.contains("h q[0];"));
+}
+
+/// Test the indentation of a conditional nested in a loop
+#[test_case(None, "for uint i in [0:1] {\n    if(c[0]==1) {\n        h q[0];\n        x q[0];\n    }\n}\n"; "default")]
+#[test_case(Some(2), "for uint i in [0:1] {\n  if(c[0]==1) {\n    h q[0];\n    x q[0];\n  }\n}\n"; "2 spaces")]
+fn test_indent_width(indent_width: Option<usize>, data: &str) {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("c".to_string(), 1, false);
+    circuit += PragmaLoop::new(
+        2.0.into(),
+        Circuit::new()
+            + PragmaConditional::new(
+                "c".to_string(),
+                0,
+                Circuit::new() + Hadamard::new(0) + PauliX::new(0),
+            ),
+    );
+
+    let mut backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    if let Some(indent_width) = indent_width {
+        backend = backend.with_indent_width(indent_width);
+    }
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.ends_with(data));
+}
+
+/// Test that a circuit without qubit operations declares no qubit register when requested
+#[test_case("2.0", "qreg q[1];"; "2.0")]
+#[test_case("3.0", "qubit[1] q;"; "3.0")]
+fn test_omit_unused_qubit_register(qasm_version: &str, declaration: &str) {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, false);
+    circuit += DefinitionFloat::new("theta".to_string(), 1, false);
+
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    assert!(backend
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains(declaration));
+
+    let backend = backend.with_omit_unused_qubit_register(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!qasm.contains(declaration));
+    assert!(!qasm.contains("qreg"));
+    assert!(!qasm.contains("qubit["));
+
+    // Circuits with qubit operations still declare the register
+    circuit += PauliX::new(1);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains(&declaration.replace('1', "2")));
+}
+
+/// Test that decomposition blocks are exported as box blocks
+#[test]
+fn test_box_decomposition_blocks() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit += Hadamard::new(0);
+    circuit += PragmaStartDecompositionBlock::new(vec![0, 1], HashMap::new());
+    circuit += CNOT::new(0, 1);
+    circuit += RotateZ::new(1, "theta".into());
+    circuit += PragmaStopDecompositionBlock::new(vec![0, 1]);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_box_decomposition_blocks(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.ends_with(
+        "h q[0];\nbox {\n    cx q[0],q[1];\n    rz(theta) q[1];\n}\nmeasure q[0] -> ro[0];\n"
+    ));
+
+    let mut output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), qasm);
+
+    // A block that is not closed can not be exported
+    let mut circuit = Circuit::new();
+    circuit += PragmaStartDecompositionBlock::new(vec![0], HashMap::new());
+    circuit += Hadamard::new(0);
+    assert!(backend.circuit_to_qasm_str(&circuit).is_err());
+}
+
+/// Test the symbolic parameters gathered from a circuit
+#[test_case("3.0", HashSet::from(["alpha".to_string(), "beta".to_string()]); "3.0")]
+#[test_case("3.0Roqoqo", HashSet::from(["alpha".to_string(), "beta".to_string()]); "3.0Roqoqo")]
+#[test_case("2.0", HashSet::new(); "2.0")]
+fn test_gather_variables(qasm_version: &str, variables: HashSet<String>) {
+    let mut circuit = Circuit::new();
+    circuit += RotateZ::new(0, "alpha".into());
+    circuit += RotateX::new(1, "beta/2".into());
+    circuit += Hadamard::new(0);
+
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    assert_eq!(backend.gather_variables(&circuit).unwrap(), variables);
+}
+
+/// Test the translation with permuted qubits
+#[test]
+fn test_permuted_qubits() {
+    let backend = Backend::new(None, Some("2.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 2);
+    circuit += MeasureQubit::new(2, "ro".to_string(), 1);
+
+    let qasm = backend
+        .circuit_to_qasm_str_permuted(&circuit, &[2, 0, 1])
+        .unwrap();
+    assert!(qasm.ends_with("h q[2];\ncx q[2],q[1];\nmeasure q[1] -> ro[1];\n"));
+
+    assert_eq!(
+        backend.circuit_to_qasm_str_permuted(&circuit, &[1, 1, 0]),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "[1, 1, 0] is not a permutation of the qubits 0..3".to_string()
+        })
+    );
+    assert_eq!(
+        backend.circuit_to_qasm_str_permuted(&circuit, &[1, 0]),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "The permutation of 2 qubits does not cover qubit 2 used by CNOT".to_string()
+        })
+    );
+}
+
+/// Test that MultiQubitMS is defined once for each number of qubits
+#[test]
+fn test_multi_qubit_ms_definitions() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += MultiQubitMS::new(vec![0, 1, 2], 0.5.into());
+    circuit += MultiQubitMS::new(vec![1, 2, 3], 0.5.into());
+    circuit += MultiQubitMS::new(vec![0, 1, 2, 3], 0.5.into());
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert_eq!(qasm.matches("gate rxx_multi_3(theta)").count(), 1);
+    assert_eq!(qasm.matches("gate rxx_multi_4(theta)").count(), 1);
+    assert!(qasm.ends_with(
+        "rxx_multi_3(5e-1) q[0],q[1],q[2];\nrxx_multi_3(5e-1) q[1],q[2],q[3];\nrxx_multi_4(5e-1) q[0],q[1],q[2],q[3];\n"
+    ));
+}
+
+/// Test that MultiQubitZZ is defined once for each number of qubits
+#[test]
+fn test_multi_qubit_zz_definitions() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += MultiQubitZZ::new(vec![0, 1, 2], 0.5.into());
+    circuit += MultiQubitZZ::new(vec![1, 2, 3], 0.5.into());
+    circuit += MultiQubitZZ::new(vec![0, 1, 2, 3], 0.5.into());
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains(
+        "gate rzz_multi_3(theta) a0,a1,a2 { cx a0,a1; cx a1,a2; rz(theta) a2; cx a1,a2; cx a0,a1; }"
+    ));
+    assert_eq!(qasm.matches("gate rzz_multi_4(theta)").count(), 1);
+    assert!(qasm.ends_with(
+        "rzz_multi_3(5e-1) q[0],q[1],q[2];\nrzz_multi_3(5e-1) q[1],q[2],q[3];\nrzz_multi_4(5e-1) q[0],q[1],q[2],q[3];\n"
+    ));
+}
+
+/// Test that the two-qubit rzz gate of the OpenQASM 2.0 CNOT ladder is defined once for all sizes
+#[test]
+fn test_multi_qubit_zz_definitions_2_0() {
+    let backend = Backend::new(None, Some("2.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += MultiQubitZZ::new(vec![0, 1], 0.5.into());
+    circuit += MultiQubitZZ::new(vec![0, 1, 2], 0.5.into());
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert_eq!(qasm.matches("gate rzz(theta) a,b").count(), 1);
+    assert!(qasm
+        .ends_with("rzz(5e-1) q[0],q[1];\ncx q[0],q[1];\nrzz(5e-1) q[1],q[2];\ncx q[0],q[1];\n"));
+}
+
+/// Test that the gate definitions are placed right before their first use
+#[test]
+fn test_definitions_inline() {
+    let mut circuit = Circuit::new();
+    circuit += RotateX::new(0, 0.5.into());
+    circuit += Hadamard::new(0);
+    circuit += PauliX::new(1);
+    circuit += Hadamard::new(1);
+    circuit += PragmaLoop::new(2.0.into(), Circuit::new() + PauliY::new(0));
+
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_definitions_inline(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.ends_with(
+        "qubit[2] q;\n\nrx(5e-1) q[0];\ngate h a { u2(0,pi) a; }\nh q[0];\ngate x a { u3(pi,0,pi) a; }\nx q[1];\nh q[1];\ngate y a { u3(pi,pi/2,pi/2) a; }\nfor uint i in [0:1] {\n    y q[0];\n}\n"
+    ));
+    // The definitions that are always needed stay after the header
+    assert!(qasm.contains("gate rx(theta) a { u3(theta,-pi/2,pi/2) a; }\n"));
+    assert_eq!(qasm.matches("gate h ").count(), 1);
+
+    let mut buffer: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut buffer)
+        .unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), qasm);
+}
+
+/// Test the end of circuit marker
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_end_marker(qasm_version: &str) {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    assert!(!backend
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("// end of circuit"));
+
+    let backend = backend.with_end_marker(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.ends_with("cx q[0],q[1];\n// end of circuit\n"));
+
+    let mut output: Vec<u8> = Vec::new();
+    backend
+        .circuit_to_qasm_writer(&circuit, &mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), qasm);
+}
+
+/// Test that EchoCrossResonance is defined for OpenQASM 2.0 and 3.0 and native in Braket
+#[test_case("2.0", true; "2.0")]
+#[test_case("3.0", true; "3.0")]
+#[test_case("3.0Braket", false; "3.0Braket")]
+fn test_echo_cross_resonance(qasm_version: &str, defined: bool) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += EchoCrossResonance::new(0, 1);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert_eq!(qasm.contains("gate ecr a,b {"), defined);
+    assert!(qasm.ends_with("ecr q[0],q[1];\n"));
+}
+
+/// Test that the reordering comment of a decomposition block is emitted on its own line
+#[test]
+fn test_decomposition_block_reordering_comment() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PragmaStartDecompositionBlock::new(vec![0, 1], HashMap::from([(0, 1), (1, 0)]));
+    circuit += CNOT::new(0, 1);
+    circuit += PragmaStopDecompositionBlock::new(vec![0, 1]);
+    circuit += Hadamard::new(0);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(qasm.ends_with("// reorder: 0->1, 1->0\ncx q[0],q[1];\nh q[0];\n"));
+}
+
+/// Test that the lint reports each category of issue
+#[test_case(
+    vec![DefinitionBit::new("ro".to_string(), 1, true).into(), MeasureQubit::new(0, "ro".to_string(), 0).into()],
+    vec![];
+    "no issue"
+)]
+#[test_case(
+    vec![DefinitionBit::new("ro".to_string(), 1, true).into(), DefinitionFloat::new("ro".to_string(), 1, true).into()],
+    vec!["Duplicate declaration of classical register ro"];
+    "duplicate register"
+)]
+#[test_case(
+    vec![MeasureQubit::new(0, "ro".to_string(), 0).into()],
+    vec!["MeasureQubit measures into undeclared classical register ro"];
+    "measurement undeclared"
+)]
+#[test_case(
+    vec![PragmaRepeatedMeasurement::new("ro".to_string(), 10, None).into()],
+    vec!["PragmaRepeatedMeasurement measures into undeclared classical register ro"];
+    "repeated measurement undeclared"
+)]
+#[test_case(
+    vec![PragmaConditional::new("c".to_string(), 0, Circuit::new() + PauliX::new(0)).into()],
+    vec!["PragmaConditional uses undeclared classical register c"];
+    "register undeclared"
+)]
+#[test_case(
+    vec![
+        CallDefinedGate::new("custom".to_owned(), vec![0], vec![]).into(),
+        PragmaLoop::new(2.0.into(), Circuit::new() + GateDefinition::new(Circuit::new() + PauliX::new(0), "custom".to_owned(), vec![0], vec![])).into(),
+    ],
+    vec!["Gate custom is called before it is defined"];
+    "call before definition"
+)]
+#[test_case(
+    vec![
+        GateDefinition::new(Circuit::new() + PauliX::new(0), "custom".to_owned(), vec![0], vec![]).into(),
+        GateDefinition::new(Circuit::new() + PauliY::new(0), "custom".to_owned(), vec![0], vec![]).into(),
+    ],
+    vec!["Duplicate definition of gate custom"];
+    "duplicate gate"
+)]
+#[test_case(
+    vec![PragmaLoop::new(2.0.into(), Circuit::new() + MeasureQubit::new(0, "ro".to_string(), 0)).into()],
+    vec!["MeasureQubit measures into undeclared classical register ro"];
+    "nested measurement undeclared"
+)]
+#[test_case(
+    vec![
+        DefinitionBit::new("ro".to_string(), 1, true).into(),
+        MeasureQubit::new(0, "ro".to_string(), 0).into(),
+        MeasureQubit::new(1, "ro".to_string(), 0).into(),
+    ],
+    vec!["MeasureQubit writes to the classical bit ro[0] that was already written"];
+    "repeated readout"
+)]
+fn test_lint(operations: Vec<Operation>, warnings: Vec<&str>) {
+    let backend = Backend::new(None, None).unwrap();
+    let mut circuit = Circuit::new();
+    for operation in operations {
+        circuit.add_operation(operation);
+    }
+
+    assert_eq!(backend.lint(&circuit), warnings);
+}
+
+/// Test that the strict backend rejects writing a classical bit twice
+#[test]
+fn test_strict_repeated_readout() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 0);
+
+    let backend = Backend::new(None, None).unwrap();
+    assert!(backend.circuit_to_qasm_str(&circuit).is_ok());
+    assert_eq!(
+        backend.with_strict(true).circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "MeasureQubit writes to the classical bit ro[0] that was already written"
+                .to_string()
+        })
+    );
+
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 0);
+    circuit += MeasureQubit::new(1, "ro".to_string(), 1);
+    assert!(Backend::new(None, None)
+        .unwrap()
+        .with_strict(true)
+        .circuit_to_qasm_str(&circuit)
+        .is_ok());
+}
+
+/// Test that the ctrl @ modifier replaces the controlled gate definitions in vanilla OpenQASM 3.0
+#[test]
+fn test_ctrl_modifier() {
+    let mut circuit = Circuit::new();
+    circuit += PauliZ::new(0);
+    circuit += ControlledPauliZ::new(0, 1);
+    circuit += ControlledPauliY::new(1, 0);
+    circuit += ControlledPhaseShift::new(0, 1, 0.5.into());
+
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_ctrl_modifier(true);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert_eq!(qasm.matches("gate z a { u1(pi) a; }").count(), 1);
+    assert!(qasm.contains("gate y a { u3(pi,pi/2,pi/2) a; }"));
+    assert!(qasm.contains("gate p(lambda) q { U(0,0,lambda) q; }"));
+    assert!(!qasm.contains("gate cz"));
+    assert!(!qasm.contains("gate cy"));
+    assert!(!qasm.contains("gate cp"));
+    assert!(qasm.ends_with(
+        "z q[0];\nctrl @ z q[0],q[1];\nctrl @ y q[1],q[0];\nctrl @ p(5e-1) q[0],q[1];\n"
+    ));
+
+    // OpenQASM 2.0 and the Roqoqo dialect keep the defined controlled gates
+    for qasm_version in ["2.0", "3.0Roqoqo"] {
+        let backend = Backend::new(None, Some(qasm_version.to_string()))
+            .unwrap()
+            .with_ctrl_modifier(true);
+        let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+        assert!(qasm.contains("gate cz a,b {"));
+        assert!(qasm.ends_with("z q[0];\ncz q[0],q[1];\ncy q[1],q[0];\ncp(5e-1) q[0],q[1];\n"));
+    }
+}
+
+/// Test that measuring beyond the length of the classical register returns an error
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+fn test_readout_index_out_of_range(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += MeasureQubit::new(0, "ro".to_string(), 1);
+    assert!(backend.circuit_to_qasm_str(&circuit).is_ok());
+
+    circuit += MeasureQubit::new(1, "ro".to_string(), 5);
+    let error = Err(RoqoqoBackendError::GenericError {
+        msg: "MeasureQubit writes to index 5 of the classical register ro of length 2".to_string(),
+    });
+    assert_eq!(backend.circuit_to_qasm_str(&circuit), error);
+    assert_eq!(
+        backend.circuit_to_qasm_writer(&circuit, Vec::new()),
+        error.map(|_| ())
+    );
+}
+
+/// Test that clone_with_version only changes the QASM version
+#[test]
+fn test_clone_with_version() {
+    let backend = Backend::new(Some("qr".to_string()), Some("2.0".to_string()))
+        .unwrap()
+        .with_builtin_cnot(true)
+        .with_end_marker(true);
+
+    let cloned = backend.clone_with_version(QasmVersion::V3point0(Qasm3Dialect::Braket));
+    assert_eq!(
+        cloned,
+        Backend::new(Some("qr".to_string()), Some("3.0Braket".to_string()))
+            .unwrap()
+            .with_builtin_cnot(true)
+            .with_end_marker(true)
+    );
+    assert_eq!(backend.clone_with_version_str("3.0Braket").unwrap(), cloned);
+    assert_eq!(
+        backend.clone_with_version_str("3").unwrap(),
+        backend.clone_with_version(QasmVersion::V3point0(Qasm3Dialect::Vanilla))
+    );
+    assert_eq!(
+        backend.clone_with_version_str("4.0"),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Version for OpenQASM used is neither 2.0 nor 3.0: 4.0. Valid versions are 2.0, 2.0Vanilla, 2.0Qulacs, 3, 3.0, 3.0Vanilla, 3.0Roqoqo, 3.0Braket, 3.0Qiskit and 3.0IonQ".to_string()
+        })
+    );
+}
+
+/// Test that the gates inside a PragmaControlledCircuit are defined
+#[test]
+fn test_pragma_controlled_circuit_definitions() {
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += PragmaControlledCircuit::new(0, Circuit::new() + Hadamard::new(1));
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+
+    assert!(qasm.contains("gate h a { u2(0,pi) a; }\n"));
+    assert!(qasm.ends_with("qubit[2] q;\n\nctrl @ h q[0],q[1];\n"));
+}
+
+/// Test that the validation mode reports the operation acting outside of the qubit register
+#[test]
+fn test_validation() {
+    let mut backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_validation(true);
+    let mut circuit = Circuit::new();
+    circuit += PauliX::new(0);
+    circuit += PauliX::new(2);
+
+    // Without a fixed register size, the register is sized from the circuit
+    assert!(backend
+        .circuit_to_qasm_str(&circuit)
+        .unwrap()
+        .contains("qubit[3] q;"));
+
+    backend.set_number_of_qubits(Some(2));
+    assert_eq!(
+        backend.circuit_to_qasm_str(&circuit),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "PauliX acts on qubit 2 outside of the fixed qubit register of size 2".to_string()
+        })
+    );
+
+    backend.set_number_of_qubits(None);
+    assert!(backend.circuit_to_qasm_str(&circuit).is_ok());
+}
+
+/// Test that the phase gates use the stdgates.inc names instead of inline definitions
+#[test]
+fn test_stdgates_include_phase_gates() {
+    let mut circuit = Circuit::new();
+    circuit += PauliZ::new(0);
+    circuit += SGate::new(0);
+    circuit += TGate::new(0);
+    circuit += InvSGate::new(0);
+    circuit += InvTGate::new(0);
+
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!qasm.contains("include \"stdgates.inc\";"));
+    assert!(qasm.contains("gate z a "));
+
+    let qasm = backend
+        .with_stdgates_include(true)
+        .circuit_to_qasm_str(&circuit)
+        .unwrap();
+    assert!(qasm.contains("include \"stdgates.inc\";"));
+    assert!(!qasm.contains("gate z"));
+    assert!(qasm.ends_with("z q[0];\ns q[0];\nt q[0];\nsdg q[0];\ntdg q[0];\n"));
+}
+
+/// Test that no gate is defined when all gates of the circuit are provided by stdgates.inc
+#[test]
+fn test_stdgates_names() {
+    let operations: Vec<(Operation, &str)> = vec![
+        (RotateX::new(0, 0.5.into()).into(), "rx(5e-1) q[0];"),
+        (RotateY::new(0, 0.5.into()).into(), "ry(5e-1) q[0];"),
+        (RotateZ::new(0, 0.5.into()).into(), "rz(5e-1) q[0];"),
+        (Hadamard::new(0).into(), "h q[0];"),
+        (PauliX::new(0).into(), "x q[0];"),
+        (PauliY::new(0).into(), "y q[0];"),
+        (PauliZ::new(0).into(), "z q[0];"),
+        (SGate::new(0).into(), "s q[0];"),
+        (InvSGate::new(0).into(), "sdg q[0];"),
+        (TGate::new(0).into(), "t q[0];"),
+        (InvTGate::new(0).into(), "tdg q[0];"),
+        (PhaseShiftState1::new(0, 0.5.into()).into(), "p(5e-1) q[0];"),
+        (SqrtPauliX::new(0).into(), "sx q[0];"),
+        (Identity::new(0).into(), "id q[0];"),
+        (CNOT::new(0, 1).into(), "cx q[0],q[1];"),
+        (ControlledPauliY::new(0, 1).into(), "cy q[0],q[1];"),
+        (ControlledPauliZ::new(0, 1).into(), "cz q[0],q[1];"),
+        (
+            ControlledPhaseShift::new(0, 1, 0.5.into()).into(),
+            "cp(5e-1) q[0],q[1];",
+        ),
+        (
+            ControlledRotateX::new(0, 1, 0.5.into()).into(),
+            "crx(5e-1) q[0],q[1];",
+        ),
+        (SWAP::new(0, 1).into(), "swap q[0],q[1];"),
+        (Toffoli::new(0, 1, 2).into(), "ccx q[0],q[1],q[2];"),
+    ];
+    let mut circuit = Circuit::new();
+    for (operation, _) in operations.iter() {
+        circuit.add_operation(operation.clone());
+    }
+
+    let backend = Backend::new(None, Some("3.0".to_string()))
+        .unwrap()
+        .with_stdgates_include(true);
+    assert_eq!(backend.definition_count(&circuit).unwrap(), 0);
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(!qasm.contains("gate "));
+    let data: Vec<&str> = operations.iter().map(|(_, line)| *line).collect();
+    assert!(qasm.ends_with(&format!("{}\n", data.join("\n"))));
+}
+
+/// Test the translation of the measurement circuits of a QuantumProgram
+#[test]
+fn test_quantum_program_to_qasm_strings() {
+    use roqoqo::measurements::ClassicalRegister;
+    use roqoqo::QuantumProgram;
+
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let constant_circuit = Circuit::new() + Hadamard::new(0);
+    let mut circuit_x = Circuit::new();
+    circuit_x += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit_x += MeasureQubit::new(0, "ro".to_string(), 0);
+    let mut circuit_z = Circuit::new();
+    circuit_z += DefinitionBit::new("ro".to_string(), 1, true);
+    circuit_z += Hadamard::new(0);
+    circuit_z += MeasureQubit::new(0, "ro".to_string(), 0);
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: Some(constant_circuit.clone()),
+            circuits: vec![circuit_x.clone(), circuit_z.clone()],
+        },
+        input_parameter_names: vec![],
+    };
+
+    let qasm_strings = backend.quantum_program_to_qasm_strings(&program).unwrap();
+
+    // Every measurement circuit is a complete QASM program with its own header
+    let expected = vec![
+        backend
+            .circuit_to_qasm_str(&(constant_circuit.clone() + circuit_x))
+            .unwrap(),
+        backend
+            .circuit_to_qasm_str(&(constant_circuit + circuit_z))
+            .unwrap(),
+    ];
+    assert_eq!(qasm_strings, expected);
+    assert!(qasm_strings
+        .iter()
+        .all(|qasm| qasm.matches("OPENQASM").count() == 1));
+}
+
+/// Test the round trip check of a Bell state circuit
+#[cfg(feature = "unstable_qasm_import")]
+#[test_case("2.0"; "2.0")]
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Roqoqo"; "3.0Roqoqo")]
+#[test_case("3.0Braket"; "3.0Braket")]
+fn test_verify_roundtrip(qasm_version: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_string(), 2, true);
+    circuit += Hadamard::new(0);
+    circuit += CNOT::new(0, 1);
+    circuit += PragmaRepeatedMeasurement::new("ro".to_string(), 100, None);
+
+    assert!(backend.verify_roundtrip(&circuit).unwrap());
+
+    // The global phase of a SingleQubitGate is not imported back
+    circuit += SingleQubitGate::new(
+        0,
+        1.0.into(),
+        0.0.into(),
+        0.0.into(),
+        0.0.into(),
+        0.5.into(),
+    );
+    assert!(!backend.verify_roundtrip(&circuit).unwrap());
+}
+
+/// Test that the custom gate definitions are written to separate files of the zip archive
+#[cfg(feature = "zip_archive")]
+#[test]
+fn test_circuit_to_qasm_archive() {
+    use std::io::Read;
+
+    let backend = Backend::new(None, Some("3.0".to_string())).unwrap();
+    let mut circuit = Circuit::new();
+    circuit += GateDefinition::new(
+        Circuit::new() + RotateX::new(0, "theta".into()),
+        "custom_rx".to_owned(),
+        vec![0],
+        vec!["theta".to_owned()],
+    );
+    circuit += GateDefinition::new(
+        Circuit::new() + PauliX::new(0) + CNOT::new(0, 1),
+        "custom_x".to_owned(),
+        vec![0, 1],
+        vec![],
+    );
+    circuit += CallDefinedGate::new("custom_rx".to_owned(), vec![0], vec![0.5.into()]);
+    circuit += CallDefinedGate::new("custom_x".to_owned(), vec![0, 1], vec![]);
+
+    backend
+        .circuit_to_qasm_archive(
+            &circuit,
+            temp_dir().as_path(),
+            Path::new("test_archive"),
+            true,
+        )
+        .unwrap();
+    let archive_path = temp_dir().join("test_archive.zip");
+    let mut archive = zip::ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["custom_rx.inc", "custom_x.inc", "test_archive.qasm"]
+    );
+
+    let mut main = String::new();
+    archive
+        .by_name("test_archive.qasm")
+        .unwrap()
+        .read_to_string(&mut main)
+        .unwrap();
+    // The gates used by a custom gate are defined before its include
+    assert!(main.contains(
+        "include \"custom_rx.inc\";\ngate x a { u3(pi,0,pi) a; }\ninclude \"custom_x.inc\";\n"
+    ));
+    assert!(!main.contains("gate custom_"));
+    let mut definition = String::new();
+    archive
+        .by_name("custom_x.inc")
+        .unwrap()
+        .read_to_string(&mut definition)
+        .unwrap();
+    assert!(definition.starts_with("gate custom_x qb_0,qb_1\n"));
+    fs::remove_file(&archive_path).unwrap();
+
+    // The archive is not overwritten without permission
+    backend
+        .circuit_to_qasm_archive(
+            &circuit,
+            temp_dir().as_path(),
+            Path::new("test_archive"),
+            false,
+        )
+        .unwrap();
+    assert!(backend
+        .circuit_to_qasm_archive(
+            &circuit,
+            temp_dir().as_path(),
+            Path::new("test_archive"),
+            false,
+        )
+        .is_err());
+    fs::remove_file(&archive_path).unwrap();
+
+    // An archive that can not be created returns an error instead of panicking
+    let missing_folder = temp_dir().join("missing_archive_folder");
+    assert!(backend
+        .circuit_to_qasm_archive(
+            &circuit,
+            missing_folder.as_path(),
+            Path::new("test_archive"),
+            true,
+        )
+        .is_err());
+}
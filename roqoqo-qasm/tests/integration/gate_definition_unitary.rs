@@ -0,0 +1,138 @@
+// Copyright © 2021-2023 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing that the gate definitions of roqoqo-qasm implement the unitary of the gates
+
+use std::convert::TryInto;
+
+use ndarray::{array, Array2};
+use num_complex::Complex64;
+use qoqo_calculator::Calculator;
+use roqoqo::operations::*;
+use roqoqo::Circuit;
+
+use roqoqo_qasm::{gate_definition, string_to_circuit, Qasm2Dialect, QasmVersion};
+use test_case::test_case;
+
+// Value the parameters of the parametric gates are set to, the complex parameters are chosen
+// with this absolute value and argument
+const PARAMETER_VALUE: f64 = 0.3;
+
+/// Parses the body of a two-qubit gate definition into a circuit acting on qubits 0 and 1.
+///
+/// The qubit arguments of the definition are mapped to the qubits in the order they are declared
+/// and the parameters of the definition are replaced by `PARAMETER_VALUE`.
+fn definition_circuit(definition: &str) -> Circuit {
+    let (signature, body) = definition
+        .trim_start_matches("gate ")
+        .trim_end_matches('}')
+        .split_once('{')
+        .unwrap();
+    let (name_and_parameters, arguments) = signature.trim().rsplit_once(' ').unwrap();
+    let parameters: Vec<&str> = match name_and_parameters.split_once('(') {
+        Some((_, parameters)) => parameters.trim_end_matches(')').split(',').collect(),
+        None => vec![],
+    };
+    let arguments: Vec<&str> = arguments.split(',').collect();
+
+    let mut qasm = String::from("OPENQASM 2.0;\n\nqreg q[2];\n\n");
+    for statement in body.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (gate, qubits) = statement.rsplit_once(' ').unwrap();
+        let gate = parameters.iter().fold(gate.to_string(), |gate, parameter| {
+            gate.replace(parameter, &PARAMETER_VALUE.to_string())
+        });
+        let qubits: Vec<String> = qubits
+            .split(',')
+            .map(|qubit| {
+                let index = arguments.iter().position(|arg| *arg == qubit).unwrap();
+                format!("q[{}]", index)
+            })
+            .collect();
+        qasm.push_str(&format!("{} {};\n", gate, qubits.join(",")));
+    }
+
+    // The parameters of the gates are parsed as expressions and evaluated here
+    let circuit = string_to_circuit(&qasm)
+        .unwrap()
+        .substitute_parameters(&Calculator::new())
+        .unwrap();
+    assert_eq!(circuit.len(), qasm.lines().skip(4).count());
+    circuit
+}
+
+/// Returns the two-qubit unitary of a gate, qubit 0 is the first factor of the tensor product.
+fn two_qubit_unitary(operation: &Operation) -> Array2<Complex64> {
+    let swap: Array2<Complex64> = array![
+        [1.0.into(), 0.0.into(), 0.0.into(), 0.0.into()],
+        [0.0.into(), 0.0.into(), 1.0.into(), 0.0.into()],
+        [0.0.into(), 1.0.into(), 0.0.into(), 0.0.into()],
+        [0.0.into(), 0.0.into(), 0.0.into(), 1.0.into()],
+    ];
+    let identity: Array2<Complex64> = Array2::eye(2);
+    if let Ok(gate) = TryInto::<SingleQubitGateOperation>::try_into(operation) {
+        let matrix = gate.unitary_matrix().unwrap();
+        return match gate.qubit() {
+            0 => kron(&matrix, &identity),
+            _ => kron(&identity, &matrix),
+        };
+    }
+    let gate: TwoQubitGateOperation = operation.try_into().unwrap();
+    let matrix = gate.unitary_matrix().unwrap();
+    match (gate.control(), gate.target()) {
+        (0, 1) => matrix,
+        _ => swap.dot(&matrix).dot(&swap),
+    }
+}
+
+/// Returns the Kronecker product of two single-qubit matrices.
+fn kron(a: &Array2<Complex64>, b: &Array2<Complex64>) -> Array2<Complex64> {
+    Array2::from_shape_fn((4, 4), |(row, column)| {
+        a[[row / 2, column / 2]] * b[[row % 2, column % 2]]
+    })
+}
+
+/// Checks that two unitaries are equal up to a global phase.
+fn equal_up_to_phase(a: &Array2<Complex64>, b: &Array2<Complex64>) -> bool {
+    let (index, _) = b
+        .indexed_iter()
+        .max_by(|(_, x), (_, y)| x.norm().total_cmp(&y.norm()))
+        .unwrap();
+    let phase = a[index] / b[index];
+    (phase.norm() - 1.0).abs() < 1e-10
+        && a.iter()
+            .zip(b.iter())
+            .all(|(x, y)| (x - phase * y).norm() < 1e-10)
+}
+
+/// Test that the decomposition of the gate definitions has the unitary of the gate
+#[test_case(Operation::from(ISwap::new(0, 1)); "ISwap")]
+#[test_case(Operation::from(FSwap::new(0, 1)); "FSwap")]
+#[test_case(Operation::from(XY::new(0, 1, PARAMETER_VALUE.into())); "XY")]
+#[test_case(Operation::from(Bogoliubov::new(0, 1, (PARAMETER_VALUE * PARAMETER_VALUE.cos()).into(), (PARAMETER_VALUE * PARAMETER_VALUE.sin()).into())); "Bogoliubov")]
+#[test_case(Operation::from(ComplexPMInteraction::new(0, 1, (PARAMETER_VALUE * PARAMETER_VALUE.cos()).into(), (PARAMETER_VALUE * PARAMETER_VALUE.sin()).into())); "ComplexPMInteraction")]
+fn test_gate_definition_unitary(operation: Operation) {
+    let definition =
+        gate_definition(&operation, QasmVersion::V2point0(Qasm2Dialect::Vanilla)).unwrap();
+    let circuit = definition_circuit(&definition);
+
+    let decomposed = circuit
+        .iter()
+        .fold(Array2::<Complex64>::eye(4), |unitary, op| {
+            two_qubit_unitary(op).dot(&unitary)
+        });
+    assert!(
+        equal_up_to_phase(&decomposed, &two_qubit_unitary(&operation)),
+        "The definition {} does not implement the unitary of {}",
+        definition,
+        operation.hqslang()
+    );
+}
@@ -18,9 +18,14 @@ use roqoqo::operations::*;
 use roqoqo::prelude::*;
 use roqoqo::Circuit;
 use roqoqo_qasm::Qasm2Dialect;
-use roqoqo_qasm::{call_circuit, call_operation, gate_definition, Qasm3Dialect, QasmVersion};
+use roqoqo_qasm::{
+    call_circuit, call_operation, call_operation_with_options, gate_definition,
+    gate_definition_with_options, operation_to_qasm, Qasm3Dialect, QasmConditionalValue,
+    QasmOptions, QasmVersion, VariableGatherer,
+};
 use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::str::FromStr;
 use test_case::test_case;
 
 fn tmp_create_map() -> HashMap<usize, usize> {
@@ -40,7 +45,6 @@ fn tmp_create_map() -> HashMap<usize, usize> {
 #[test_case(Operation::from(RotateX::new(0, CalculatorFloat::from(-PI))), "rx(-3.141592653589793e0) q[0];"; "RotateX")]
 #[test_case(Operation::from(RotateY::new(0, CalculatorFloat::from(-PI))), "ry(-3.141592653589793e0) q[0];"; "RotateY")]
 #[test_case(Operation::from(RotateZ::new(0, CalculatorFloat::from(-PI))), "rz(-3.141592653589793e0) q[0];"; "RotateZ")]
-#[test_case(Operation::from(InvSqrtPauliX::new(0)), "sxdg q[0];"; "InvSqrtPauliX")]
 #[test_case(Operation::from(Identity::new(0)), "id q[0];"; "Identity")]
 #[test_case(Operation::from(ControlledPauliY::new(0, 1)), "cy q[0],q[1];"; "ControlledPauliY")]
 #[test_case(Operation::from(ControlledPauliZ::new(0, 1)), "cz q[0],q[1];"; "ControlledPauliZ")]
@@ -61,14 +65,11 @@ fn tmp_create_map() -> HashMap<usize, usize> {
 #[test_case(Operation::from(ControlledRotateXY::new(0, 1, CalculatorFloat::from(0.3), CalculatorFloat::from(0.5))), "crxy(3e-1,5e-1) q[0],q[1];"; "ControlledRotateXY")]
 #[test_case(Operation::from(PhaseShiftedControlledZ::new(0, 1, CalculatorFloat::from(0.1))), "pscz(1e-1) q[0],q[1];"; "PhaseShiftedControlledZ")]
 #[test_case(Operation::from(PhaseShiftedControlledPhase::new(0, 1, CalculatorFloat::from(0.1), CalculatorFloat::from(0.2))), "pscp(1e-1,2e-1) q[0],q[1];"; "PhaseShiftedControlledPhase")]
-#[test_case(Operation::from(SingleQubitGate::new(0, CalculatorFloat::from(1.0), CalculatorFloat::from(0.0), CalculatorFloat::from(0.0), CalculatorFloat::from(0.0), CalculatorFloat::from(0.0))), "u3(0.000000000000000,0.000000000000000,-0.000000000000000) q[0];"; "SingleQubitGate")]
 #[test_case(Operation::from(PragmaActiveReset::new(0)), "reset q[0];"; "PragmaActiveReset")]
 #[test_case(Operation::from(MeasureQubit::new(0, "ro".to_string(), 0)), "measure q[0] -> ro[0];"; "MeasureQubit")]
-#[test_case(Operation::from(ControlledControlledPauliZ::new(0, 1, 2)), "ccz q[0],q[1],q[2];"; "ControlledControlledPauliZ")]
-#[test_case(Operation::from(ControlledControlledPhaseShift::new(0, 1, 2, 0.3.into())), "ccp(3e-1) q[0],q[1],q[2];"; "ControlledControlledPhaseShift")]
-#[test_case(Operation::from(Toffoli::new(0, 1, 2)), "ccx q[0],q[1],q[2];"; "Toffoli")]
 #[test_case(Operation::from(GateDefinition::new(Circuit::new(), "test_gate".to_owned(), vec![0,1], vec!["theta".to_owned()])), ""; "GateDefinition")]
 #[test_case(Operation::from(CallDefinedGate::new("gate_name".to_owned(), vec![0, 1], vec![CalculatorFloat::FRAC_PI_2])), "gate_name(1.5707963267948966e0) q[0],q[1];"; "CallDefinedGate")]
+#[test_case(Operation::from(CallDefinedGate::new("gate_name".to_owned(), vec![0, 1], vec![])), "gate_name q[0],q[1];"; "CallDefinedGate no parameters")]
 #[test_case(Operation::from(SqrtPauliY::new(0)), "sy q[0];"; "SqrtPauliY")]
 #[test_case(Operation::from(InvSqrtPauliY::new(0)), "sydg q[0];"; "InvSqrtPauliY")]
 #[test_case(Operation::from(EchoCrossResonance::new(0, 1)), "ecr q[0],q[1];"; "EchoCrossResonance")]
@@ -115,6 +116,38 @@ fn test_call_operation_identical_2_3_all(operation: Operation, converted: &str)
     );
 }
 
+/// Test that vanilla OpenQASM 3.0 emits SingleQubitGate as U with the global phase of the gate
+#[test_case(SingleQubitGate::new(0, 1.0.into(), 0.0.into(), 0.0.into(), 0.0.into(), 0.0.into()), "u3(0.000000000000000,0.000000000000000,-0.000000000000000) q[0];", "U(0.000000000000000,0.000000000000000,-0.000000000000000) q[0];"; "no global phase")]
+#[test_case(SingleQubitGate::new(0, 1.0.into(), 0.0.into(), 0.0.into(), 0.0.into(), 0.5.into()), "u3(0.000000000000000,0.000000000000000,-0.000000000000000) q[0];", "gphase(0.500000000000000);\nU(0.000000000000000,0.000000000000000,-0.000000000000000) q[0];"; "global phase")]
+#[test_case(SingleQubitGate::new(1, 0.0.into(), 1.0.into(), 0.0.into(), 0.0.into(), 0.0.into()), "u3(0.000000000000000,-1.570796326794897,-1.570796326794897) q[1];", "gphase(1.570796326794897);\nU(0.000000000000000,-1.570796326794897,-1.570796326794897) q[1];"; "phase of alpha")]
+fn test_single_qubit_gate_global_phase(
+    gate: SingleQubitGate,
+    converted_2: &str,
+    converted_3: &str,
+) {
+    let operation = Operation::from(gate);
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Braket),
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            converted_2
+        );
+    }
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        converted_3
+    );
+}
+
 /// Test that all operations return the correct String: 2.0 vs. 3.0 differences
 #[test_case(Operation::from(InputSymbolic::new("other".to_string(), 0.0)), "", "input float other;"; "InputSymbolic")]
 fn test_call_operation_different_2_3(operation: Operation, converted_2: &str, converted_3: &str) {
@@ -160,6 +193,71 @@ fn test_call_operation_different_2_3(operation: Operation, converted_2: &str, co
     );
 }
 
+/// Test the gate names and definitions of the Qiskit dialect
+#[test_case(Operation::from(SqrtPauliX::new(0)), "sx q[0];", ""; "SqrtPauliX")]
+#[test_case(Operation::from(InvSqrtPauliX::new(0)), "sxdg q[0];", "gate sxdg a { u1(pi/2) a; u2(0,pi) a; u1(pi/2) a; }"; "InvSqrtPauliX")]
+#[test_case(Operation::from(InvSGate::new(0)), "sdg q[0];", ""; "InvSGate")]
+#[test_case(Operation::from(InvTGate::new(0)), "tdg q[0];", ""; "InvTGate")]
+#[test_case(Operation::from(CNOT::new(0, 1)), "cx q[0],q[1];", ""; "CNOT")]
+#[test_case(Operation::from(PhaseShiftState1::new(0, 0.5.into())), "p(5e-1) q[0];", ""; "PhaseShiftState1")]
+#[test_case(Operation::from(ControlledPhaseShift::new(0, 1, 0.5.into())), "cp(5e-1) q[0],q[1];", ""; "ControlledPhaseShift")]
+#[test_case(Operation::from(ISwap::new(0, 1)), "iswap q[0],q[1];", "gate iswap a,b { rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }"; "ISwap")]
+fn test_qiskit_dialect(operation: Operation, converted: &str, definition: &str) {
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Qiskit);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted.to_string()
+    );
+    assert_eq!(
+        gate_definition(&operation, qasm_version).unwrap(),
+        definition.to_string()
+    );
+}
+
+/// Test the native gates of the IonQ dialect
+#[test_case(Operation::from(GPi::new(0, 0.5.into())), "gpi(5e-1) q[0];"; "GPi")]
+#[test_case(Operation::from(GPi2::new(0, 0.5.into())), "gpi2(5e-1) q[0];"; "GPi2")]
+#[test_case(Operation::from(MolmerSorensenXX::new(0, 1)), "ms(0,0,pi/2) q[0],q[1];"; "MolmerSorensenXX")]
+fn test_ionq_dialect(operation: Operation, converted: &str) {
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::IonQ);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted.to_string()
+    );
+}
+
+/// Test that the IonQ dialect rejects gates that are not native to IonQ hardware
+#[test_case(Operation::from(Hadamard::new(0)); "Hadamard")]
+#[test_case(Operation::from(CNOT::new(0, 1)); "CNOT")]
+#[test_case(Operation::from(RotateZ::new(0, 0.5.into())); "RotateZ")]
+fn test_ionq_dialect_error(operation: Operation) {
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::IonQ);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None),
+        Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "QasmBackend version 3.0 IonQ",
+            hqslang: operation.hqslang(),
+        })
+    );
+}
+
+/// Test that InvSqrtPauliX uses the inv @ modifier in vanilla OpenQASM 3.0 only
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla), "sxdg q[0];", "gate sxdg a { u1(pi/2) a; u2(0,pi) a; u1(pi/2) a; }"; "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla), "inv @ sx q[0];", "gate sx a { u1(-pi/2) a; u2(0,pi) a; u1(-pi/2) a; }"; "3.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Roqoqo), "sxdg q[0];", "gate sxdg a { u1(pi/2) a; u2(0,pi) a; u1(pi/2) a; }"; "3.0Roqoqo")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket), "sxdg q[0];", "gate sxdg a { u1(pi/2) a; u2(0,pi) a; u1(pi/2) a; }"; "3.0Braket")]
+fn test_inv_sqrt_pauli_x(qasm_version: QasmVersion, converted: &str, definition: &str) {
+    let operation = Operation::from(InvSqrtPauliX::new(0));
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted.to_string()
+    );
+    assert_eq!(
+        gate_definition(&operation, qasm_version).unwrap(),
+        definition.to_string()
+    );
+}
+
 /// Test that all operations return the correct String: 2.0 vs. 3.0 differences (Roqoqo dialect)
 #[test_case(Operation::from(PragmaStopDecompositionBlock::new(vec![0,1])), "", "pragma roqoqo PragmaStopDecompositionBlock [0, 1];"; "PragmaStopDecompositionBlock")]
 #[test_case(Operation::from(PragmaStopParallelBlock::new(vec![], CalculatorFloat::from(0.0))), "", "pragma roqoqo PragmaStopParallelBlock [] 0e0;"; "PragmaStopParallelBlock")]
@@ -169,7 +267,6 @@ fn test_call_operation_different_2_3(operation: Operation, converted_2: &str, co
 #[test_case(Operation::from(PragmaGetOccupationProbability::new("test".into(), None)), "", "pragma roqoqo PragmaGetOccupationProbability test ;"; "PragmaGetOccupationProbability")]
 #[test_case(Operation::from(PragmaGetPauliProduct::new(HashMap::new(), "test".into(), Circuit::new())), "", "pragma roqoqo PragmaGetPauliProduct {} test ;"; "PragmaGetPauliProduct")]
 #[test_case(Operation::from(PragmaGetStateVector::new("test".into(), None)), "", "pragma roqoqo PragmaGetStateVector test ;"; "PragmaGetStateVector")]
-#[test_case(Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 1, None)), "measure q -> ro;", "measure q -> ro;\npragma roqoqo PragmaSetNumberOfMeasurements 1 ro;"; "PragmaRepeatedMeasurement")]
 fn test_call_operation_different_2_3_roqoqo_dialect(
     operation: Operation,
     converted_2: &str,
@@ -224,6 +321,9 @@ fn test_call_operation_different_2_3_roqoqo_dialect(
 #[test_case(Operation::from(VariableMSXX::new(0, 1, CalculatorFloat::from(PI/2.0))), "rxx(1.5707963267948966e0) q[0],q[1];", "xx(1.5707963267948966e0) q[0],q[1];"; "VariableMSXX")]
 #[test_case(Operation::from(SqrtPauliX::new(0)), "sx q[0];", "v q[0];"; "SqrtPauliX")]
 #[test_case(Operation::from(PhaseShiftState1::new(0, CalculatorFloat::from(PI))), "p(3.141592653589793e0) q[0];", "phaseshift(3.141592653589793e0) q[0];"; "PhaseShiftState1")]
+#[test_case(Operation::from(ControlledControlledPhaseShift::new(0, 1, 2, 0.3.into())), "ccp(3e-1) q[0],q[1],q[2];", "phaseshift(7.5e-2) q[1];\ncnot q[1],q[2];\nphaseshift(-7.5e-2) q[2];\ncnot q[1],q[2];\nphaseshift(7.5e-2) q[2];\ncnot q[0],q[1];\nphaseshift(-7.5e-2) q[1];\ncnot q[1],q[2];\nphaseshift(7.5e-2) q[2];\ncnot q[1],q[2];\nphaseshift(-7.5e-2) q[2];\ncnot q[0],q[1];\nphaseshift(7.5e-2) q[0];\ncnot q[0],q[2];\nphaseshift(-7.5e-2) q[2];\ncnot q[0],q[2];\nphaseshift(7.5e-2) q[2];"; "ControlledControlledPhaseShift")]
+#[test_case(Operation::from(Toffoli::new(0, 1, 2)), "ccx q[0],q[1],q[2];", "ccnot q[0],q[1],q[2];"; "Toffoli")]
+#[test_case(Operation::from(ControlledControlledPauliZ::new(0, 1, 2)), "ccz q[0],q[1],q[2];", "h q[2];\nccnot q[0],q[1],q[2];\nh q[2];"; "ControlledControlledPauliZ")]
 fn test_call_operation_different_2_3_braket_dialect(
     operation: Operation,
     converted_2: &str,
@@ -279,7 +379,9 @@ fn test_call_operation_different_2_3_braket_dialect(
 #[test_case(Operation::from(DefinitionBit::new("ro".to_string(), 1, false)), "creg ro[1];", "bit[1] ro;", "bit[1] ro;"; "DefinitionBit")]
 #[test_case(Operation::from(DefinitionComplex::new("ro".to_string(), 1, true)), "creg ro[1];", "output float[1] ro_re;\noutput float[1] ro_im;", "float[1] ro_re;\nfloat[1] ro_im;"; "DefinitionComplex output")]
 #[test_case(Operation::from(DefinitionComplex::new("ro".to_string(), 1, false)), "creg ro[1];", "float[1] ro_re;\nfloat[1] ro_im;", "float[1] ro_re;\nfloat[1] ro_im;"; "DefinitionComplex")]
-#[test_case(Operation::from(PragmaGlobalPhase::new(CalculatorFloat::from(1.0))), "", "gphase 1e0;", ""; "PragmaGlobalPhase")]
+#[test_case(Operation::from(PragmaGlobalPhase::new(CalculatorFloat::from(1.0))), "", "gphase 1e0;", "gphase(1e0);"; "PragmaGlobalPhase")]
+#[test_case(Operation::from(InvSGate::new(0)), "sdg q[0];", "sdg q[0];", "si q[0];"; "InvSGate")]
+#[test_case(Operation::from(InvTGate::new(0)), "tdg q[0];", "tdg q[0];", "ti q[0];"; "InvTGate")]
 fn test_call_operation_different_braket_dialect(
     operation: Operation,
     converted_2: &str,
@@ -445,11 +547,10 @@ fn test_call_operation_error_2_3_braket_dialect(operation: Operation, converted_
 }
 
 /// Test that all operations return the correct error: 2.0 vs. 3.0 differences
-#[test_case(Operation::from(InputBit::new("other".to_string(), 0, false)), "other[0] = false;"; "InputBit")]
-fn test_call_operation_error_2_3(operation: Operation, converted_3: &str) {
-    let error = RoqoqoBackendError::OperationNotInBackend {
-        backend: "QASM",
-        hqslang: operation.hqslang(),
+#[test_case(Operation::from(InputBit::new("other".to_string(), 0, false)), "other[0] = false;", "InputBit requires OpenQASM 3.0"; "InputBit")]
+fn test_call_operation_error_2_3(operation: Operation, converted_3: &str, error_2: &str) {
+    let error = RoqoqoBackendError::GenericError {
+        msg: error_2.to_string(),
     };
 
     assert_eq!(
@@ -500,6 +601,8 @@ fn test_call_operation_error_2_3(operation: Operation, converted_3: &str) {
 #[test_case(Operation::from(Hadamard::new(0)), "gate h a { u2(0,pi) a; }"; "Hadamard")]
 #[test_case(Operation::from(SGate::new(0)), "gate s a { u1(pi/2) a; }"; "SGate")]
 #[test_case(Operation::from(TGate::new(0)), "gate t a { u1(pi/4) a; }"; "TGate")]
+#[test_case(Operation::from(InvSGate::new(0)), "gate sdg a { u1(-pi/2) a; }"; "InvSGate")]
+#[test_case(Operation::from(InvTGate::new(0)), "gate tdg a { u1(-pi/4) a; }"; "InvTGate")]
 #[test_case(Operation::from(PhaseShiftState1::new(0, CalculatorFloat::from(PI))), "gate p(lambda) q { U(0,0,lambda) q; }"; "PhaseShiftState1")]
 #[test_case(Operation::from(RotateX::new(0, CalculatorFloat::from(-PI))), "gate rx(theta) a { u3(theta,-pi/2,pi/2) a; }"; "RotateX")]
 #[test_case(Operation::from(RotateY::new(0, CalculatorFloat::from(-PI))), "gate ry(theta) a { u3(theta,0,0) a; }"; "RotateY")]
@@ -541,6 +644,7 @@ fn test_call_operation_error_2_3(operation: Operation, converted_3: &str) {
 #[test_case(Operation::from(ControlledControlledPhaseShift::new(0, 1, 2, 0.3.into())), "gate ccp(theta) a,b,c { U(0,0,theta/4) b; cx b,c; U(0,0,-theta/4) c; cx b,c; U(0,0,theta/4) c; cx a,b; U(0,0,-theta/4) b; cx b,c; U(0,0,theta/4) c; cx b,c; U(0,0,-theta/4) c; cx a,b; U(0,0,theta/4) a; cx a,c; U(0,0,-theta/4) c; cx a,c; U(0,0,theta/4) c; }"; "ControlledControlledPhaseShift")]
 #[test_case(Operation::from(Toffoli::new(0, 1, 2)), "gate ccx a,b,c { u2(0,pi) c; cx b,c; u1(-pi/4) c; cx a,c; u1(pi/4) c; cx b,c; u1(-pi/4) c; cx a,c; u1(pi/4) b; u1(pi/4) c; u2(0,pi) c; cx a,b; u1(pi/4) a; u1(-pi/4) b; cx a,b; }"; "Toffoli")]
 #[test_case(Operation::from(GateDefinition::new(vec![Operation::from(RotateX::new(0,CalculatorFloat::from("theta"))), Operation::from(RotateX::new(1,CalculatorFloat::from("pi")))].into_iter().collect(), "test_gate".to_owned(), vec![0,1], vec!["theta".to_owned()])), "gate test_gate(theta) qb_0,qb_1\n{\n    rx(theta) qb_0;\n    rx(pi) qb_1;\n}"; "GateDefinition")]
+#[test_case(Operation::from(GateDefinition::new(Circuit::new() + PauliX::new(0), "test_gate".to_owned(), vec![0], vec![])), "gate test_gate qb_0\n{\n    x qb_0;\n}"; "GateDefinition no parameters")]
 #[test_case(Operation::from(CallDefinedGate::new("gate_name".to_owned(), vec![0, 1], vec![CalculatorFloat::from(0.5)])), ""; "CallDefinedGate")]
 #[test_case(Operation::from(SqrtPauliY::new(0)), "gate sy a { u3(pi/2,0,0) a; }"; "SqrtPauliY")]
 #[test_case(Operation::from(InvSqrtPauliY::new(0)), "gate sydg a { u3(-pi/2,0,0) a; }"; "InvSqrtPauliY")]
@@ -552,8 +656,29 @@ fn test_gate_definition(operation: Operation, converted: &str) {
     )
 }
 
-#[test_case(Operation::from(GPi::new(0, 0.0.into())), "gate gpi(theta) a { u3(pi,-pi/2,pi/2) a; u1(2*theta) a; gphase pi/2; }"; "GPi")]
-#[test_case(Operation::from(GPi2::new(0, 0.0.into())), "gate gpi2(theta) a { u1(-theta) a; u3(pi/2,-pi/2,pi/2) a; u1(theta) a; }"; "GPi2")]
+/// Test that operation_to_qasm returns the gate definition together with the call
+#[test_case(Operation::from(Hadamard::new(0)), Some("gate h a { u2(0,pi) a; }"), "h q[0];"; "Hadamard")]
+#[test_case(Operation::from(PragmaSetNumberOfMeasurements::new(20, "ro".to_string())), None, ""; "PragmaSetNumberOfMeasurements")]
+fn test_operation_to_qasm(operation: Operation, definition: Option<&str>, converted: &str) {
+    assert_eq!(
+        operation_to_qasm(
+            &operation,
+            "q",
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla)
+        )
+        .unwrap(),
+        (definition.map(|x| x.to_string()), converted.to_string())
+    );
+}
+
+/// Test that the gates native to Braket are not defined while the other gates are
+#[test_case(Operation::from(GPi::new(0, 0.0.into())), ""; "GPi")]
+#[test_case(Operation::from(GPi2::new(0, 0.0.into())), ""; "GPi2")]
+#[test_case(Operation::from(RotateX::new(0, 0.0.into())), ""; "RotateX")]
+#[test_case(Operation::from(SqrtPauliX::new(0)), ""; "SqrtPauliX")]
+#[test_case(Operation::from(ISwap::new(0, 1)), ""; "ISwap")]
+#[test_case(Operation::from(Toffoli::new(0, 1, 2)), ""; "Toffoli")]
+#[test_case(Operation::from(FSwap::new(0, 1)), "gate fswap a,b { rz(-pi/2) a; rz(-pi/2) b; rx(pi/2) a; cx a,b; rx(-pi/2) a; ry(-pi/2) b; cx a,b; rx(-pi/2) a; }"; "FSwap")]
 fn test_gate_definition_braket(operation: Operation, converted: &str) {
     assert_eq!(
         gate_definition(&operation, QasmVersion::V3point0(Qasm3Dialect::Braket)).unwrap(),
@@ -562,7 +687,7 @@ fn test_gate_definition_braket(operation: Operation, converted: &str) {
 }
 
 /// Test that operations return the correct gate definition error
-#[test_case(Operation::from(Bogoliubov::new(0, 1, 0.1.into(), 0.2.into())); "Bogoliubov")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)); "ControlledSWAP")]
 #[test_case(Operation::from(GPi::new(0, 0.1.into())); "GPi")]
 #[test_case(Operation::from(GPi2::new(0, 0.2.into())); "GPi2")]
 fn test_gate_definition_error(operation: Operation) {
@@ -576,6 +701,445 @@ fn test_gate_definition_error(operation: Operation) {
     )
 }
 
+/// Test that the built-in CX option only changes the OpenQASM 2.0 output
+#[test]
+fn test_builtin_cnot_option() {
+    let options = QasmOptions {
+        builtin_cnot: true,
+        ..Default::default()
+    };
+    let cnot = Operation::from(CNOT::new(0, 1));
+    assert_eq!(
+        call_operation_with_options(
+            &cnot,
+            "q",
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+            &mut None,
+            &options
+        )
+        .unwrap(),
+        "CX q[0],q[1];".to_string()
+    );
+    assert_eq!(
+        call_operation_with_options(
+            &cnot,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None,
+            &options
+        )
+        .unwrap(),
+        "cx q[0],q[1];".to_string()
+    );
+    assert_eq!(
+        gate_definition_with_options(
+            &Operation::from(SWAP::new(0, 1)),
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+            &options
+        )
+        .unwrap(),
+        "gate swap a,b { CX a,b; CX b,a; CX a,b; }".to_string()
+    );
+    assert_eq!(
+        gate_definition_with_options(
+            &Operation::from(Toffoli::new(0, 1, 2)),
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+            &options
+        )
+        .unwrap(),
+        "gate ccx a,b,c { u2(0,pi) c; CX b,c; u1(-pi/4) c; CX a,c; u1(pi/4) c; CX b,c; u1(-pi/4) c; CX a,c; u1(pi/4) b; u1(pi/4) c; u2(0,pi) c; CX a,b; u1(pi/4) a; u1(-pi/4) b; CX a,b; }".to_string()
+    );
+}
+
+/// Test that the ctrl @ modifier option only changes the vanilla OpenQASM 3.0 output
+#[test_case(Operation::from(ControlledPauliY::new(0, 1)), "ctrl @ y q[0],q[1];", "cy q[0],q[1];", "gate y a { u3(pi,pi/2,pi/2) a; }"; "ControlledPauliY")]
+#[test_case(Operation::from(ControlledPauliZ::new(0, 1)), "ctrl @ z q[0],q[1];", "cz q[0],q[1];", "gate z a { u1(pi) a; }"; "ControlledPauliZ")]
+#[test_case(Operation::from(ControlledPhaseShift::new(0, 1, 0.5.into())), "ctrl @ p(5e-1) q[0],q[1];", "cp(5e-1) q[0],q[1];", "gate p(lambda) q { U(0,0,lambda) q; }"; "ControlledPhaseShift")]
+fn test_ctrl_modifier_option(
+    operation: Operation,
+    converted_modifier: &str,
+    converted: &str,
+    definition_modifier: &str,
+) {
+    let options = QasmOptions {
+        ctrl_modifier: true,
+        ..Default::default()
+    };
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Vanilla);
+    assert_eq!(
+        call_operation_with_options(&operation, "q", qasm_version, &mut None, &options).unwrap(),
+        converted_modifier
+    );
+    assert_eq!(
+        gate_definition_with_options(&operation, qasm_version, &options).unwrap(),
+        definition_modifier
+    );
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+    ] {
+        assert_eq!(
+            call_operation_with_options(&operation, "q", qasm_version, &mut None, &options)
+                .unwrap(),
+            converted
+        );
+        assert_eq!(
+            gate_definition_with_options(&operation, qasm_version, &options).unwrap(),
+            gate_definition(&operation, qasm_version).unwrap()
+        );
+    }
+}
+
+/// Test the translation of MultiQubitZZ
+#[test_case(vec![0, 1], "rzz_multi_2(5e-1) q[0],q[1];", "rzz(5e-1) q[0],q[1];", "zz(5e-1) q[0],q[1];"; "2 qubits")]
+#[test_case(vec![0, 1, 2], "rzz_multi_3(5e-1) q[0],q[1],q[2];", "cx q[0],q[1];\nrzz(5e-1) q[1],q[2];\ncx q[0],q[1];", "cnot q[0],q[1];\nzz(5e-1) q[1],q[2];\ncnot q[0],q[1];"; "3 qubits")]
+#[test_case(vec![3, 0, 2, 1], "rzz_multi_4(5e-1) q[3],q[0],q[2],q[1];", "cx q[3],q[0];\ncx q[0],q[2];\nrzz(5e-1) q[2],q[1];\ncx q[0],q[2];\ncx q[3],q[0];", "cnot q[3],q[0];\ncnot q[0],q[2];\nzz(5e-1) q[2],q[1];\ncnot q[0],q[2];\ncnot q[3],q[0];"; "4 qubits")]
+fn test_multi_qubit_zz(
+    qubits: Vec<usize>,
+    converted_3: &str,
+    converted_2: &str,
+    converted_braket: &str,
+) {
+    let number_qubits = qubits.len();
+    let operation = Operation::from(MultiQubitZZ::new(qubits, 0.5.into()));
+    for qasm_version in [
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            converted_3
+        );
+        let definition = gate_definition(&operation, qasm_version).unwrap();
+        assert!(definition.starts_with(&format!("gate rzz_multi_{}(theta) a0,", number_qubits)));
+        assert_eq!(definition.matches("cx ").count(), 2 * (number_qubits - 1));
+        assert_eq!(definition.matches("rz(theta)").count(), 1);
+    }
+    let qasm_version = QasmVersion::V2point0(Qasm2Dialect::Vanilla);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted_2
+    );
+    assert_eq!(
+        gate_definition(&operation, qasm_version).unwrap(),
+        "gate rzz(theta) a,b { cx a,b; rz(theta) b; cx a,b; }"
+    );
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Braket),
+            &mut None
+        )
+        .unwrap(),
+        converted_braket
+    );
+}
+
+/// Test the translation of MultiQubitMS
+#[test_case(
+    vec![0, 1, 2],
+    "rxx_multi_3(5e-1) q[0],q[1],q[2];",
+    "gate rxx_multi_3(theta) a0,a1,a2 { u3(pi/2,0,pi) a0; u3(pi/2,0,pi) a1; u3(pi/2,0,pi) a2; cx a0,a1; cx a1,a2; rz(theta) a2; cx a1,a2; cx a0,a1; u3(pi/2,0,pi) a0; u3(pi/2,0,pi) a1; u3(pi/2,0,pi) a2; }",
+    "u2(0,pi) q[0];\nu2(0,pi) q[1];\nu2(0,pi) q[2];\ncx q[0],q[1];\ncx q[1],q[2];\nrz(5e-1) q[2];\ncx q[1],q[2];\ncx q[0],q[1];\nu2(0,pi) q[0];\nu2(0,pi) q[1];\nu2(0,pi) q[2];",
+    "h q[0];\nh q[1];\nh q[2];\ncnot q[0],q[1];\ncnot q[1],q[2];\nrz(5e-1) q[2];\ncnot q[1],q[2];\ncnot q[0],q[1];\nh q[0];\nh q[1];\nh q[2];";
+    "3 qubits"
+)]
+#[test_case(
+    vec![3, 0, 2, 1],
+    "rxx_multi_4(5e-1) q[3],q[0],q[2],q[1];",
+    "gate rxx_multi_4(theta) a0,a1,a2,a3 { u3(pi/2,0,pi) a0; u3(pi/2,0,pi) a1; u3(pi/2,0,pi) a2; u3(pi/2,0,pi) a3; cx a0,a1; cx a1,a2; cx a2,a3; rz(theta) a3; cx a2,a3; cx a1,a2; cx a0,a1; u3(pi/2,0,pi) a0; u3(pi/2,0,pi) a1; u3(pi/2,0,pi) a2; u3(pi/2,0,pi) a3; }",
+    "u2(0,pi) q[3];\nu2(0,pi) q[0];\nu2(0,pi) q[2];\nu2(0,pi) q[1];\ncx q[3],q[0];\ncx q[0],q[2];\ncx q[2],q[1];\nrz(5e-1) q[1];\ncx q[2],q[1];\ncx q[0],q[2];\ncx q[3],q[0];\nu2(0,pi) q[3];\nu2(0,pi) q[0];\nu2(0,pi) q[2];\nu2(0,pi) q[1];",
+    "h q[3];\nh q[0];\nh q[2];\nh q[1];\ncnot q[3],q[0];\ncnot q[0],q[2];\ncnot q[2],q[1];\nrz(5e-1) q[1];\ncnot q[2],q[1];\ncnot q[0],q[2];\ncnot q[3],q[0];\nh q[3];\nh q[0];\nh q[2];\nh q[1];";
+    "4 qubits"
+)]
+fn test_multi_qubit_ms(
+    qubits: Vec<usize>,
+    converted_3: &str,
+    definition_3: &str,
+    converted_2: &str,
+    converted_braket: &str,
+) {
+    let operation = Operation::from(MultiQubitMS::new(qubits, 0.5.into()));
+    for qasm_version in [
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            converted_3
+        );
+        assert_eq!(
+            gate_definition(&operation, qasm_version).unwrap(),
+            definition_3
+        );
+    }
+    let qasm_version = QasmVersion::V2point0(Qasm2Dialect::Vanilla);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted_2
+    );
+    assert_eq!(gate_definition(&operation, qasm_version).unwrap(), "");
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Braket);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted_braket
+    );
+    assert_eq!(gate_definition(&operation, qasm_version).unwrap(), "");
+}
+
+/// Test that the symbolic angle of MultiQubitMS is gathered as an input
+#[test]
+fn test_multi_qubit_ms_symbolic() {
+    let mut variable_gatherer = VariableGatherer::new();
+    let operation = Operation::from(MultiQubitMS::new(vec![0, 1, 2], "theta".into()));
+    call_operation(
+        &operation,
+        "q",
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+        &mut Some(&mut variable_gatherer),
+    )
+    .unwrap();
+    assert!(variable_gatherer.variables.contains("theta"));
+}
+
+/// Test the translation of Bogoliubov
+#[test]
+fn test_bogoliubov() {
+    let operation = Operation::from(Bogoliubov::new(0, 1, 0.3.into(), 0.4.into()));
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Roqoqo);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        "bogoliubov(3e-1,4e-1) q[0],q[1];"
+    );
+    assert_eq!(
+        gate_definition(&operation, qasm_version).unwrap(),
+        "gate bogoliubov(delta_real,delta_imag) a,b { rz(atan2(delta_imag,delta_real)) b; u3(pi,0,pi) b; rx(pi/2) a; cx a,b; rx(-sqrt(delta_real^2+delta_imag^2)) a; ry(-sqrt(delta_real^2+delta_imag^2)) b; cx a,b; rx(-pi/2) a; u3(pi,0,pi) b; rz(-atan2(delta_imag,delta_real)) b; }"
+    );
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            "bogoliubov(5e-1,9.272952180016123e-1) q[0],q[1];"
+        );
+        assert_eq!(
+            gate_definition(&operation, qasm_version).unwrap(),
+            "gate bogoliubov(delta_abs,delta_arg) a,b { rz(delta_arg) b; u3(pi,0,pi) b; rx(pi/2) a; cx a,b; rx(-delta_abs) a; ry(-delta_abs) b; cx a,b; rx(-pi/2) a; u3(pi,0,pi) b; rz(-delta_arg) b; }"
+        );
+    }
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Braket),
+            &mut None
+        )
+        .unwrap(),
+        "rz(9.272952180016123e-1) q[1];\nx q[1];\nrx(pi/2) q[0];\ncnot q[0],q[1];\nrx(-5e-1) q[0];\nry(-5e-1) q[1];\ncnot q[0],q[1];\nrx(-pi/2) q[0];\nx q[1];\nrz(-9.272952180016123e-1) q[1];"
+    );
+}
+
+/// Test that the symbolic parameters of Bogoliubov are gathered as inputs in the Roqoqo dialect
+#[test]
+fn test_bogoliubov_symbolic() {
+    let mut variable_gatherer = VariableGatherer::new_permissive();
+    let operation = Operation::from(Bogoliubov::new(0, 1, "delta_re".into(), "delta_im".into()));
+    call_operation(
+        &operation,
+        "q",
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+        &mut Some(&mut variable_gatherer),
+    )
+    .unwrap();
+    assert!(variable_gatherer.variables.contains("delta_re"));
+    assert!(variable_gatherer.variables.contains("delta_im"));
+}
+
+/// Test that the symbolic parameters of Bogoliubov are rejected outside of the Roqoqo dialect
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla); "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla); "3.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket); "3.0Braket")]
+fn test_bogoliubov_symbolic_error(qasm_version: QasmVersion) {
+    let operation = Operation::from(Bogoliubov::new(0, 1, "delta_re".into(), 0.4.into()));
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None),
+        Err(RoqoqoBackendError::GenericError {
+            msg: format!("The symbolic complex parameter (delta_re, 4e-1) of Bogoliubov is only supported by the Roqoqo dialect, its absolute value and argument can not be expressed in {:?}", qasm_version)
+        })
+    );
+}
+
+/// Test the translation of ComplexPMInteraction
+#[test]
+fn test_complex_pm_interaction() {
+    let operation = Operation::from(ComplexPMInteraction::new(0, 1, 0.3.into(), 0.4.into()));
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Roqoqo);
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        "cpmint(3e-1,4e-1) q[0],q[1];"
+    );
+    assert_eq!(
+        gate_definition(&operation, qasm_version).unwrap(),
+        "gate cpmint(t_real,t_imag) a,b { rz(atan2(t_imag,t_real)) b; rx(pi/2) a; cx a,b; rx(sqrt(t_real^2+t_imag^2)) a; ry(sqrt(t_real^2+t_imag^2)) b; cx a,b; rx(-pi/2) a; rz(-atan2(t_imag,t_real)) b; }"
+    );
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            "cpmint(5e-1,9.272952180016123e-1) q[0],q[1];"
+        );
+        assert_eq!(
+            gate_definition(&operation, qasm_version).unwrap(),
+            "gate cpmint(t_abs,t_arg) a,b { rz(t_arg) b; rx(pi/2) a; cx a,b; rx(t_abs) a; ry(t_abs) b; cx a,b; rx(-pi/2) a; rz(-t_arg) b; }"
+        );
+    }
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Braket),
+            &mut None
+        )
+        .unwrap(),
+        "rz(9.272952180016123e-1) q[1];\nrx(pi/2) q[0];\ncnot q[0],q[1];\nrx(5e-1) q[0];\nry(5e-1) q[1];\ncnot q[0],q[1];\nrx(-pi/2) q[0];\nrz(-9.272952180016123e-1) q[1];"
+    );
+
+    let mut variable_gatherer = VariableGatherer::new_permissive();
+    let operation = Operation::from(ComplexPMInteraction::new(
+        0,
+        1,
+        "t_re".into(),
+        "t_im".into(),
+    ));
+    call_operation(
+        &operation,
+        "q",
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+        &mut Some(&mut variable_gatherer),
+    )
+    .unwrap();
+    assert!(variable_gatherer.variables.contains("t_re"));
+    assert!(variable_gatherer.variables.contains("t_im"));
+
+    // The absolute value and argument of symbolic parameters need the Roqoqo dialect
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        ),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "The symbolic complex parameter (t_re, t_im) of ComplexPMInteraction is only supported by the Roqoqo dialect, its absolute value and argument can not be expressed in V3point0(Vanilla)".to_string()
+        })
+    );
+}
+
+/// Test that MultiQubitZZ on a single qubit returns an error
+#[test]
+fn test_multi_qubit_zz_error() {
+    let operation = Operation::from(MultiQubitZZ::new(vec![0], 0.5.into()));
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+            &mut None
+        ),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "MultiQubitZZ needs at least 2 qubits to be translated to rzz gates, got 1"
+                .to_string()
+        })
+    );
+}
+
+/// Test that the JSON serialized Roqoqo dialect pragmas can be deserialized back
+#[test_case(Operation::from(PragmaDamping::new(0, 1.0.into(), 1.5.into())); "PragmaDamping")]
+#[test_case(Operation::from(PragmaStopParallelBlock::new(vec![0, 1], 0.5.into())); "PragmaStopParallelBlock")]
+#[test_case(Operation::from(PragmaStartDecompositionBlock::new(vec![0, 1], HashMap::from([(0, 1), (1, 0)]))); "PragmaStartDecompositionBlock")]
+#[test_case(Operation::from(PragmaStopDecompositionBlock::new(vec![0, 1])); "PragmaStopDecompositionBlock")]
+#[test_case(Operation::from(PragmaOverrotation::new("RotateX".to_string(), vec![0], 0.1, 0.2)); "PragmaOverrotation")]
+#[test_case(Operation::from(PragmaSleep::new(vec![0, 2], "time".into())); "PragmaSleep")]
+#[test_case(Operation::from(PragmaGetPauliProduct::new(HashMap::from([(0, 1)]), "ro".to_string(), Circuit::new())); "PragmaGetPauliProduct")]
+#[test_case(Operation::from(PragmaLoop::new(3.0.into(), Circuit::new() + PauliX::new(0))); "PragmaLoop")]
+#[test_case(Operation::from(PragmaSetNumberOfMeasurements::new(20, "ro".to_string())); "PragmaSetNumberOfMeasurements")]
+fn test_json_pragmas_roundtrip(operation: Operation) {
+    let options = QasmOptions {
+        json_pragmas: true,
+        ..Default::default()
+    };
+    let line = call_operation_with_options(
+        &operation,
+        "q",
+        QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+        &mut None,
+        &options,
+    )
+    .unwrap();
+
+    let prefix = format!("pragma roqoqo {} ", operation.hqslang());
+    let json = line
+        .strip_prefix(&prefix)
+        .unwrap()
+        .strip_suffix(';')
+        .unwrap();
+    let deserialized: Operation =
+        serde_json::from_str(&format!("{{\"{}\":{}}}", operation.hqslang(), json)).unwrap();
+    assert_eq!(deserialized, operation);
+
+    // Other dialects are not affected
+    assert_eq!(
+        call_operation_with_options(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None,
+            &options,
+        ),
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+    );
+}
+
+/// Test the JSON serialization of the Roqoqo dialect pragmas
+#[test]
+fn test_json_pragmas() {
+    let options = QasmOptions {
+        json_pragmas: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        call_operation_with_options(
+            &Operation::from(PragmaDamping::new(0, 1.0.into(), 1.5.into())),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+            &mut None,
+            &options,
+        )
+        .unwrap(),
+        "pragma roqoqo PragmaDamping {\"qubit\":0,\"gate_time\":1.0,\"rate\":1.5};"
+    );
+    assert_eq!(
+        call_operation_with_options(
+            &Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 20, None)),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+            &mut None,
+            &options,
+        )
+        .unwrap(),
+        "measure q -> ro;\npragma roqoqo PragmaSetNumberOfMeasurements {\"number_measurements\":20,\"readout\":\"ro\"};"
+    );
+}
+
 /// Test PragmaConditional correct behaviour
 #[test]
 fn test_pragma_conditional() {
@@ -610,7 +1174,7 @@ fn test_pragma_conditional() {
         .unwrap(),
         data_2
     );
-    let data_3 = "if(c[0]==1) {\nh q[0];x q[0];}";
+    let data_3 = "if(c[0]==1) {\n    h q[0];\n    x q[0];\n}";
     assert_eq!(
         call_operation(
             &Operation::from(pcond.clone()),
@@ -643,11 +1207,11 @@ fn test_pragma_conditional() {
     );
 
     let mut break_circuit = Circuit::new();
-    break_circuit += Bogoliubov::new(0, 1, 0.1.into(), 0.2.into());
+    break_circuit += ControlledSWAP::new(0, 1, 2);
     let pcond = PragmaConditional::new("c".to_string(), 0, break_circuit);
     let error = RoqoqoBackendError::OperationNotInBackend {
         backend: "QASM",
-        hqslang: "Bogoliubov",
+        hqslang: "ControlledSWAP",
     };
     assert_eq!(
         call_operation(
@@ -660,6 +1224,198 @@ fn test_pragma_conditional() {
     );
 }
 
+/// Test that nested conditionals are emitted as indented if blocks in OpenQASM 3.0
+#[test]
+fn test_pragma_conditional_nested_blocks() {
+    let inner = PragmaConditional::new("c".to_string(), 1, Circuit::new() + PauliX::new(1));
+    let outer = PragmaConditional::new(
+        "c".to_string(),
+        0,
+        Circuit::new()
+            + Hadamard::new(0)
+            + PragmaSetNumberOfMeasurements::new(1, "c".to_string())
+            + inner,
+    );
+    assert_eq!(
+        call_operation(
+            &Operation::from(outer),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        "if(c[0]==1) {\n    h q[0];\n    if(c[1]==1) {\n        x q[1];\n    }\n}"
+    );
+}
+
+/// Test that every gate of a multi-line translation is conditioned in OpenQASM 2.0
+#[test_case(Operation::from(MultiQubitZZ::new(vec![0, 1, 2], 0.4.into())), "if(c[0]==1) cx q[0],q[1];\nif(c[0]==1) rzz(4e-1) q[1],q[2];\nif(c[0]==1) cx q[0],q[1];"; "MultiQubitZZ")]
+#[test_case(Operation::from(MultiQubitMS::new(vec![0, 1, 2], 0.4.into())), "if(c[0]==1) u2(0,pi) q[0];\nif(c[0]==1) u2(0,pi) q[1];\nif(c[0]==1) u2(0,pi) q[2];\nif(c[0]==1) cx q[0],q[1];\nif(c[0]==1) cx q[1],q[2];\nif(c[0]==1) rz(4e-1) q[2];\nif(c[0]==1) cx q[1],q[2];\nif(c[0]==1) cx q[0],q[1];\nif(c[0]==1) u2(0,pi) q[0];\nif(c[0]==1) u2(0,pi) q[1];\nif(c[0]==1) u2(0,pi) q[2];"; "MultiQubitMS")]
+fn test_pragma_conditional_multi_line_body(operation: Operation, converted: &str) {
+    let pcond = PragmaConditional::new("c".to_string(), 0, Circuit::new() + operation);
+    assert_eq!(
+        call_operation(
+            &Operation::from(pcond),
+            "q",
+            QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        converted
+    );
+}
+
+/// Test the value the PragmaConditional condition bit is compared against
+#[test_case(QasmConditionalValue::One, "2.0", "if(c[1]==1) h q[0];\nif(c[1]==1) x q[0];"; "one_2_0")]
+#[test_case(QasmConditionalValue::Zero, "2.0", "if(c[1]==0) h q[0];\nif(c[1]==0) x q[0];"; "zero_2_0")]
+#[test_case(QasmConditionalValue::One, "3.0", "if(c[1]==1) {\n    h q[0];\n    x q[0];\n}"; "one_3_0")]
+#[test_case(QasmConditionalValue::Zero, "3.0", "if(c[1]==0) {\n    h q[0];\n    x q[0];\n}"; "zero_3_0")]
+fn test_pragma_conditional_value(
+    conditional_value: QasmConditionalValue,
+    qasm_version: &str,
+    converted: &str,
+) {
+    let pcond = PragmaConditional::new(
+        "c".to_string(),
+        1,
+        Circuit::new() + Hadamard::new(0) + PauliX::new(0),
+    );
+    let options = QasmOptions {
+        conditional_value,
+        ..Default::default()
+    };
+    assert_eq!(
+        call_operation_with_options(
+            &Operation::from(pcond),
+            "q",
+            QasmVersion::from_str(qasm_version).unwrap(),
+            &mut None,
+            &options
+        )
+        .unwrap(),
+        converted
+    );
+}
+
+/// Test PragmaControlledCircuit correct behaviour
+#[test]
+fn test_pragma_controlled_circuit() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(1);
+    circuit += CNOT::new(1, 2);
+    circuit += RotateZ::new(2, 0.5.into());
+
+    let pcontrolled = PragmaControlledCircuit::new(0, circuit.clone());
+    let data_3 = "ctrl @ h q[0],q[1];\nctrl @ cx q[0],q[1],q[2];\nctrl @ rz(5e-1) q[0],q[2];";
+    assert_eq!(
+        call_operation(
+            &Operation::from(pcontrolled.clone()),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        data_3
+    );
+    let data_3_roqoqo = "pragma roqoqo PragmaControlledCircuit 0 Hadamard(Hadamard { qubit: 1 })\nCNOT(CNOT { control: 1, target: 2 })\nRotateZ(RotateZ { qubit: 2, theta: Float(0.5) })\n;";
+    assert_eq!(
+        call_operation(
+            &Operation::from(pcontrolled.clone()),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Roqoqo),
+            &mut None
+        )
+        .unwrap(),
+        data_3_roqoqo
+    );
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Braket),
+    ] {
+        assert_eq!(
+            call_operation(
+                &Operation::from(pcontrolled.clone()),
+                "q",
+                qasm_version,
+                &mut None
+            ),
+            Err(RoqoqoBackendError::OperationNotInBackend {
+                backend: "QASM",
+                hqslang: "PragmaControlledCircuit",
+            })
+        );
+    }
+
+    let mut break_circuit = circuit;
+    break_circuit += MeasureQubit::new(1, "ro".to_string(), 0);
+    let pcontrolled = PragmaControlledCircuit::new(0, break_circuit);
+    assert_eq!(
+        call_operation(
+            &Operation::from(pcontrolled),
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        ),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "The ctrl @ modifier can not be applied to MeasureQubit inside PragmaControlledCircuit"
+                .to_string()
+        })
+    );
+}
+
+/// Test that a PragmaLoop without repetitions is not emitted, except in the Roqoqo dialect
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla), ""; "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla), ""; "3.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket), ""; "3.0Braket")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Roqoqo), "pragma roqoqo PragmaLoop 0e0 Hadamard(Hadamard { qubit: 0 })\n;"; "3.0Roqoqo")]
+fn test_pragma_loop_zero_repetitions(qasm_version: QasmVersion, converted: &str) {
+    let operation = Operation::from(PragmaLoop::new(
+        0.0.into(),
+        Circuit::new() + Hadamard::new(0),
+    ));
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted
+    );
+}
+
+/// Test that a PragmaLoop with a single repetition loops over a single value
+#[test]
+fn test_pragma_loop_one_repetition() {
+    let operation = Operation::from(PragmaLoop::new(
+        1.0.into(),
+        Circuit::new() + Hadamard::new(0),
+    ));
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        "for uint i in [0:0] {\n    h q[0];\n}"
+    );
+}
+
+/// Test that a PragmaLoop with a negative number of repetitions is rejected
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla); "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla); "3.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket); "3.0Braket")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Roqoqo); "3.0Roqoqo")]
+fn test_pragma_loop_negative_repetitions(qasm_version: QasmVersion) {
+    let operation = Operation::from(PragmaLoop::new(
+        (-2.0).into(),
+        Circuit::new() + Hadamard::new(0),
+    ));
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Used PragmaLoop with a negative number of repetitions -2e0, the number of repetitions must not be negative".to_string()
+        })
+    );
+}
+
 /// Test PragmaLoop correct behaviour
 #[test]
 fn test_pragma_loop() {
@@ -716,7 +1472,7 @@ fn test_pragma_loop() {
     );
 
     let pcond = PragmaLoop::new(2.0.into(), circuit);
-    let data_3 = "for uint i in [0:2] {\n    h q[0];\n}";
+    let data_3 = "for uint i in [0:1] {\n    h q[0];\n}";
     assert_eq!(
         call_operation(
             &Operation::from(pcond),
@@ -729,11 +1485,11 @@ fn test_pragma_loop() {
     );
 
     let mut break_circuit = Circuit::new();
-    break_circuit += Bogoliubov::new(0, 1, 0.1.into(), 0.2.into());
+    break_circuit += ControlledSWAP::new(0, 1, 2);
     let pcond = PragmaLoop::new(2.0.into(), break_circuit.clone());
     let error = RoqoqoBackendError::OperationNotInBackend {
         backend: "QASM",
-        hqslang: "Bogoliubov",
+        hqslang: "ControlledSWAP",
     };
     assert_eq!(
         call_operation(
@@ -807,6 +1563,63 @@ fn test_pragma_sleep() {
     );
 }
 
+/// Test that PragmaRepeatedMeasurement is annotated with the probability result type in the Braket dialect
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla), None, "measure q -> ro;"; "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla), None, "measure q -> ro;"; "3.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Roqoqo), None, "measure q -> ro;\npragma roqoqo PragmaSetNumberOfMeasurements 1 ro;"; "3.0Roqoqo")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket), None, "measure q -> ro;\n#pragma braket result probability"; "3.0Braket")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Braket), Some(HashMap::from([(2, 0)])), "measure q[2] -> ro[0];\n#pragma braket result probability q[2]"; "3.0Braket mapping")]
+fn test_pragma_repeated_measurement_result(
+    qasm_version: QasmVersion,
+    qubit_mapping: Option<HashMap<usize, usize>>,
+    converted: &str,
+) {
+    let operation = Operation::from(PragmaRepeatedMeasurement::new(
+        "ro".to_string(),
+        1,
+        qubit_mapping,
+    ));
+    assert_eq!(
+        call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+        converted.to_string()
+    );
+}
+
+/// Test that the Roqoqo dialect does not silently drop any allowed operation
+#[test_case(Operation::from(PragmaGetDensityMatrix::new("ro".into(), None)); "PragmaGetDensityMatrix")]
+#[test_case(Operation::from(PragmaGetOccupationProbability::new("ro".into(), None)); "PragmaGetOccupationProbability")]
+#[test_case(Operation::from(PragmaGetPauliProduct::new(HashMap::new(), "ro".into(), Circuit::new())); "PragmaGetPauliProduct")]
+#[test_case(Operation::from(PragmaGetStateVector::new("ro".into(), None)); "PragmaGetStateVector")]
+#[test_case(Operation::from(PragmaSleep::new(vec![0], 1.0.into())); "PragmaSleep")]
+#[test_case(Operation::from(PragmaSetNumberOfMeasurements::new(1, "ro".into())); "PragmaSetNumberOfMeasurements")]
+#[test_case(Operation::from(PragmaStartDecompositionBlock::new(vec![0], HashMap::new())); "PragmaStartDecompositionBlock")]
+#[test_case(Operation::from(PragmaStopDecompositionBlock::new(vec![0])); "PragmaStopDecompositionBlock")]
+#[test_case(Operation::from(PragmaStopParallelBlock::new(vec![0], 0.0.into())); "PragmaStopParallelBlock")]
+#[test_case(Operation::from(InputSymbolic::new("x".into(), 1.0)); "InputSymbolic")]
+#[test_case(Operation::from(PragmaGlobalPhase::new(1.0.into())); "PragmaGlobalPhase")]
+fn test_roqoqo_dialect_keeps_allowed_operations(operation: Operation) {
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Roqoqo);
+    assert!(!call_operation(&operation, "q", qasm_version, &mut None)
+        .unwrap()
+        .is_empty());
+}
+
+/// Test that the GateDefinition is emitted as a gate definition instead of a pragma in the Roqoqo dialect
+#[test]
+fn test_roqoqo_dialect_gate_definition() {
+    let qasm_version = QasmVersion::V3point0(Qasm3Dialect::Roqoqo);
+    let gate_definition = Operation::from(GateDefinition::new(
+        Circuit::new() + PauliX::new(0),
+        "custom".to_owned(),
+        vec![0],
+        vec![],
+    ));
+    assert_eq!(
+        call_operation(&gate_definition, "q", qasm_version, &mut None).unwrap(),
+        ""
+    );
+}
+
 /// Test PragmaRepeatedMeasurement correct behaviour
 #[test]
 fn test_pragma_repeated_operation_mapping() {
@@ -854,12 +1667,7 @@ fn test_pragma_repeated_operation_mapping() {
 }
 
 /// Test that non-included gates return an error
-#[test_case(Operation::from(Bogoliubov::new(
-    0,
-    1,
-    CalculatorFloat::from(0.2),
-    CalculatorFloat::from(0.2),
-)); "Bogoliubov")]
+#[test_case(Operation::from(ControlledSWAP::new(0, 1, 2)); "ControlledSWAP")]
 fn test_call_operation_error(operation: Operation) {
     assert_eq!(
         call_operation(
@@ -967,9 +1775,96 @@ fn test_call_circuit() {
 #[test_case(Operation::from(PMInteraction::new(0, 1, "alpha/3*beta".into())), QasmVersion::V3point0(Qasm3Dialect::Braket), "pmint(alpha/3*beta) q[0],q[1];"; "PMInteraction3_0")]
 #[test_case(Operation::from(GivensRotation::new(0, 1, 1.445.into(), "beta".into())), QasmVersion::V3point0(Qasm3Dialect::Braket), "gvnsrot(1.445e0,beta) q[0],q[1];"; "GivensRotation3_0")]
 #[test_case(Operation::from(GivensRotationLittleEndian::new(0, 1, "beta".into(), 1.445.into())), QasmVersion::V3point0(Qasm3Dialect::Braket), "gvnsrotle(beta,1.445e0) q[0],q[1];"; "GivensRotationLE3_0")]
+#[test_case(Operation::from(ControlledControlledPhaseShift::new(0, 1, 2, "theta".into())), QasmVersion::V3point0(Qasm3Dialect::Vanilla), "ccp(theta) q[0],q[1],q[2];"; "ControlledControlledPhaseShift3_0")]
+#[test_case(Operation::from(ControlledControlledPhaseShift::new(0, 1, 2, "theta".into())), QasmVersion::V3point0(Qasm3Dialect::Braket), "phaseshift((theta / 4e0)) q[1];\ncnot q[1],q[2];\nphaseshift((theta / -4e0)) q[2];\ncnot q[1],q[2];\nphaseshift((theta / 4e0)) q[2];\ncnot q[0],q[1];\nphaseshift((theta / -4e0)) q[1];\ncnot q[1],q[2];\nphaseshift((theta / 4e0)) q[2];\ncnot q[1],q[2];\nphaseshift((theta / -4e0)) q[2];\ncnot q[0],q[1];\nphaseshift((theta / 4e0)) q[0];\ncnot q[0],q[2];\nphaseshift((theta / -4e0)) q[2];\ncnot q[0],q[2];\nphaseshift((theta / 4e0)) q[2];"; "ControlledControlledPhaseShift3_0Braket")]
 fn test_parametric_gates(operation: Operation, qasm_version: QasmVersion, converted: &str) {
     assert_eq!(
         call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
         converted
     );
 }
+
+/// Test that nesting PragmaLoop and PragmaConditional beyond the maximum recursion depth returns an error
+#[test_case(QasmVersion::V2point0(Qasm2Dialect::Vanilla); "2.0")]
+#[test_case(QasmVersion::V3point0(Qasm3Dialect::Vanilla); "3.0")]
+fn test_max_recursion_depth(qasm_version: QasmVersion) {
+    let mut circuit = Circuit::new() + PauliX::new(0);
+    for level in 0..4 {
+        let operation = if level % 2 == 0 {
+            Operation::from(PragmaLoop::new(2.0.into(), circuit))
+        } else {
+            Operation::from(PragmaConditional::new("ro".to_string(), 0, circuit))
+        };
+        circuit = Circuit::new();
+        circuit.add_operation(operation);
+    }
+    let operation = circuit.get(0).unwrap().clone();
+
+    let options = QasmOptions {
+        max_recursion_depth: 4,
+        ..Default::default()
+    };
+    assert!(
+        call_operation_with_options(&operation, "q", qasm_version, &mut None, &options).is_ok()
+    );
+
+    let options = QasmOptions {
+        max_recursion_depth: 3,
+        ..Default::default()
+    };
+    assert_eq!(
+        call_operation_with_options(&operation, "q", qasm_version, &mut None, &options),
+        Err(RoqoqoBackendError::GenericError {
+            msg: "Maximum recursion depth of 3 exceeded while translating nested operations"
+                .to_string()
+        })
+    );
+}
+
+/// Test that measurements inside a PragmaConditional return an error
+#[test_case(Operation::from(MeasureQubit::new(0, "ro".to_string(), 0)), QasmVersion::V2point0(Qasm2Dialect::Vanilla); "MeasureQubit 2.0")]
+#[test_case(Operation::from(MeasureQubit::new(0, "ro".to_string(), 0)), QasmVersion::V3point0(Qasm3Dialect::Vanilla); "MeasureQubit 3.0")]
+#[test_case(Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 10, None)), QasmVersion::V2point0(Qasm2Dialect::Vanilla); "PragmaRepeatedMeasurement 2.0")]
+#[test_case(Operation::from(PragmaRepeatedMeasurement::new("ro".to_string(), 10, None)), QasmVersion::V3point0(Qasm3Dialect::Roqoqo); "PragmaRepeatedMeasurement 3.0")]
+fn test_conditional_measurement(measurement: Operation, qasm_version: QasmVersion) {
+    let mut circuit = Circuit::new() + Hadamard::new(0);
+    circuit.add_operation(measurement.clone());
+    let pcond = PragmaConditional::new("c".to_string(), 0, circuit);
+    assert_eq!(
+        call_operation(&Operation::from(pcond), "q", qasm_version, &mut None),
+        Err(RoqoqoBackendError::GenericError {
+            msg: format!(
+                "Conditional measurements are not supported in OpenQASM, found {} inside PragmaConditional",
+                measurement.hqslang()
+            )
+        })
+    );
+}
+
+/// Test that the reordering of PragmaStartDecompositionBlock is emitted as a comment in vanilla OpenQASM 3.0
+#[test]
+fn test_decomposition_block_reordering_comment() {
+    let operation = Operation::from(PragmaStartDecompositionBlock::new(
+        vec![0, 1, 2],
+        HashMap::from([(1, 0), (0, 2), (2, 1)]),
+    ));
+    assert_eq!(
+        call_operation(
+            &operation,
+            "q",
+            QasmVersion::V3point0(Qasm3Dialect::Vanilla),
+            &mut None
+        )
+        .unwrap(),
+        "// reorder: 0->2, 1->0, 2->1"
+    );
+    for qasm_version in [
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        QasmVersion::V3point0(Qasm3Dialect::Braket),
+    ] {
+        assert_eq!(
+            call_operation(&operation, "q", qasm_version, &mut None).unwrap(),
+            ""
+        );
+    }
+}
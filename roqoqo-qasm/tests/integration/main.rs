@@ -21,3 +21,6 @@ mod parser;
 
 #[cfg(test)]
 mod variable_gatherer;
+
+#[cfg(test)]
+mod gate_definition_unitary;
@@ -13,6 +13,7 @@
 //! Testing the roqoqo-qasm Parser
 
 use std::convert::TryInto;
+use std::f64::consts::PI;
 use std::fs::File;
 
 use num_complex::Complex64;
@@ -20,7 +21,12 @@ use qoqo_calculator::CalculatorFloat;
 use roqoqo::operations::*;
 use roqoqo::Circuit;
 
-use roqoqo_qasm::file_to_circuit;
+use roqoqo_qasm::{
+    call_operation, file_to_circuit, file_to_circuit_with_options, string_to_circuit,
+    string_to_circuit_with_docstrings, string_to_circuit_with_options, Backend, ParserOptions,
+    Qasm2Dialect, QasmVersion,
+};
+use test_case::test_case;
 
 // helper function
 fn is_close(a: Complex64, b: Complex64) -> bool {
@@ -66,6 +72,8 @@ fn test_qoqo_gates() {
     circuit_qoqo += PhaseShiftState1::new(2, 0.6.into());
     circuit_qoqo += SqrtPauliX::new(1);
     circuit_qoqo += InvSqrtPauliX::new(0);
+    circuit_qoqo += SqrtPauliY::new(2);
+    circuit_qoqo += InvSqrtPauliY::new(1);
     circuit_qoqo += CNOT::new(0, 1);
     circuit_qoqo += MolmerSorensenXX::new(1, 2);
     circuit_qoqo += VariableMSXX::new(0, 2, 0.7.into());
@@ -91,12 +99,50 @@ fn test_qoqo_gates() {
     circuit_qoqo += PhaseShiftedControlledZ::new(0, 2, 0.3.into());
     circuit_qoqo += PhaseShiftedControlledPhase::new(0, 1, 1.0.into(), 1.9.into());
     circuit_qoqo += Toffoli::new(0, 2, 1);
+    circuit_qoqo += Toffoli::new(1, 2, 0);
     circuit_qoqo += ControlledControlledPauliZ::new(2, 1, 0);
     circuit_qoqo += ControlledControlledPhaseShift::new(1, 0, 2, 0.3.into());
     circuit_qoqo += PragmaActiveReset::new(1);
     circuit_qoqo += MeasureQubit::new(0, "c".into(), 0);
 
     assert_eq!(circuit_from_file, circuit_qoqo);
+
+    // The exported sy and sydg gates are imported back
+    let circuit_sy = Circuit::new() + SqrtPauliY::new(2) + InvSqrtPauliY::new(1);
+    let backend = Backend::new(None, None).unwrap();
+    let qasm_sy = backend.circuit_to_qasm_str(&circuit_sy).unwrap();
+    assert_eq!(string_to_circuit(&qasm_sy).unwrap(), circuit_sy);
+}
+
+/// Test that the controlled phase gate is imported back under both of its names
+#[test_case(false, "cp(5e-1) q[0],q[1];"; "cp")]
+#[test_case(true, "cu1(5e-1) q[0],q[1];"; "cu1")]
+fn test_controlled_phase_names(cu1_controlled_phase: bool, call: &str) {
+    let backend = Backend::new(None, None)
+        .unwrap()
+        .with_cu1_controlled_phase(cu1_controlled_phase);
+    let circuit = Circuit::new() + ControlledPhaseShift::new(0, 1, 0.5.into());
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains(call));
+    assert_eq!(string_to_circuit(&qasm).unwrap(), circuit);
+}
+
+/// Test that InvSqrtPauliX is imported back from `inv @ sx` and from `sxdg`
+#[test_case("2.0", "sxdg q[0];"; "2.0")]
+#[test_case("3.0", "inv @ sx q[0];"; "3.0")]
+#[test_case("3.0Roqoqo", "sxdg q[0];"; "3.0Roqoqo")]
+fn test_inverse_sqrt_pauli_x(qasm_version: &str, call: &str) {
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let circuit = Circuit::new() + SqrtPauliX::new(1) + InvSqrtPauliX::new(0);
+
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains(call));
+    if qasm_version == "3.0" {
+        assert!(!qasm.contains("gate sxdg"));
+        assert_eq!(qasm.matches("gate sx a {").count(), 1);
+    }
+    assert_eq!(string_to_circuit(&qasm).unwrap(), circuit);
 }
 
 /// Test errors
@@ -139,6 +185,58 @@ fn test_single_qubit_gate() {
     assert!(is_close(sq.beta_i().float().unwrap().into(), (-1.0).into()));
 }
 
+/// Test that the OpenQASM 3.0 u and U gates are imported like u3
+#[test]
+fn test_u_gate() {
+    let file = File::open(std::env::current_dir().unwrap().join("tests/u_gate.qasm")).unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+    assert_eq!(circuit_from_file.len(), 3);
+
+    let u3: SingleQubitGateOperation = circuit_from_file.get(2).unwrap().try_into().unwrap();
+    for (index, qubit) in [(0, 0), (1, 1)] {
+        let sq: SingleQubitGateOperation =
+            circuit_from_file.get(index).unwrap().try_into().unwrap();
+        assert_eq!(*sq.qubit(), qubit);
+        assert_eq!(sq.alpha_r(), u3.alpha_r());
+        assert_eq!(sq.alpha_i(), u3.alpha_i());
+        assert_eq!(sq.beta_r(), u3.beta_r());
+        assert_eq!(sq.beta_i(), u3.beta_i());
+    }
+}
+
+/// Test that the sdg and tdg adjoint gates are imported as the inverse S and T gates
+#[test]
+fn test_adjoint_gates() {
+    let file = File::open(
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/adjoint_gates.qasm"),
+    )
+    .unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+    assert_eq!(
+        circuit_from_file,
+        Circuit::new() + InvSGate::new(0) + InvTGate::new(1)
+    );
+
+    // The adjoint gates are phase rotations by -pi/2 and -pi/4
+    for (operation, angle) in [
+        (circuit_from_file.get(0).unwrap(), -PI / 2.0),
+        (circuit_from_file.get(1).unwrap(), -PI / 4.0),
+    ] {
+        let gate: SingleQubitGateOperation = operation.try_into().unwrap();
+        let phase_shift = PhaseShiftState1::new(0, angle.into());
+        let matrix = gate.unitary_matrix().unwrap();
+        let expected = phase_shift.unitary_matrix().unwrap();
+        assert!(matrix
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, b)| is_close(*a, *b)));
+    }
+}
+
 #[test]
 fn test_comments() {
     let file = File::open(std::env::current_dir().unwrap().join("tests/comments.qasm")).unwrap();
@@ -187,6 +285,84 @@ fn test_gate_definitions() {
     assert_eq!(circuit_from_file, circuit_qoqo);
 }
 
+/// Test the parameters of parametric custom gate calls
+#[test]
+fn test_custom_gate_parameters() {
+    let file = File::open(
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/custom_param.qasm"),
+    )
+    .unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo.add_operation(GateDefinition::new(
+        Circuit::new() + RotateX::new(0, CalculatorFloat::from("theta")),
+        "mygate".to_owned(),
+        vec![0],
+        vec!["theta".to_owned()],
+    ));
+    // Numeric parameters are evaluated, symbolic parameters are kept
+    circuit_qoqo.add_operation(GateDefinition::new(
+        Circuit::new()
+            + CallDefinedGate::new(
+                "mygate".to_owned(),
+                vec![0],
+                vec![CalculatorFloat::from("phi/2")],
+            )
+            + CallDefinedGate::new(
+                "mygate".to_owned(),
+                vec![1],
+                vec![CalculatorFloat::FRAC_PI_2],
+            ),
+        "outer".to_owned(),
+        vec![0, 1],
+        vec!["phi".to_owned()],
+    ));
+    circuit_qoqo.add_operation(CallDefinedGate::new(
+        "mygate".to_owned(),
+        vec![0],
+        vec![CalculatorFloat::FRAC_PI_2],
+    ));
+    circuit_qoqo.add_operation(CallDefinedGate::new(
+        "outer".to_owned(),
+        vec![0, 1],
+        vec![CalculatorFloat::PI],
+    ));
+
+    assert_eq!(circuit_from_file, circuit_qoqo);
+}
+
+/// Test that gate definitions built on the U and CX built-ins are imported with their body
+#[test]
+fn test_gate_definition_builtins() {
+    let input = "OPENQASM 2.0;\n\ngate mygate a,b { U(pi,0,pi) a; CX a,b; }\n\nqreg q[2];\n\nmygate q[1],q[0];\nCX q[0],q[1];\n";
+    let circuit = string_to_circuit(input).unwrap();
+    assert_eq!(circuit.len(), 3);
+
+    let definition: GateDefinition = circuit.get(0).unwrap().clone().try_into().unwrap();
+    assert_eq!(definition.name(), "mygate");
+    assert_eq!(definition.circuit().len(), 2);
+    let u: SingleQubitGateOperation = definition.circuit().get(0).unwrap().try_into().unwrap();
+    assert_eq!(*u.qubit(), 0);
+    assert_eq!(
+        definition.circuit().get(1),
+        Some(&Operation::from(CNOT::new(0, 1)))
+    );
+
+    assert_eq!(
+        circuit.get(1),
+        Some(&Operation::from(CallDefinedGate::new(
+            "mygate".to_owned(),
+            vec![1, 0],
+            vec![]
+        )))
+    );
+    assert_eq!(circuit.get(2), Some(&Operation::from(CNOT::new(0, 1))));
+}
+
 #[test]
 fn test_include_line_skip() {
     let file = File::open(std::env::current_dir().unwrap().join("tests/include.qasm")).unwrap();
@@ -204,6 +380,163 @@ fn test_include_line_skip() {
     assert_eq!(circuit_from_file, circuit_qoqo);
 }
 
+/// Test that barriers are skipped by default and imported as PragmaStopParallelBlock on request
+#[test]
+fn test_barrier() {
+    let path = std::env::current_dir().unwrap().join("tests/barrier.qasm");
+
+    let circuit_from_file = file_to_circuit(File::open(&path).unwrap()).unwrap();
+
+    let mut circuit_skipped = Circuit::new();
+    circuit_skipped += DefinitionBit::new("c".into(), 2, true);
+    circuit_skipped += Hadamard::new(0);
+    circuit_skipped += CNOT::new(0, 1);
+    circuit_skipped += MeasureQubit::new(0, "c".into(), 0);
+
+    assert_eq!(circuit_from_file, circuit_skipped);
+
+    let options = ParserOptions {
+        keep_barriers: true,
+        ..Default::default()
+    };
+    let circuit_from_file =
+        file_to_circuit_with_options(File::open(&path).unwrap(), &options).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("c".into(), 2, true);
+    circuit_qoqo += Hadamard::new(0);
+    circuit_qoqo += PragmaStopParallelBlock::new(vec![0, 1], 0.0.into());
+    circuit_qoqo += CNOT::new(0, 1);
+    circuit_qoqo += PragmaStopParallelBlock::new(vec![0, 1, 2], 0.0.into());
+    circuit_qoqo += MeasureQubit::new(0, "c".into(), 0);
+
+    assert_eq!(circuit_from_file, circuit_qoqo);
+
+    let result = string_to_circuit_with_options("OPENQASM 2.0;\nbarrier r;", &options);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Barrier on the undeclared qubit register r"));
+}
+
+/// Test that measurements of the whole register are imported as PragmaRepeatedMeasurement
+#[test]
+fn test_measure_all() {
+    let file = File::open(
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/measure_all.qasm"),
+    )
+    .unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("ro".into(), 2, true);
+    circuit_qoqo += Hadamard::new(0);
+    circuit_qoqo += CNOT::new(0, 1);
+    circuit_qoqo += PragmaRepeatedMeasurement::new("ro".into(), 1, None);
+
+    assert_eq!(circuit_from_file, circuit_qoqo);
+}
+
+/// Test that conditionals on single bits are imported as PragmaConditional
+#[test]
+fn test_conditional() {
+    let file = File::open(
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/conditional.qasm"),
+    )
+    .unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("c".into(), 2, true);
+    circuit_qoqo += MeasureQubit::new(0, "c".into(), 0);
+    circuit_qoqo += PragmaConditional::new("c".into(), 0, Circuit::new() + PauliX::new(1));
+    circuit_qoqo +=
+        PragmaConditional::new("c".into(), 1, Circuit::new() + RotateX::new(0, 0.5.into()));
+
+    assert_eq!(circuit_from_file, circuit_qoqo);
+}
+
+/// Test that exported PragmaConditional operations are imported back
+#[test]
+fn test_conditional_round_trip() {
+    let conditional = PragmaConditional::new(
+        "c".into(),
+        0,
+        Circuit::new() + PauliX::new(1) + RotateZ::new(0, 0.5.into()),
+    );
+    let qasm_conditional = call_operation(
+        &Operation::from(conditional),
+        "q",
+        QasmVersion::V2point0(Qasm2Dialect::Vanilla),
+        &mut None,
+    )
+    .unwrap();
+    let path = std::env::temp_dir().join("conditional_round_trip.qasm");
+    std::fs::write(
+        &path,
+        format!(
+            "OPENQASM 2.0;\nqreg q[2];\ncreg c[1];\n{}\n",
+            qasm_conditional
+        ),
+    )
+    .unwrap();
+
+    let circuit_from_file = file_to_circuit(File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Every operation of the conditional circuit is exported and imported with its own condition
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("c".into(), 1, true);
+    circuit_qoqo += PragmaConditional::new("c".into(), 0, Circuit::new() + PauliX::new(1));
+    circuit_qoqo +=
+        PragmaConditional::new("c".into(), 0, Circuit::new() + RotateZ::new(0, 0.5.into()));
+    assert_eq!(circuit_from_file, circuit_qoqo);
+}
+
+/// Test that conditionals qoqo cannot represent return an error
+#[test_case("if(c==3) x q[1];", "Conditions on the whole classical register c are not supported"; "whole register")]
+#[test_case("if(c[0]==0) x q[1];", "Only conditions on a bit being set are supported, found c[0]==0"; "bit not set")]
+fn test_conditional_errors(conditional: &str, message: &str) {
+    let input = format!("OPENQASM 2.0;\nqreg q[2];\ncreg c[2];\n{conditional}");
+
+    let result = string_to_circuit(&input);
+
+    assert!(result.unwrap_err().to_string().contains(message));
+}
+
+/// Test the import of a gate definition redefining a built-in gate
+#[test]
+fn test_builtin_redefinition() {
+    let input = "OPENQASM 2.0;\nqreg q[1];\ngate x a { y a; z a; }\nx q[0];";
+
+    let mut circuit_ignored = Circuit::new();
+    circuit_ignored += PauliX::new(0);
+    assert_eq!(string_to_circuit(input).unwrap(), circuit_ignored);
+
+    let options = ParserOptions {
+        honor_builtin_redefinitions: true,
+        ..Default::default()
+    };
+    let mut circuit_honored = Circuit::new();
+    circuit_honored += GateDefinition::new(
+        Circuit::new() + PauliY::new(0) + PauliZ::new(0),
+        "x".to_owned(),
+        vec![0],
+        vec![],
+    );
+    circuit_honored += CallDefinedGate::new("x".to_owned(), vec![0], vec![]);
+    assert_eq!(
+        string_to_circuit_with_options(input, &options).unwrap(),
+        circuit_honored
+    );
+}
+
 #[allow(clippy::approx_constant)]
 #[test]
 fn test_symbols() {
@@ -240,3 +573,231 @@ fn test_acceptance_test() {
     let file = File::open(path).unwrap();
     assert!(file_to_circuit(file).is_ok());
 }
+
+/// Test that gate parameters referencing classical bits return an error
+#[test_case("rz(c[0] * pi) q[0];", "found classical bit c[0] in parameter c[0] * pi of gate rz"; "product")]
+#[test_case("rx(-(c[1])) q[0];", "found classical bit c[1] in parameter -(c[1]) of gate rx"; "nested")]
+fn test_classical_parameter_error(gate: &str, message: &str) {
+    let input = format!("OPENQASM 2.0;\nqreg q[1];\ncreg c[2];\n{gate}");
+
+    let result = string_to_circuit(&input);
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("Dynamic classical-parameterized gates are not supported"));
+    assert!(error.contains(message));
+}
+
+/// Test that files with CRLF and lone CR line endings are parsed like files with LF line endings
+#[test]
+fn test_line_endings() {
+    let file = File::open(std::env::current_dir().unwrap().join("tests/crlf.qasm")).unwrap();
+    let circuit_crlf = file_to_circuit(file).unwrap();
+    let file = File::open(
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/conditional.qasm"),
+    )
+    .unwrap();
+    let circuit_lf = file_to_circuit(file).unwrap();
+    assert_eq!(circuit_crlf, circuit_lf);
+
+    let input =
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\n// comment\nh q[0];\ncx q[0],q[1];";
+    let circuit_lf = string_to_circuit(input).unwrap();
+    assert_eq!(
+        string_to_circuit(&input.replace('\n', "\r\n")).unwrap(),
+        circuit_lf
+    );
+    assert_eq!(
+        string_to_circuit(&input.replace('\n', "\r")).unwrap(),
+        circuit_lf
+    );
+}
+
+/// Test the import of a file without header when a version is assumed
+#[test]
+fn test_assume_version() {
+    let path = std::env::current_dir()
+        .unwrap()
+        .join("tests/headerless.qasm");
+    assert!(file_to_circuit(File::open(&path).unwrap()).is_err());
+
+    let options = ParserOptions {
+        assume_version: Some(QasmVersion::V2point0(Qasm2Dialect::Vanilla)),
+        ..Default::default()
+    };
+    let circuit_from_file =
+        file_to_circuit_with_options(File::open(&path).unwrap(), &options).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += Hadamard::new(0);
+    circuit_qoqo += CNOT::new(0, 1);
+    assert_eq!(circuit_from_file, circuit_qoqo);
+
+    // Input with a header is not changed by the assumed version
+    assert_eq!(
+        string_to_circuit_with_options(
+            "OPENQASM 2.0;\nqreg q[2];\nh q[0];\ncx q[0],q[1];",
+            &options
+        )
+        .unwrap(),
+        circuit_qoqo
+    );
+}
+
+/// Test the capture of the comments documenting a gate definition
+#[test]
+fn test_gate_docstrings() {
+    let input = "OPENQASM 2.0;\nqreg q[2];\n// Unrelated comment\n\n// Entangles two qubits\n// with a Hadamard and a CNOT\ngate bell a,b { h a; cx a,b; }\ngate plain a { x a; }\nbell q[0],q[1];";
+
+    let (circuit, docstrings) =
+        string_to_circuit_with_docstrings(input, &ParserOptions::default()).unwrap();
+    assert_eq!(circuit, string_to_circuit(input).unwrap());
+    assert!(docstrings.is_empty());
+
+    let options = ParserOptions {
+        capture_gate_docstrings: true,
+        ..Default::default()
+    };
+    let (circuit_documented, docstrings) =
+        string_to_circuit_with_docstrings(input, &options).unwrap();
+    assert_eq!(circuit_documented, circuit);
+    assert_eq!(docstrings.len(), 1);
+    assert_eq!(
+        docstrings.get("bell").unwrap(),
+        "Entangles two qubits\nwith a Hadamard and a CNOT"
+    );
+}
+
+/// Test basic OpenQASM 3.0 file and the round trip through the Backend
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Roqoqo"; "3.0Roqoqo")]
+fn test_basic_file_3(qasm_version: &str) {
+    let file = File::open(std::env::current_dir().unwrap().join("tests/input3.qasm")).unwrap();
+
+    let circuit_from_file = file_to_circuit(file).unwrap();
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("c".into(), 2, true);
+    circuit_qoqo += PauliX::new(0);
+    circuit_qoqo += Hadamard::new(1);
+    circuit_qoqo += RotateX::new(2, 2.3.into());
+    circuit_qoqo += CNOT::new(0, 1);
+    circuit_qoqo += MeasureQubit::new(0, "c".into(), 0);
+    circuit_qoqo += MeasureQubit::new(1, "c".into(), 1);
+
+    assert_eq!(circuit_from_file, circuit_qoqo);
+
+    let backend = Backend::new(Some("q".to_string()), Some(qasm_version.to_string())).unwrap();
+    let qasm_string = backend.circuit_to_qasm_str(&circuit_from_file).unwrap();
+    assert_eq!(string_to_circuit(&qasm_string).unwrap(), circuit_qoqo);
+}
+
+/// Test the OpenQASM 3.0 declarations and the ctrl @ modifier
+#[test]
+fn test_qasm3_statements() {
+    let input = "OPENQASM 3.0;\nqubit[2] q;\nbit[1] c;\nctrl @ z q[0],q[1];\nc[0] = measure q[1];";
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += DefinitionBit::new("c".into(), 1, false);
+    circuit_qoqo += ControlledPauliZ::new(0, 1);
+    circuit_qoqo += MeasureQubit::new(1, "c".into(), 0);
+    assert_eq!(string_to_circuit(input).unwrap(), circuit_qoqo);
+}
+
+/// Test that the OpenQASM 3.0 output of the Backend is imported back, including inputs and blocks
+#[test_case("3.0"; "3.0")]
+#[test_case("3.0Qiskit"; "3.0Qiskit")]
+fn test_qasm3_round_trip(qasm_version: &str) {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".into(), 2, true);
+    circuit += RotateX::new(0, "theta".into());
+    circuit += RotateZ::new(1, "theta*2".into());
+    circuit += MeasureQubit::new(0, "ro".into(), 0);
+    circuit += PragmaConditional::new(
+        "ro".into(),
+        0,
+        Circuit::new() + PauliX::new(0) + RotateZ::new(1, 0.5.into()),
+    );
+    circuit += PragmaLoop::new(
+        2.0.into(),
+        Circuit::new() + Hadamard::new(1) + RotateZ::new(0, 0.5.into()),
+    );
+    circuit += Hadamard::new(1);
+    circuit += MeasureQubit::new(1, "ro".into(), 1);
+
+    let backend = Backend::new(None, Some(qasm_version.to_string())).unwrap();
+    let qasm = backend.circuit_to_qasm_str(&circuit).unwrap();
+    assert!(qasm.contains("input angle[32] theta;\n"));
+    assert!(qasm.contains("if(ro[0]==1) {\n"));
+    assert!(qasm.contains("for uint i in [0:1] {\n"));
+    assert_eq!(string_to_circuit(&qasm).unwrap(), circuit);
+}
+
+/// Test the import of the OpenQASM 3.0 input declarations and global phases
+#[test]
+fn test_qasm3_inputs_and_global_phase() {
+    let input = "OPENQASM 3.0;\ninput angle[32] theta;\ninput float[64] phi;\nqubit[1] q;\nx q[0];\ngphase theta;\ngphase(5e-1);";
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += PauliX::new(0);
+    circuit_qoqo += PragmaGlobalPhase::new("theta".into());
+    circuit_qoqo += PragmaGlobalPhase::new(0.5.into());
+    assert_eq!(string_to_circuit(input).unwrap(), circuit_qoqo);
+}
+
+/// Test that statements the parser does not support return an error instead of being skipped
+#[test_case("OPENQASM 3.0;\nqubit[1] q;\nbox {\n    x q[0];\n}\nh q[0];"; "box statement")]
+#[test_case("OPENQASM 3.0;\nqubit[1] q;\nbit[1] c;\nif(c[0]==1) {\n    x q[0];\n} else {\n    h q[0];\n}"; "else branch")]
+#[test_case("OPENQASM 3.0;\nqubit[2] q;\nctrl @ ctrl @ x q[0],q[1],q[2];"; "two modifiers")]
+#[test_case("OPENQASM 2.0;\nqreg q[2];\nctrl @ x q[0],q[1];"; "modifier in 2.0")]
+fn test_unsupported_statements(input: &str) {
+    assert!(string_to_circuit(input).is_err());
+}
+
+/// Test that the ctrl @ and inv @ modifiers are imported as the matching qoqo operations
+#[test_case("inv @ rx(0.3) q[0];", Operation::from(RotateX::new(0, (-0.3).into())); "inv rx")]
+#[test_case("inv @ s q[0];", Operation::from(InvSGate::new(0)); "inv s")]
+#[test_case("inv @ sdg q[0];", Operation::from(SGate::new(0)); "inv sdg")]
+#[test_case("inv @ h q[0];", Operation::from(Hadamard::new(0)); "inv h")]
+#[test_case("ctrl @ x q[0],q[1];", Operation::from(CNOT::new(0, 1)); "ctrl x")]
+#[test_case("ctrl @ p(0.5) q[0],q[1];", Operation::from(ControlledPhaseShift::new(0, 1, 0.5.into())); "ctrl p")]
+#[test_case("ctrl @ cx q[0],q[1],q[2];", Operation::from(Toffoli::new(0, 1, 2)); "ctrl cx")]
+#[test_case("ctrl @ swap q[0],q[1],q[2];", Operation::from(ControlledSWAP::new(0, 1, 2)); "ctrl swap")]
+fn test_gate_modifiers(gate: &str, operation: Operation) {
+    let input = format!("OPENQASM 3.0;\nqubit[3] q;\n{gate}");
+
+    assert_eq!(
+        string_to_circuit(&input).unwrap(),
+        Circuit::new() + operation
+    );
+}
+
+/// Test that modifiers without a matching qoqo operation return an error
+#[test_case("ctrl @ rz(0.5) q[0],q[1];", "The ctrl @ modifier is not supported for the gate rz"; "ctrl rz")]
+#[test_case("inv @ u(0.1,0.2,0.3) q[0];", "The inv @ modifier is not supported for the gate u"; "inv u")]
+#[test_case("gate bell a,b { h a; ctrl @ h a,b; }", "The ctrl @ modifier is not supported for the gate h"; "in definition")]
+fn test_gate_modifier_errors(gate: &str, message: &str) {
+    let input = format!("OPENQASM 3.0;\nqubit[2] q;\n{gate}");
+
+    assert!(string_to_circuit(&input)
+        .unwrap_err()
+        .to_string()
+        .contains(message));
+}
+
+/// Test that modifiers in OpenQASM 3.0 gate definitions are imported
+#[test]
+fn test_gate_definition_modifier() {
+    let input = "OPENQASM 3.0;\nqubit[2] q;\ngate bell a,b { h a; ctrl @ x a,b; }\nbell q[0],q[1];";
+
+    let mut circuit_qoqo = Circuit::new();
+    circuit_qoqo += GateDefinition::new(
+        Circuit::new() + Hadamard::new(0) + CNOT::new(0, 1),
+        "bell".to_owned(),
+        vec![0, 1],
+        vec![],
+    );
+    circuit_qoqo += CallDefinedGate::new("bell".to_owned(), vec![0, 1], vec![]);
+    assert_eq!(string_to_circuit(input).unwrap(), circuit_qoqo);
+}
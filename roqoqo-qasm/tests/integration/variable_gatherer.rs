@@ -1,6 +1,6 @@
 use qoqo_calculator::CalculatorFloat;
 
-use roqoqo_qasm::VariableGatherer;
+use roqoqo_qasm::{VariableGatherer, VariableKind};
 
 use test_case::test_case;
 
@@ -36,6 +36,45 @@ fn test_multiple_cf() {
     assert!(cp.variables.contains("c"));
 }
 
+/// Test that the built-in constants are not gathered as variables
+#[test_case("pi/2", &[]; "pi")]
+#[test_case("tau*euler", &[]; "tau euler")]
+#[test_case("alpha", &["alpha"]; "alpha")]
+#[test_case("alpha*pi", &["alpha"]; "alpha pi")]
+fn test_builtin_constants(expression: &str, variables: &[&str]) {
+    let mut cp = VariableGatherer::new();
+    cp.parse(&CalculatorFloat::from(expression).to_string())
+        .unwrap();
+
+    assert_eq!(cp.variables.len(), variables.len());
+    for variable in variables {
+        assert!(cp.variables.contains(*variable));
+    }
+}
+
+/// Test the classification of variables used as angles and as general parameters
+#[test]
+fn test_variable_kinds() {
+    let mut cp = VariableGatherer::new();
+    cp.parse("2*alpha").unwrap();
+    cp.parse_with_kind("beta+gamma", VariableKind::Float)
+        .unwrap();
+    cp.parse("gamma/2").unwrap();
+    cp.parse("delta").unwrap();
+    cp.parse_with_kind("delta", VariableKind::Float).unwrap();
+
+    assert_eq!(cp.variables.len(), 4);
+    assert_eq!(cp.variable_kind("alpha"), Some(VariableKind::Angle));
+    assert_eq!(cp.variable_kind("beta"), Some(VariableKind::Float));
+    // A variable used in both contexts is a float, independent of the order
+    assert_eq!(cp.variable_kind("gamma"), Some(VariableKind::Float));
+    assert_eq!(cp.variable_kind("delta"), Some(VariableKind::Float));
+    assert_eq!(cp.variable_kind("epsilon"), None);
+
+    cp.register_variable_kind("alpha", VariableKind::Float);
+    assert_eq!(cp.variable_kind("alpha"), Some(VariableKind::Float));
+}
+
 /// Test non-supported mathematical functions
 #[test_case(CalculatorFloat::from("2*abs(a+1)"), "abs")]
 #[test_case(CalculatorFloat::from("2*cosh(a+1)"), "cosh")]
@@ -96,3 +135,43 @@ fn test_math_functions(cf: CalculatorFloat) {
 
     assert!(correct_parse.is_ok());
 }
+
+/// Test that log10, exp2 and cbrt are rejected by default and rewritten when not strict
+#[test_case("2*log10(a+1)", "2*(log(a+1)/log(10))"; "log10")]
+#[test_case("exp2(a)", "exp((a)*log(2))"; "exp2")]
+#[test_case("cbrt(a*(b+1))", "pow(a*(b+1),1.0/3.0)"; "cbrt")]
+#[test_case("sin(cbrt(log10(a)))+b", "sin(pow((log(a)/log(10)),1.0/3.0))+b"; "nested")]
+fn test_rewritten_functions(expression: &str, rewritten: &str) {
+    let mut cp = VariableGatherer::new();
+    assert!(cp.strict);
+    assert!(cp.parse(expression).is_err());
+    assert!(cp.parse_for_output(expression).is_err());
+
+    let mut cp = VariableGatherer::new_rewriting();
+    assert!(!cp.strict);
+    assert_eq!(cp.parse_for_output(expression).unwrap(), rewritten);
+    assert!(cp.variables.contains("a"));
+
+    // The rewritten expression only uses functions supported in OpenQASM 3.0
+    assert!(VariableGatherer::new().parse(rewritten).is_ok());
+}
+
+/// Test that functions that can not be rewritten are still rejected when not strict
+#[test]
+fn test_rewriting_keeps_other_errors() {
+    let mut cp = VariableGatherer::new_rewriting();
+    assert!(cp.parse("cosh(a)").is_err());
+    assert_eq!(cp.parse_for_output("sin(a)").unwrap(), "sin(a)");
+}
+
+/// Test that the permissive mode accepts functions not supported in OpenQASM 3.0
+#[test_case(CalculatorFloat::from("2*cosh(a+1)"))]
+#[test_case(CalculatorFloat::from("2*log10(a+1)"))]
+#[test_case(CalculatorFloat::from("2*atan2(a, 1)"))]
+fn test_math_functions_permissive(cf: CalculatorFloat) {
+    let mut cp = VariableGatherer::new_permissive();
+
+    cp.parse(&cf.to_string()).unwrap();
+
+    assert!(cp.variables.contains("a"));
+}
@@ -0,0 +1,9 @@
+OPENQASM 2.0;
+include "qelib1.inc";
+
+qreg qr[2];
+creg ro[2];
+
+h qr[0];
+cx qr[0],qr[1];
+measure qr -> ro;
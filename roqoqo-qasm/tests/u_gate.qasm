@@ -0,0 +1,8 @@
+OPENQASM 3.0;
+include "stdgates.inc";
+
+qubit[2] q;
+
+u(0.5,0.2,0.3) q[0];
+U(0.5,0.2,0.3) q[1];
+u3(0.5,0.2,0.3) q[0];